@@ -0,0 +1,279 @@
+use crate::protocol::{
+    ClientMessage, Envelope, Participant, Room, Seat, ServerMessage, PROTOCOL_VERSION,
+};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// What the client knows about the room, kept up to date from the server's messages.
+#[derive(Debug, Default)]
+pub struct App {
+    pub room: Option<Room>,
+    pub seat: Option<Seat>,
+    /// Index of the highlighted card in the deck.
+    pub selected: usize,
+    pub timer: Option<u64>,
+    pub auto_reveal: Option<u64>,
+    /// Last error or hint shown in the footer.
+    pub status: Option<String>,
+    pub quit: bool,
+    next_id: u64,
+}
+
+impl App {
+    pub fn me(&self) -> Option<&Participant> {
+        let seat = self.seat.as_ref()?;
+        self.room
+            .as_ref()?
+            .participants
+            .iter()
+            .find(|p| p.id == seat.id)
+    }
+
+    pub fn is_facilitator(&self) -> bool {
+        self.me().is_some_and(|me| me.facilitator)
+    }
+
+    pub fn can_vote(&self) -> bool {
+        self.me().is_some_and(|me| !me.observer)
+    }
+
+    pub fn apply(&mut self, message: ServerMessage) {
+        match message {
+            ServerMessage::Welcome { version, seat } => {
+                if version != PROTOCOL_VERSION {
+                    self.status = Some(format!("Server speaks protocol version {version}"));
+                }
+                self.seat = seat;
+            }
+            ServerMessage::Ack => {
+                self.status = None;
+            }
+            ServerMessage::Error { message } => {
+                self.status = Some(message);
+            }
+            ServerMessage::Snapshot { room } => {
+                self.room = Some(room);
+                self.auto_reveal = None;
+                self.select_own_card();
+            }
+            ServerMessage::ParticipantJoined { participant }
+            | ServerMessage::ParticipantUpdated { participant } => {
+                if let Some(room) = &mut self.room {
+                    match room
+                        .participants
+                        .iter_mut()
+                        .find(|p| p.id == participant.id)
+                    {
+                        Some(existing) => *existing = participant,
+                        None => {
+                            room.participants.push(participant);
+                            room.participants.sort_by(|a, b| a.name.cmp(&b.name));
+                        }
+                    }
+                }
+            }
+            ServerMessage::ParticipantLeft { id } => {
+                if let Some(room) = &mut self.room {
+                    room.participants.retain(|p| p.id != id);
+                }
+            }
+            ServerMessage::EstimatesDeleted => self.clear_estimates(),
+            ServerMessage::Statistics { statistics } => {
+                if let Some(room) = &mut self.room {
+                    room.statistics = Some(statistics);
+                }
+            }
+            ServerMessage::DeckChanged { deck } => {
+                if let Some(room) = &mut self.room {
+                    room.deck = deck;
+                }
+                self.selected = 0;
+                self.clear_estimates();
+            }
+            ServerMessage::StoryChanged { story } => {
+                if let Some(room) = &mut self.room {
+                    room.story = story;
+                }
+            }
+            ServerMessage::AutoRevealCountdown { seconds } => self.auto_reveal = Some(seconds),
+            ServerMessage::AutoRevealCancelled => self.auto_reveal = None,
+            ServerMessage::TimerStarted { seconds } | ServerMessage::TimerTick { seconds } => {
+                self.timer = Some(seconds);
+            }
+            ServerMessage::TimerExpired | ServerMessage::TimerStopped => self.timer = None,
+            ServerMessage::Notice { message } => self.status = Some(message),
+            ServerMessage::RoomClosed => self.status = Some("The room was closed.".to_string()),
+            ServerMessage::Unknown => {}
+        }
+    }
+
+    fn clear_estimates(&mut self) {
+        if let Some(room) = &mut self.room {
+            for p in room.participants.iter_mut() {
+                p.voted = false;
+                p.estimate = None;
+            }
+            room.statistics = None;
+        }
+    }
+
+    /// Highlights the card of the participant, e.g. after reconnecting mid round.
+    fn select_own_card(&mut self) {
+        let Some(room) = &self.room else {
+            return;
+        };
+        let own_card = self
+            .me()
+            .and_then(|me| me.estimate.as_ref())
+            .and_then(|estimate| room.deck.iter().position(|card| card == estimate));
+        self.selected = own_card.unwrap_or(self.selected.min(room.deck.len().saturating_sub(1)));
+    }
+
+    /// Handles a key press, returning the message to send to the server, if any.
+    pub fn on_key(&mut self, key: KeyEvent) -> Option<Envelope> {
+        let deck_len = self.room.as_ref().map_or(0, |room| room.deck.len());
+        let message = match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => {
+                self.quit = true;
+                return None;
+            }
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.quit = true;
+                return None;
+            }
+            KeyCode::Left => {
+                self.selected = self.selected.saturating_sub(1);
+                return None;
+            }
+            KeyCode::Right => {
+                self.selected = (self.selected + 1).min(deck_len.saturating_sub(1));
+                return None;
+            }
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                let card = self.room.as_ref()?.deck.get(self.selected)?.clone();
+                self.vote(card)?
+            }
+            KeyCode::Backspace | KeyCode::Delete => self.vote(String::new())?,
+            KeyCode::Char('r') => self.facilitate(ClientMessage::Reveal)?,
+            KeyCode::Char('h') => self.facilitate(ClientMessage::Hide)?,
+            KeyCode::Char('x') => self.facilitate(ClientMessage::Reset)?,
+            _ => return None,
+        };
+        self.next_id += 1;
+        Some(Envelope {
+            id: self.next_id,
+            message,
+        })
+    }
+
+    fn vote(&mut self, estimate: String) -> Option<ClientMessage> {
+        if !self.can_vote() {
+            self.status = Some("Watching only, you cannot vote".to_string());
+            return None;
+        }
+        Some(ClientMessage::Estimate { estimate })
+    }
+
+    fn facilitate(&mut self, message: ClientMessage) -> Option<ClientMessage> {
+        if !self.is_facilitator() {
+            self.status = Some("Only the facilitator can do this".to_string());
+            return None;
+        }
+        Some(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyEventKind;
+
+    fn participant(id: &str, name: &str, facilitator: bool) -> Participant {
+        Participant {
+            id: id.to_string(),
+            name: name.to_string(),
+            facilitator,
+            observer: false,
+            online: true,
+            voted: false,
+            estimate: None,
+        }
+    }
+
+    fn app() -> App {
+        let mut app = App::default();
+        app.apply(ServerMessage::Welcome {
+            version: 1,
+            seat: Some(Seat {
+                id: "b".to_string(),
+            }),
+        });
+        app.apply(ServerMessage::Snapshot {
+            room: Room {
+                room_id: "room1".to_string(),
+                revealed: false,
+                deck: vec!["1".to_string(), "2".to_string(), "3".to_string()],
+                participants: vec![
+                    participant("a", "Alice", true),
+                    participant("b", "Bob", false),
+                ],
+                story: None,
+                statistics: None,
+            },
+        });
+        app
+    }
+
+    fn press(code: KeyCode) -> KeyEvent {
+        KeyEvent::new_with_kind(code, KeyModifiers::NONE, KeyEventKind::Press)
+    }
+
+    #[test]
+    fn follows_participants() {
+        let mut app = app();
+        let mut alice = participant("a", "Alice", true);
+        alice.voted = true;
+        app.apply(ServerMessage::ParticipantUpdated { participant: alice });
+        app.apply(ServerMessage::ParticipantJoined {
+            participant: participant("c", "Carol", false),
+        });
+        app.apply(ServerMessage::ParticipantLeft {
+            id: "b".to_string(),
+        });
+
+        let room = app.room.as_ref().unwrap();
+        let names: Vec<&str> = room.participants.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["Alice", "Carol"]);
+        assert!(room.participants[0].voted);
+        assert!(app.me().is_none());
+
+        app.apply(ServerMessage::EstimatesDeleted);
+        assert!(!app.room.unwrap().participants[0].voted);
+    }
+
+    #[test]
+    fn votes_with_the_selected_card() {
+        let mut app = app();
+        assert!(app.on_key(press(KeyCode::Right)).is_none());
+        assert!(app.on_key(press(KeyCode::Right)).is_none());
+        assert!(app.on_key(press(KeyCode::Right)).is_none());
+        assert_eq!(app.selected, 2);
+
+        let envelope = app.on_key(press(KeyCode::Enter)).unwrap();
+        assert_eq!(envelope.id, 1);
+        assert!(matches!(
+            envelope.message,
+            ClientMessage::Estimate { estimate } if estimate == "3"
+        ));
+    }
+
+    #[test]
+    fn only_the_facilitator_reveals() {
+        let mut app = app();
+        assert!(app.on_key(press(KeyCode::Char('r'))).is_none());
+        assert!(app.status.is_some());
+
+        app.seat.as_mut().unwrap().id = "a".to_string();
+        let envelope = app.on_key(press(KeyCode::Char('r'))).unwrap();
+        assert!(matches!(envelope.message, ClientMessage::Reveal));
+    }
+}
@@ -0,0 +1,112 @@
+use crate::{
+    app::App,
+    protocol::{ClientMessage, ServerMessage, PROTOCOL_VERSION},
+};
+use anyhow::{anyhow, bail, Context};
+use crossterm::event::{Event, EventStream, KeyEventKind};
+use futures::{SinkExt, StreamExt};
+use ratatui::DefaultTerminal;
+use std::env;
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+mod app;
+mod protocol;
+mod ui;
+
+const USAGE: &str = "usage: scrum_poker_tui <room link> [--name <name>] [--observer] [--watch]";
+
+struct Args {
+    room_url: String,
+    name: Option<String>,
+    observer: bool,
+    /// Follow the room without joining it.
+    watch: bool,
+}
+
+fn parse_args() -> anyhow::Result<Args> {
+    let mut room_url = None;
+    let mut name = env::var("USER").ok();
+    let mut observer = false;
+    let mut watch = false;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--name" => name = Some(args.next().context(USAGE)?),
+            "--observer" => observer = true,
+            "--watch" => watch = true,
+            "-h" | "--help" => bail!(USAGE),
+            _ if room_url.is_none() => room_url = Some(arg),
+            _ => bail!(USAGE),
+        }
+    }
+    Ok(Args {
+        room_url: room_url.context(USAGE)?,
+        name,
+        observer,
+        watch,
+    })
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = parse_args()?;
+    let socket_url = protocol::socket_url(&args.room_url).map_err(|err| anyhow!(err))?;
+    let (mut socket, _) = connect_async(socket_url.as_str())
+        .await
+        .with_context(|| format!("Failed to connect to {socket_url}"))?;
+
+    let hello = ClientMessage::Hello {
+        version: PROTOCOL_VERSION,
+        name: (!args.watch).then_some(args.name).flatten(),
+        observer: args.observer,
+    };
+    socket
+        .send(Message::Text(serde_json::to_string(&hello)?))
+        .await?;
+
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, socket).await;
+    ratatui::restore();
+    result
+}
+
+async fn run<S>(terminal: &mut DefaultTerminal, socket: S) -> anyhow::Result<()>
+where
+    S: StreamExt<Item = Result<Message, tokio_tungstenite::tungstenite::Error>>
+        + SinkExt<Message, Error = tokio_tungstenite::tungstenite::Error>
+        + Unpin,
+{
+    let (mut sink, mut stream) = socket.split();
+    let mut keys = EventStream::new();
+    let mut app = App::default();
+
+    while !app.quit {
+        terminal.draw(|frame| ui::draw(frame, &app))?;
+        tokio::select! {
+            message = stream.next() => match message {
+                Some(Ok(Message::Text(text))) => match serde_json::from_str::<ServerMessage>(&text) {
+                    Ok(message) => app.apply(message),
+                    Err(err) => app.status = Some(format!("Unreadable message from server: {err}")),
+                },
+                Some(Ok(Message::Close(_))) | None => {
+                    let reason = app.status.take().unwrap_or_default();
+                    bail!("The server closed the connection. {reason}");
+                }
+                Some(Ok(_)) => {}
+                Some(Err(err)) => return Err(err.into()),
+            },
+            event = keys.next() => match event {
+                Some(Ok(Event::Key(key))) if key.kind == KeyEventKind::Press => {
+                    if let Some(envelope) = app.on_key(key) {
+                        sink.send(Message::Text(serde_json::to_string(&envelope)?)).await?;
+                    }
+                }
+                Some(Ok(_)) => {}
+                Some(Err(err)) => return Err(err.into()),
+                None => break,
+            },
+        }
+    }
+    _ = sink.close().await;
+    Ok(())
+}
@@ -0,0 +1,211 @@
+use serde::{Deserialize, Serialize};
+
+/// Version of the room socket protocol this client speaks.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    Hello {
+        version: u32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+        observer: bool,
+    },
+    /// Card from the deck, empty to take the estimate back.
+    Estimate {
+        estimate: String,
+    },
+    Reveal,
+    Hide,
+    Reset,
+}
+
+/// Client message with the id the server echoes in its `ack` or `error`.
+#[derive(Debug, Serialize)]
+pub struct Envelope {
+    pub id: u64,
+    #[serde(flatten)]
+    pub message: ClientMessage,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Seat {
+    pub id: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Participant {
+    pub id: String,
+    pub name: String,
+    pub facilitator: bool,
+    pub observer: bool,
+    pub online: bool,
+    pub voted: bool,
+    #[serde(default)]
+    pub estimate: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Story {
+    #[serde(default)]
+    pub key: Option<String>,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Statistics {
+    pub average: Option<f64>,
+    pub median: Option<f64>,
+    pub mode: Vec<String>,
+    pub min: Option<String>,
+    pub max: Option<String>,
+    pub votes: usize,
+    pub voters: usize,
+    pub consensus: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Room {
+    pub room_id: String,
+    pub revealed: bool,
+    pub deck: Vec<String>,
+    pub participants: Vec<Participant>,
+    #[serde(default)]
+    pub story: Option<Story>,
+    #[serde(default)]
+    pub statistics: Option<Statistics>,
+}
+
+/// Everything the server sends: the answers to the client and the events of the room.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    Welcome {
+        version: u32,
+        #[serde(default)]
+        seat: Option<Seat>,
+    },
+    Ack,
+    Error {
+        message: String,
+    },
+    Snapshot {
+        room: Room,
+    },
+    ParticipantJoined {
+        participant: Participant,
+    },
+    ParticipantUpdated {
+        participant: Participant,
+    },
+    ParticipantLeft {
+        id: String,
+    },
+    EstimatesDeleted,
+    Statistics {
+        statistics: Statistics,
+    },
+    DeckChanged {
+        deck: Vec<String>,
+    },
+    StoryChanged {
+        story: Option<Story>,
+    },
+    AutoRevealCountdown {
+        seconds: u64,
+    },
+    AutoRevealCancelled,
+    TimerStarted {
+        seconds: u64,
+    },
+    TimerTick {
+        seconds: u64,
+    },
+    TimerExpired,
+    TimerStopped,
+    Notice {
+        message: String,
+    },
+    RoomClosed,
+    /// Events added by newer servers.
+    #[serde(other)]
+    Unknown,
+}
+
+/// Socket URL of the room behind a room link like `https://poker.example.com/AbC123`.
+pub fn socket_url(room_url: &str) -> Result<String, String> {
+    let room_url = room_url.trim().trim_end_matches('/');
+    let (scheme, rest) = room_url
+        .split_once("://")
+        .ok_or_else(|| format!("{room_url} is not a room link"))?;
+    let scheme = match scheme {
+        "https" | "wss" => "wss",
+        "http" | "ws" => "ws",
+        scheme => return Err(format!("unsupported scheme {scheme}")),
+    };
+    let (host, room_id) = rest
+        .split_once('/')
+        .ok_or_else(|| format!("{room_url} has no room id"))?;
+    let room_id = room_id.rsplit('/').next().unwrap_or_default();
+    if room_id.is_empty() || !room_id.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(format!("{room_id} is not a room id"));
+    }
+    Ok(format!("{scheme}://{host}/api/v1/rooms/{room_id}/socket"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derives_socket_url_from_room_link() {
+        assert_eq!(
+            socket_url("https://poker.example.com/AbC123").unwrap(),
+            "wss://poker.example.com/api/v1/rooms/AbC123/socket"
+        );
+        assert_eq!(
+            socket_url("http://127.0.0.1:3030/room1/").unwrap(),
+            "ws://127.0.0.1:3030/api/v1/rooms/room1/socket"
+        );
+        assert!(socket_url("poker.example.com/AbC123").is_err());
+        assert!(socket_url("https://poker.example.com").is_err());
+        assert!(socket_url("ftp://poker.example.com/AbC123").is_err());
+    }
+
+    #[test]
+    fn reads_server_messages() {
+        let snapshot: ServerMessage = serde_json::from_str(
+            r#"{"type": "snapshot", "room": {"room_id": "room1", "revealed": true,
+                "deck": ["?", "1", "2"], "participants": [{"id": "a", "name": "Alice",
+                "facilitator": true, "observer": false, "online": true, "voted": true,
+                "estimate": "2"}]}}"#,
+        )
+        .unwrap();
+        let ServerMessage::Snapshot { room } = snapshot else {
+            panic!("expected a snapshot");
+        };
+        assert_eq!(room.participants[0].estimate.as_deref(), Some("2"));
+
+        let ack: ServerMessage = serde_json::from_str(r#"{"type": "ack", "id": 4}"#).unwrap();
+        assert!(matches!(ack, ServerMessage::Ack));
+
+        let unknown: ServerMessage =
+            serde_json::from_str(r#"{"type": "confetti", "amount": 3}"#).unwrap();
+        assert!(matches!(unknown, ServerMessage::Unknown));
+    }
+
+    #[test]
+    fn writes_client_messages() {
+        let envelope = Envelope {
+            id: 4,
+            message: ClientMessage::Estimate {
+                estimate: "5".to_string(),
+            },
+        };
+        assert_eq!(
+            serde_json::to_value(&envelope).unwrap(),
+            serde_json::json!({ "id": 4, "type": "estimate", "estimate": "5" })
+        );
+    }
+}
@@ -0,0 +1,172 @@
+use crate::{
+    app::App,
+    protocol::{Room, Statistics},
+};
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Paragraph, Row, Table},
+    Frame,
+};
+
+pub fn draw(frame: &mut Frame, app: &App) {
+    let [header, body, deck, footer] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Min(5),
+        Constraint::Length(3),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    let Some(room) = &app.room else {
+        frame.render_widget(
+            Paragraph::new("Joining the room...").block(Block::bordered().title(" Scrum Poker ")),
+            header,
+        );
+        draw_footer(frame, footer, app);
+        return;
+    };
+
+    draw_header(frame, header, app, room);
+    let [participants, results] =
+        Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(body);
+    draw_participants(frame, participants, room);
+    draw_results(frame, results, room);
+    draw_deck(frame, deck, app, room);
+    draw_footer(frame, footer, app);
+}
+
+fn draw_header(frame: &mut Frame, area: Rect, app: &App, room: &Room) {
+    let mut spans = vec![if room.revealed {
+        Span::styled("Estimates revealed", Style::new().fg(Color::Green).bold())
+    } else {
+        Span::styled("Estimates hidden", Style::new().fg(Color::Yellow).bold())
+    }];
+    if let Some(story) = &room.story {
+        let title = match &story.key {
+            Some(key) => format!("{key} {}", story.title),
+            None => story.title.clone(),
+        };
+        spans.push(Span::raw(format!("  |  {title}")));
+    }
+    if let Some(seconds) = app.timer {
+        spans.push(Span::raw(format!(
+            "  |  Timer {}:{:02}",
+            seconds / 60,
+            seconds % 60
+        )));
+    }
+    if let Some(seconds) = app.auto_reveal {
+        spans.push(Span::raw(format!("  |  Revealing in {seconds}s")));
+    }
+    let title = format!(" Scrum Poker - room {} ", room.room_id);
+    frame.render_widget(
+        Paragraph::new(Line::from(spans)).block(Block::bordered().title(title)),
+        area,
+    );
+}
+
+fn draw_participants(frame: &mut Frame, area: Rect, room: &Room) {
+    let rows = room.participants.iter().map(|p| {
+        let mut name = p.name.clone();
+        if p.facilitator {
+            name.push_str(" (facilitator)");
+        }
+        let estimate = match (&p.estimate, p.voted, p.observer) {
+            (_, _, true) => "observer".to_string(),
+            (Some(estimate), _, _) => estimate.clone(),
+            (None, true, _) => "voted".to_string(),
+            (None, false, _) => "...".to_string(),
+        };
+        let style = if p.online {
+            Style::new()
+        } else {
+            Style::new().add_modifier(Modifier::DIM)
+        };
+        Row::new([name, estimate]).style(style)
+    });
+    let table = Table::new(rows, [Constraint::Min(10), Constraint::Length(10)])
+        .header(Row::new(["Name", "Estimate"]).bold())
+        .block(Block::bordered().title(" Participants "));
+    frame.render_widget(table, area);
+}
+
+fn statistics_lines(statistics: &Statistics) -> Vec<Line<'static>> {
+    let number = |value: Option<f64>| match value {
+        Some(value) => format!("{value:.1}"),
+        None => "-".to_string(),
+    };
+    let card = |card: &Option<String>| card.clone().unwrap_or_else(|| "-".to_string());
+    vec![
+        Line::from(format!("Consensus: {}", statistics.consensus)),
+        Line::from(format!(
+            "Votes: {} of {}",
+            statistics.votes, statistics.voters
+        )),
+        Line::from(format!("Average: {}", number(statistics.average))),
+        Line::from(format!("Median: {}", number(statistics.median))),
+        Line::from(format!("Most voted: {}", statistics.mode.join(", "))),
+        Line::from(format!(
+            "Range: {} to {}",
+            card(&statistics.min),
+            card(&statistics.max)
+        )),
+    ]
+}
+
+fn draw_results(frame: &mut Frame, area: Rect, room: &Room) {
+    let lines = match (&room.statistics, room.revealed) {
+        (Some(statistics), true) => statistics_lines(statistics),
+        (_, true) => vec![Line::from("No votes")],
+        (_, false) => {
+            let voted = room.participants.iter().filter(|p| p.voted).count();
+            let voters = room.participants.iter().filter(|p| !p.observer).count();
+            vec![Line::from(format!("{voted} of {voters} voted"))]
+        }
+    };
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::bordered().title(" Results ")),
+        area,
+    );
+}
+
+fn draw_deck(frame: &mut Frame, area: Rect, app: &App, room: &Room) {
+    let own_estimate = app.me().and_then(|me| me.estimate.clone());
+    let mut spans = Vec::new();
+    for (index, card) in room.deck.iter().enumerate() {
+        let mut style = Style::new();
+        if Some(card) == own_estimate.as_ref() {
+            style = style.fg(Color::Green);
+        }
+        if index == app.selected {
+            style = style.add_modifier(Modifier::REVERSED | Modifier::BOLD);
+        }
+        spans.push(Span::styled(format!(" {card} "), style));
+        spans.push(Span::raw(" "));
+    }
+    frame.render_widget(
+        Paragraph::new(Line::from(spans)).block(Block::bordered().title(" Cards ")),
+        area,
+    );
+}
+
+fn draw_footer(frame: &mut Frame, area: Rect, app: &App) {
+    let line = match &app.status {
+        Some(status) => Line::from(Span::styled(status.clone(), Style::new().fg(Color::Red))),
+        None => {
+            let mut help = String::from("q quit");
+            if app.can_vote() {
+                help.push_str("  <-/-> pick card  enter vote  backspace take back");
+            }
+            if app.is_facilitator() {
+                help.push_str("  r reveal  h hide  x reset");
+            }
+            if app.seat.is_none() {
+                help.push_str("  (watching)");
+            }
+            Line::from(help).dim()
+        }
+    };
+    frame.render_widget(Paragraph::new(line), area);
+}
@@ -1,5 +1,25 @@
+// Close code of sockets the server closes because it restarts.
+const SERVICE_RESTART = 1012;
+
+// Reloads once the server answers again, so the page does not show the restart.
+function reloadWhenReady() {
+    fetch("/readyz", { cache: "no-store" })
+        .then((response) => {
+            if (response.ok) {
+                window.location.reload();
+            } else {
+                setTimeout(reloadWhenReady, 2000);
+            }
+        })
+        .catch(() => setTimeout(reloadWhenReady, 2000));
+}
+
 setTimeout(function(){ 
     window.ipc.ws.addEventListener("close", (event) => {
+        if (event.code === SERVICE_RESTART) {
+            setTimeout(reloadWhenReady, 1000);
+            return;
+        }
         setTimeout(function() {
             window.location.reload(true);
         }, 1000);
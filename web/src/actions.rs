@@ -35,7 +35,10 @@ pub fn ShowEstimatesButton(estimate_visibility: Signal<EstimateVisibility>) -> E
             class: if estimate_visibility().is_visible() { "{WHITE_BTN_STYLE}" } else { "{BLACK_BTN_STLYE}" },
             onclick: move |_| {
                 async move {
-                    _ = app_props().channel.send(RoomRequest::ChangeVisibility).await;
+                    _ = app_props()
+                        .channel
+                        .send(RoomRequest::ChangeVisibility(app_props().session_id))
+                        .await;
                 }
             },
             if estimate_visibility().is_visible() {
@@ -47,6 +50,32 @@ pub fn ShowEstimatesButton(estimate_visibility: Signal<EstimateVisibility>) -> E
     }
 }
 
+#[component]
+pub fn ObserverToggle(is_observer: Memo<bool>) -> Element {
+    let app_props = use_app_props();
+
+    rsx! {
+        label { class: "inline-flex items-center gap-x-2 cursor-pointer select-none text-slate-600",
+            input {
+                r#type: "checkbox",
+                class: "w-4 h-4 accent-slate-600",
+                checked: is_observer(),
+                onchange: move |evt| {
+                    let observer = evt.checked();
+                    async move {
+                        app_props().session.set("observer", observer);
+                        _ = app_props()
+                            .channel
+                            .send(RoomRequest::ChangeObserver(app_props().session_id, observer))
+                            .await;
+                    }
+                },
+            }
+            "Observer, I don't vote"
+        }
+    }
+}
+
 #[component]
 pub fn DeleteEstimatesModal(show_modal: Signal<bool>) -> Element {
     let app_props = use_app_props();
@@ -119,7 +148,10 @@ pub fn DeleteEstimatesModal(show_modal: Signal<bool>) -> Element {
                                 class: "mt-3 w-full inline-flex items-center justify-center rounded-full border border-transparent px-8 py-4 bg-red-500 text-base font-medium text-white hover:bg-red-700 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-red-500 sm:ml-3 sm:w-auto sm:text-sm",
                                 onclick: move |_| {
                                     async move {
-                                        _ = app_props().channel.send(RoomRequest::DeleteEstimates).await;
+                                        _ = app_props()
+                                            .channel
+                                            .send(RoomRequest::DeleteEstimates(app_props().session_id))
+                                            .await;
                                         show_modal.set(false);
                                     }
                                 },
@@ -0,0 +1,503 @@
+use crate::{
+    error::ScError,
+    estimate::Estimate,
+    room::{Participant, ParticipantStatus, RoomId},
+    room_pool::{self, CtrlMessage, CtrlRequest, CtrlResponse, RoomSummary},
+    state::AppState,
+};
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{Html, IntoResponse, Redirect, Response},
+    routing::{get, post},
+    Form, Router,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Utc};
+use itertools::Itertools;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::{cmp::Reverse, fmt, sync::Arc, time::Duration};
+use tokio::{sync::mpsc, time::timeout};
+
+/// Time a room gets to answer a request of the dashboard.
+const CTRL_TIMEOUT: Duration = Duration::from_secs(2);
+const MAX_NOTICE_LENGTH: usize = 500;
+
+/// Operator pages, nested under `/admin`.
+///
+/// They need the `ADMIN_TOKEN` of the environment, either as bearer token or as password of
+/// basic auth so browsers can prompt for it. Without the variable the dashboard does not exist.
+/// Browsers send basic auth along with forms of other sites too, so posts from another origin are
+/// refused.
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(dashboard))
+        .route("/notice", post(send_notice))
+        .route("/rooms/:room_id", get(room))
+        .route("/rooms/:room_id/close", post(close_room))
+}
+
+#[derive(Debug)]
+pub enum AdminError {
+    Disabled,
+    Unauthorized,
+    /// A post of a page on another site.
+    CrossOrigin,
+    UnknownRoom(RoomId),
+    /// The room did not answer in time.
+    Unresponsive(RoomId),
+    Room(ScError),
+}
+
+impl fmt::Display for AdminError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AdminError::Disabled => write!(f, "not found"),
+            AdminError::Unauthorized => write!(f, "missing or invalid admin token"),
+            AdminError::CrossOrigin => write!(f, "cross-origin requests are not allowed"),
+            AdminError::UnknownRoom(room_id) => write!(f, "room {} is not running", room_id),
+            AdminError::Unresponsive(room_id) => write!(f, "room {} does not answer", room_id),
+            AdminError::Room(_) => write!(f, "room pool is not available"),
+        }
+    }
+}
+
+impl From<ScError> for AdminError {
+    fn from(err: ScError) -> Self {
+        AdminError::Room(err)
+    }
+}
+
+impl IntoResponse for AdminError {
+    fn into_response(self) -> Response {
+        match &self {
+            AdminError::Disabled => StatusCode::NOT_FOUND.into_response(),
+            AdminError::Unauthorized => (
+                StatusCode::UNAUTHORIZED,
+                [(
+                    header::WWW_AUTHENTICATE,
+                    "Basic realm=\"Scrum Poker admin\"",
+                )],
+                self.to_string(),
+            )
+                .into_response(),
+            AdminError::CrossOrigin => (StatusCode::FORBIDDEN, self.to_string()).into_response(),
+            AdminError::UnknownRoom(_) => (StatusCode::NOT_FOUND, self.to_string()).into_response(),
+            AdminError::Unresponsive(_) => {
+                (StatusCode::GATEWAY_TIMEOUT, self.to_string()).into_response()
+            }
+            AdminError::Room(err) => {
+                tracing::error!("Admin request failed, error: {}", err);
+                (StatusCode::INTERNAL_SERVER_ERROR, self.to_string()).into_response()
+            }
+        }
+    }
+}
+
+/// Token of a `Bearer` authorization, or the password of a `Basic` one.
+fn credentials(authorization: &str) -> Option<String> {
+    if let Some(token) = authorization.strip_prefix("Bearer ") {
+        return Some(token.trim().to_string());
+    }
+    let decoded = STANDARD
+        .decode(authorization.strip_prefix("Basic ")?.trim())
+        .ok()?;
+    let decoded = String::from_utf8(decoded).ok()?;
+    decoded
+        .split_once(':')
+        .map(|(_, password)| password.to_string())
+}
+
+/// Compares digests, so the time taken does not tell how much of the token matched.
+fn token_matches(admin_token: &str, token: &str) -> bool {
+    let expected = Sha256::digest(admin_token.as_bytes());
+    let actual = Sha256::digest(token.as_bytes());
+    expected
+        .iter()
+        .zip(actual.iter())
+        .fold(0, |diff, (a, b)| diff | (a ^ b))
+        == 0
+}
+
+fn authorize(state: &AppState, headers: &HeaderMap) -> Result<(), AdminError> {
+    let Some(admin_token) = &state.admin_token else {
+        return Err(AdminError::Disabled);
+    };
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(credentials)
+        .filter(|token| token_matches(admin_token, token))
+        .map(|_| ())
+        .ok_or(AdminError::Unauthorized)
+}
+
+/// Whether the request comes from a page of the dashboard itself. Browsers tell with
+/// `Sec-Fetch-Site`, older ones with the `Origin` of posts. Clients like curl send neither.
+fn same_origin(headers: &HeaderMap) -> bool {
+    let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+    if let Some(site) = header("sec-fetch-site") {
+        return site == "same-origin" || site == "none";
+    }
+    match (
+        header(header::ORIGIN.as_str()),
+        header(header::HOST.as_str()),
+    ) {
+        (None, _) => true,
+        (Some(origin), Some(host)) => origin
+            .split_once("://")
+            .is_some_and(|(_, origin_host)| origin_host == host),
+        (Some(_), None) => false,
+    }
+}
+
+/// Authorizes requests that change something, which must not come from other sites.
+fn authorize_post(state: &AppState, headers: &HeaderMap) -> Result<(), AdminError> {
+    authorize(state, headers)?;
+    if same_origin(headers) {
+        Ok(())
+    } else {
+        Err(AdminError::CrossOrigin)
+    }
+}
+
+async fn ask(
+    room_id: &RoomId,
+    ctrl: &mpsc::Sender<CtrlMessage>,
+    request: CtrlRequest,
+) -> Result<CtrlResponse, AdminError> {
+    match timeout(CTRL_TIMEOUT, room_pool::control(ctrl, request)).await {
+        Ok(Some(response)) => Ok(response),
+        _ => Err(AdminError::Unresponsive(room_id.clone())),
+    }
+}
+
+async fn room_control(
+    state: &AppState,
+    room_id: RoomId,
+) -> Result<mpsc::Sender<CtrlMessage>, AdminError> {
+    state
+        .room_pool
+        .controls()
+        .await?
+        .into_iter()
+        .find(|(id, _)| *id == room_id)
+        .map(|(_, ctrl)| ctrl)
+        .ok_or(AdminError::UnknownRoom(room_id))
+}
+
+async fn summary(
+    room_id: &RoomId,
+    ctrl: &mpsc::Sender<CtrlMessage>,
+) -> Result<RoomSummary, AdminError> {
+    match ask(room_id, ctrl, CtrlRequest::Summary).await? {
+        CtrlResponse::Summary(summary) => Ok(summary),
+        _ => Err(AdminError::Room(ScError::UnexpectedResponse)),
+    }
+}
+
+async fn dashboard(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Html<String>, AdminError> {
+    authorize(&state, &headers)?;
+    let controls = state.room_pool.controls().await?;
+    let summaries = futures::future::join_all(
+        controls
+            .iter()
+            .map(|(room_id, ctrl)| summary(room_id, ctrl)),
+    )
+    .await;
+    let (mut rooms, unresponsive): (Vec<RoomSummary>, Vec<AdminError>) =
+        summaries.into_iter().partition_result();
+    rooms.sort_by_key(|room| Reverse(room.last_activity));
+    Ok(page(
+        "Rooms",
+        &dashboard_body(&rooms, &unresponsive, Utc::now()),
+    ))
+}
+
+async fn room(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(room_id): Path<RoomId>,
+) -> Result<Html<String>, AdminError> {
+    authorize(&state, &headers)?;
+    let ctrl = room_control(&state, room_id.clone()).await?;
+    let summary = summary(&room_id, &ctrl).await?;
+    let CtrlResponse::Participants(mut participants) =
+        ask(&room_id, &ctrl, CtrlRequest::Participants).await?
+    else {
+        return Err(AdminError::Room(ScError::UnexpectedResponse));
+    };
+    participants.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(page(
+        &format!("Room {}", room_id),
+        &room_body(&summary, &participants, Utc::now()),
+    ))
+}
+
+async fn close_room(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(room_id): Path<RoomId>,
+) -> Result<Redirect, AdminError> {
+    authorize_post(&state, &headers)?;
+    let ctrl = room_control(&state, room_id.clone()).await?;
+    ask(&room_id, &ctrl, CtrlRequest::Close).await?;
+    tracing::info!("Operator closed room {}", room_id);
+    Ok(Redirect::to("/admin"))
+}
+
+#[derive(Deserialize)]
+struct NoticeForm {
+    message: String,
+}
+
+async fn send_notice(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Form(form): Form<NoticeForm>,
+) -> Result<Redirect, AdminError> {
+    authorize_post(&state, &headers)?;
+    let message = form.message.trim();
+    if message.is_empty() {
+        return Ok(Redirect::to("/admin"));
+    }
+    let message: Arc<str> = Arc::from(message.chars().take(MAX_NOTICE_LENGTH).collect::<String>());
+    let controls = state.room_pool.controls().await?;
+    let sent = futures::future::join_all(
+        controls
+            .iter()
+            .map(|(room_id, ctrl)| ask(room_id, ctrl, CtrlRequest::Notice(message.clone()))),
+    )
+    .await;
+    let failed = sent.iter().filter(|result| result.is_err()).count();
+    tracing::info!(
+        "Operator sent notice to {} rooms, {} did not answer",
+        controls.len() - failed,
+        failed
+    );
+    Ok(Redirect::to("/admin"))
+}
+
+/// Percent encodes everything but unreserved characters, for a segment of a URL path.
+fn path_segment(text: &str) -> String {
+    text.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Coarse time since the given moment, like `3d 4h` or `12m 5s`.
+fn elapsed(since: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let seconds = (now - since).num_seconds().max(0);
+    let (days, hours, minutes) = (seconds / 86400, seconds / 3600 % 24, seconds / 60 % 60);
+    match (days, hours, minutes) {
+        (0, 0, 0) => format!("{}s", seconds),
+        (0, 0, _) => format!("{}m {}s", minutes, seconds % 60),
+        (0, _, _) => format!("{}h {}m", hours, minutes),
+        _ => format!("{}d {}h", days, hours),
+    }
+}
+
+fn page(title: &str, body: &str) -> Html<String> {
+    Html(format!(
+        r#"
+        <!DOCTYPE html>
+        <html>
+        <head>
+            <title>Scrum Poker admin - {title}</title>
+            <meta name="viewport" content="width=device-width, initial-scale=1" />
+            <style>
+                body {{ font-family: sans-serif; margin: 2rem; color: #1e293b; }}
+                table {{ border-collapse: collapse; margin: 1rem 0; }}
+                th, td {{ border-bottom: 1px solid #cbd5e1; padding: 0.4rem 0.8rem; text-align: left; }}
+                form {{ display: inline; }}
+                textarea {{ width: 30rem; height: 4rem; display: block; margin-bottom: 0.5rem; }}
+                .warning {{ color: #b91c1c; }}
+            </style>
+        </head>
+        <body>
+            <h1>{title}</h1>
+            {body}
+            <script>
+                document.addEventListener("submit", (event) => {{
+                    const question = event.target.dataset.confirm;
+                    if (question && !confirm(question)) event.preventDefault();
+                }});
+            </script>
+        </body>
+        </html>
+        "#,
+        title = escape(title),
+    ))
+}
+
+fn dashboard_body(
+    rooms: &[RoomSummary],
+    unresponsive: &[AdminError],
+    now: DateTime<Utc>,
+) -> String {
+    let rows: String = rooms
+        .iter()
+        .map(|room| {
+            let room_id = escape(&room.room_id);
+            let path = escape(&path_segment(&room.room_id));
+            format!(
+                r#"<tr><td><a href="/admin/rooms/{path}">{room_id}</a></td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{} ago</td>
+                <td><form method="post" action="/admin/rooms/{path}/close" data-confirm="Close room {room_id}?"><button>Close</button></form></td></tr>"#,
+                room.online,
+                room.left,
+                if room.visibility.is_visible() { "revealed" } else { "hidden" },
+                elapsed(room.created_at, now),
+                elapsed(room.last_activity, now),
+            )
+        })
+        .collect();
+    let warnings: String = unresponsive
+        .iter()
+        .map(|err| format!(r#"<p class="warning">{}</p>"#, escape(&err.to_string())))
+        .collect();
+    format!(
+        r#"
+        {warnings}
+        <table>
+            <tr><th>Room</th><th>Online</th><th>Left</th><th>Estimates</th><th>Age</th><th>Last activity</th><th></th></tr>
+            {rows}
+        </table>
+        <form method="post" action="/admin/notice">
+            <textarea name="message" maxlength="{MAX_NOTICE_LENGTH}" placeholder="Maintenance at 18:00 UTC, the rooms will be back within minutes."></textarea>
+            <button>Send notice to every room</button>
+        </form>
+        "#
+    )
+}
+
+fn room_body(summary: &RoomSummary, participants: &[Participant], now: DateTime<Utc>) -> String {
+    let rows: String = participants
+        .iter()
+        .map(|p| {
+            format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape(&p.name),
+                if p.is_facilitator() {
+                    "facilitator"
+                } else if p.observer {
+                    "observer"
+                } else {
+                    "voter"
+                },
+                match p.status {
+                    ParticipantStatus::Online => "online",
+                    ParticipantStatus::Left => "left",
+                },
+                if p.estimate == Estimate::None {
+                    "no"
+                } else {
+                    "yes"
+                },
+            )
+        })
+        .collect();
+    let room_id = escape(&summary.room_id);
+    let path = escape(&path_segment(&summary.room_id));
+    format!(
+        r#"
+        <p><a href="/admin">All rooms</a></p>
+        <p>Spawned {} ago, last activity {} ago.</p>
+        <table>
+            <tr><th>Name</th><th>Role</th><th>Status</th><th>Voted</th></tr>
+            {rows}
+        </table>
+        <form method="post" action="/admin/rooms/{path}/close" data-confirm="Close room {room_id}?">
+            <button>Close room</button>
+        </form>
+        "#,
+        elapsed(summary.created_at, now),
+        elapsed(summary.last_activity, now),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+
+    #[test]
+    fn reads_bearer_and_basic_credentials() {
+        assert_eq!(credentials("Bearer s3cret").as_deref(), Some("s3cret"));
+        let basic = format!("Basic {}", STANDARD.encode("admin:s3cret"));
+        assert_eq!(credentials(&basic).as_deref(), Some("s3cret"));
+        assert_eq!(credentials("Basic not base64!"), None);
+        assert!(token_matches("s3cret", "s3cret"));
+        assert!(!token_matches("s3cret", "s3cre"));
+    }
+
+    #[test]
+    fn refuses_posts_of_other_sites() {
+        let headers = |pairs: &[(&'static str, &'static str)]| {
+            let mut headers = HeaderMap::new();
+            for (name, value) in pairs {
+                headers.insert(*name, value.parse().unwrap());
+            }
+            headers
+        };
+        assert!(same_origin(&headers(&[])));
+        assert!(same_origin(&headers(&[("sec-fetch-site", "same-origin")])));
+        assert!(!same_origin(&headers(&[("sec-fetch-site", "cross-site")])));
+        assert!(!same_origin(&headers(&[("sec-fetch-site", "same-site")])));
+        assert!(same_origin(&headers(&[
+            ("origin", "https://poker.example"),
+            ("host", "poker.example")
+        ])));
+        assert!(!same_origin(&headers(&[
+            ("origin", "https://evil.example"),
+            ("host", "poker.example")
+        ])));
+        assert!(!same_origin(&headers(&[("origin", "null")])));
+    }
+
+    #[test]
+    fn room_ids_stay_inside_urls_and_attributes() {
+        assert_eq!(path_segment("abc-1_2"), "abc-1_2");
+        assert_eq!(path_segment("a/b?c'"), "a%2Fb%3Fc%27");
+
+        let summary = RoomSummary {
+            room_id: Arc::from("x');alert(1);('"),
+            online: 1,
+            left: 0,
+            visibility: crate::channel::EstimateVisibility::Hidden,
+            created_at: Utc::now(),
+            last_activity: Utc::now(),
+        };
+        let body = dashboard_body(std::slice::from_ref(&summary), &[], Utc::now());
+        assert!(!body.contains("onsubmit"));
+        assert!(body.contains(r#"action="/admin/rooms/x%27%29%3Balert%281%29%3B%28%27/close""#));
+        assert!(!room_body(&summary, &[], Utc::now()).contains("');"));
+    }
+
+    #[test]
+    fn formats_elapsed_time() {
+        let now = Utc::now();
+        let ago = |seconds| elapsed(now - TimeDelta::seconds(seconds), now);
+        assert_eq!(ago(42), "42s");
+        assert_eq!(ago(12 * 60 + 5), "12m 5s");
+        assert_eq!(ago(5 * 3600 + 2 * 60), "5h 2m");
+        assert_eq!(ago(3 * 86400 + 4 * 3600 + 59), "3d 4h");
+        assert_eq!(elapsed(now + TimeDelta::seconds(3), now), "0s");
+    }
+}
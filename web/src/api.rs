@@ -0,0 +1,525 @@
+use crate::{
+    channel::{EstimateVisibility, Rejection, RoomChannel, RoomRequest, RoomResponse, RoomState},
+    error::ScError,
+    estimate::Estimate,
+    events::RoomEvents,
+    room::{Participant, ParticipantStatus, RoomId},
+    socket,
+    state::AppState,
+    statistics::RoundStatistics,
+    story::Story,
+    validate::{self, ALPHABET_AND_NUMBERS},
+};
+use axum::{
+    extract::{ws::WebSocketUpgrade, Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::{delete, get, post, put},
+    Json, Router,
+};
+use chrono::Utc;
+use futures::{stream, Stream, StreamExt};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::{convert::Infallible, fmt, sync::Arc};
+use uuid::Uuid;
+
+/// Routes of the JSON API, nested under `/api/v1`.
+///
+/// Joining a room hands out a bearer token for the new participant. Voting, leaving and the
+/// facilitator actions need it in the `Authorization` header, reading the room does not.
+/// Participants leave after five minutes without a request, clients that only watch keep their
+/// seat with `POST /rooms/:room_id/heartbeat`.
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/rooms", post(create_room))
+        .route("/rooms/:room_id", get(get_room))
+        .route("/rooms/:room_id/participants", post(join_room))
+        .route("/rooms/:room_id/participants/me", delete(leave_room))
+        .route("/rooms/:room_id/heartbeat", post(heartbeat))
+        .route("/rooms/:room_id/estimate", put(send_estimate))
+        .route("/rooms/:room_id/reveal", post(reveal))
+        .route("/rooms/:room_id/hide", post(hide))
+        .route("/rooms/:room_id/reset", post(reset))
+        .route("/rooms/:room_id/events", get(room_events))
+        .route("/rooms/:room_id/socket", get(room_socket))
+}
+
+#[derive(Debug)]
+pub enum ApiError {
+    InvalidRoomId,
+    Unauthorized,
+    Forbidden,
+    NotInRoom,
+    Observer,
+    NotInDeck(Estimate),
+    /// Only sent on the socket, REST requests are checked by their extractors.
+    InvalidMessage(String),
+    UnsupportedVersion(u32),
+    Room(ScError),
+}
+
+impl ApiError {
+    /// Stable identifier of the error clients can match on.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::InvalidRoomId => "invalid_room_id",
+            ApiError::Unauthorized => "unauthorized",
+            ApiError::Forbidden => "forbidden",
+            ApiError::NotInRoom => "not_in_room",
+            ApiError::Observer => "observer",
+            ApiError::NotInDeck(_) => "not_in_deck",
+            ApiError::InvalidMessage(_) => "invalid_message",
+            ApiError::UnsupportedVersion(_) => "unsupported_version",
+            ApiError::Room(_) => "unavailable",
+        }
+    }
+
+    fn status(&self) -> StatusCode {
+        match self {
+            ApiError::InvalidRoomId
+            | ApiError::InvalidMessage(_)
+            | ApiError::UnsupportedVersion(_) => StatusCode::BAD_REQUEST,
+            ApiError::Unauthorized => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden => StatusCode::FORBIDDEN,
+            ApiError::NotInRoom => StatusCode::NOT_FOUND,
+            ApiError::Observer => StatusCode::CONFLICT,
+            ApiError::NotInDeck(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::Room(ScError::ShuttingDown | ScError::RoomUnavailable) => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+            ApiError::Room(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::InvalidRoomId => write!(f, "invalid room id"),
+            ApiError::Unauthorized => write!(f, "missing or invalid participant token"),
+            ApiError::Forbidden => write!(f, "only the facilitator may do this"),
+            ApiError::NotInRoom => write!(f, "participant is not in the room"),
+            ApiError::Observer => write!(f, "observers do not estimate"),
+            ApiError::NotInDeck(estimate) => {
+                write!(f, "{} is not in the deck of the room", estimate)
+            }
+            ApiError::InvalidMessage(reason) => write!(f, "invalid message: {}", reason),
+            ApiError::UnsupportedVersion(version) => {
+                write!(f, "protocol version {} is not supported", version)
+            }
+            ApiError::Room(_) => write!(f, "room is not available"),
+        }
+    }
+}
+
+impl From<ScError> for ApiError {
+    fn from(err: ScError) -> Self {
+        ApiError::Room(err)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        if let ApiError::Room(err) = &self {
+            tracing::error!("API request failed, error: {}", err);
+        }
+        let body = json!({ "code": self.code(), "error": self.to_string() });
+        (self.status(), Json(body)).into_response()
+    }
+}
+
+/// What anyone with the link sees of a room. Estimates are only shown once revealed.
+#[derive(Debug, Clone, Serialize)]
+pub struct RoomView {
+    pub room_id: RoomId,
+    pub revealed: bool,
+    pub deck: Vec<Estimate>,
+    pub participants: Vec<ParticipantView>,
+    /// Story the room is estimating.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub story: Option<Story>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub statistics: Option<RoundStatistics>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ParticipantView {
+    pub id: Uuid,
+    pub name: Arc<str>,
+    pub facilitator: bool,
+    pub observer: bool,
+    pub online: bool,
+    pub voted: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<Estimate>,
+}
+
+impl ParticipantView {
+    pub fn new(p: &Participant, revealed: bool) -> ParticipantView {
+        ParticipantView {
+            id: participant_id(p.session_id),
+            name: p.name.clone(),
+            facilitator: p.is_facilitator(),
+            observer: p.observer,
+            online: p.status == ParticipantStatus::Online,
+            voted: p.estimate != Estimate::None,
+            estimate: (revealed && p.estimate != Estimate::None).then(|| p.estimate.clone()),
+        }
+    }
+}
+
+impl RoomView {
+    pub fn new(room_id: RoomId, state: &RoomState) -> RoomView {
+        let revealed = state.visibility.is_visible();
+        let mut participants: Vec<ParticipantView> = state
+            .participants
+            .values()
+            .map(|p| ParticipantView::new(p, revealed))
+            .collect();
+        participants.sort_by(|a, b| a.name.cmp(&b.name));
+        RoomView {
+            room_id,
+            revealed,
+            deck: state.deck.cards(),
+            participants,
+            story: state.stories.current().cloned(),
+            statistics: state.statistics.clone(),
+        }
+    }
+}
+
+/// Public id of a participant. Session ids double as login sessions of the web UI, so they are
+/// never handed out; this hash of the session id identifies the participant just as well.
+pub fn participant_id(session_id: Uuid) -> Uuid {
+    let digest = Sha256::digest(session_id.as_bytes());
+    Uuid::from_slice(&digest[..16]).expect("SHA-256 digests are longer than a UUID")
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JoinRequest {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub observer: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Joined {
+    /// Bearer token of the new participant.
+    pub token: String,
+    pub room: RoomView,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EstimateRequest {
+    /// Card from the deck of the room, empty to take the estimate back.
+    pub estimate: Estimate,
+}
+
+fn mac(secret: &str, room_id: &str, session_id: Uuid) -> Hmac<Sha256> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(format!("{room_id}:{session_id}").as_bytes());
+    mac
+}
+
+/// `<session_id>.<signature>`, valid for the room it was issued in as long as the secret stays
+/// the same. Nothing is stored, so tokens survive restarts when `API_SECRET` is set, and the
+/// participant keeps its seat as long as it is not idle for `API_IDLE_TIMEOUT`.
+pub fn participant_token(secret: &str, room_id: &str, session_id: Uuid) -> String {
+    let signature = mac(secret, room_id, session_id).finalize().into_bytes();
+    format!("{}.{}", session_id, hex::encode(signature))
+}
+
+/// Session id of the participant the token was issued to in the room.
+pub fn verify_token(secret: &str, room_id: &str, token: &str) -> Option<Uuid> {
+    let (session_id, signature) = token.split_once('.')?;
+    let session_id = Uuid::parse_str(session_id).ok()?;
+    let signature = hex::decode(signature).ok()?;
+    mac(secret, room_id, session_id)
+        .verify_slice(&signature)
+        .ok()
+        .map(|_| session_id)
+}
+
+fn authorize(state: &AppState, room_id: &str, headers: &HeaderMap) -> Result<Uuid, ApiError> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .and_then(|token| verify_token(&state.api_secret, room_id, token.trim()))
+        .ok_or(ApiError::Unauthorized)
+}
+
+pub fn checked_room_id(room_id: RoomId) -> Result<RoomId, ApiError> {
+    let validated_room_id = validate::room_id(room_id.clone());
+    if validated_room_id.is_empty() || validated_room_id != room_id {
+        return Err(ApiError::InvalidRoomId);
+    }
+    Ok(validated_room_id)
+}
+
+pub async fn channel(state: &AppState, room_id: &RoomId) -> Result<RoomChannel, ApiError> {
+    Ok(state.room_pool.spawn(room_id).await?)
+}
+
+pub async fn room_state(channel: &RoomChannel) -> Result<RoomState, ApiError> {
+    match channel.send(RoomRequest::State).await? {
+        RoomResponse::RoomState(room_state) => Ok(*room_state),
+        _ => Err(ApiError::Room(ScError::UnexpectedResponse)),
+    }
+}
+
+pub async fn send(channel: &RoomChannel, request: RoomRequest) -> Result<(), ApiError> {
+    match channel.send(request).await? {
+        RoomResponse::PermissionDenied => Err(ApiError::Forbidden),
+        RoomResponse::Rejected(Rejection::NotInRoom) => Err(ApiError::NotInRoom),
+        RoomResponse::Rejected(Rejection::Observer) => Err(ApiError::Observer),
+        RoomResponse::Rejected(Rejection::NotInDeck(estimate)) => {
+            Err(ApiError::NotInDeck(estimate))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Sends the request and replies with the room as it is afterwards.
+async fn update(
+    room_id: RoomId,
+    channel: &RoomChannel,
+    request: RoomRequest,
+) -> Result<Json<RoomView>, ApiError> {
+    send(channel, request).await?;
+    Ok(Json(RoomView::new(room_id, &room_state(channel).await?)))
+}
+
+/// Sends the estimate, the room rejects estimates that are not in its deck and those of
+/// observers or participants it does not know.
+pub async fn send_estimate_of(
+    channel: &RoomChannel,
+    session_id: Uuid,
+    estimate: Estimate,
+) -> Result<(), ApiError> {
+    send(channel, RoomRequest::SendEstimate(session_id, estimate)).await
+}
+
+/// Joins the participant, or brings it back online when its session is in the room already.
+pub async fn join_as(
+    channel: &RoomChannel,
+    participant: Participant,
+) -> Result<RoomState, ApiError> {
+    match channel.send(RoomRequest::Join(participant)).await? {
+        RoomResponse::RoomState(room_state) => Ok(*room_state),
+        _ => Err(ApiError::Room(ScError::UnexpectedResponse)),
+    }
+}
+
+async fn join(
+    state: &AppState,
+    room_id: RoomId,
+    request: JoinRequest,
+) -> Result<(StatusCode, Json<Joined>), ApiError> {
+    let channel = channel(state, &room_id).await?;
+    let session_id = Uuid::new_v4();
+    let mut participant =
+        Participant::new(session_id, Arc::from(validate::username(&request.name)));
+    participant.observer = request.observer;
+    participant.last_seen = Some(Utc::now());
+    let room_state = join_as(&channel, participant).await?;
+    tracing::trace!("API participant {} joined room {}", session_id, room_id);
+    let joined = Joined {
+        token: participant_token(&state.api_secret, &room_id, session_id),
+        room: RoomView::new(room_id, &room_state),
+    };
+    Ok((StatusCode::CREATED, Json(joined)))
+}
+
+/// Creates a room with the caller as its facilitator.
+async fn create_room(
+    State(state): State<AppState>,
+    Json(request): Json<JoinRequest>,
+) -> Result<(StatusCode, Json<Joined>), ApiError> {
+    let room_id: RoomId = Arc::from(nanoid::nanoid!(10, &ALPHABET_AND_NUMBERS));
+    join(&state, room_id, request).await
+}
+
+async fn get_room(
+    State(state): State<AppState>,
+    Path(room_id): Path<RoomId>,
+) -> Result<Json<RoomView>, ApiError> {
+    let room_id = checked_room_id(room_id)?;
+    let channel = channel(&state, &room_id).await?;
+    Ok(Json(RoomView::new(room_id, &room_state(&channel).await?)))
+}
+
+async fn join_room(
+    State(state): State<AppState>,
+    Path(room_id): Path<RoomId>,
+    Json(request): Json<JoinRequest>,
+) -> Result<(StatusCode, Json<Joined>), ApiError> {
+    join(&state, checked_room_id(room_id)?, request).await
+}
+
+/// Participants joined through the API leave here, or once they are idle for `API_IDLE_TIMEOUT`.
+async fn leave_room(
+    State(state): State<AppState>,
+    Path(room_id): Path<RoomId>,
+    headers: HeaderMap,
+) -> Result<StatusCode, ApiError> {
+    let room_id = checked_room_id(room_id)?;
+    let session_id = authorize(&state, &room_id, &headers)?;
+    let channel = channel(&state, &room_id).await?;
+    channel.send(RoomRequest::Leave(session_id)).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Keeps the participant in the room while it sends no other requests.
+async fn heartbeat(
+    State(state): State<AppState>,
+    Path(room_id): Path<RoomId>,
+    headers: HeaderMap,
+) -> Result<StatusCode, ApiError> {
+    let room_id = checked_room_id(room_id)?;
+    let session_id = authorize(&state, &room_id, &headers)?;
+    let channel = channel(&state, &room_id).await?;
+    channel.send(RoomRequest::Heartbeat(session_id)).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn send_estimate(
+    State(state): State<AppState>,
+    Path(room_id): Path<RoomId>,
+    headers: HeaderMap,
+    Json(request): Json<EstimateRequest>,
+) -> Result<Json<RoomView>, ApiError> {
+    let room_id = checked_room_id(room_id)?;
+    let session_id = authorize(&state, &room_id, &headers)?;
+    let channel = channel(&state, &room_id).await?;
+    send_estimate_of(&channel, session_id, request.estimate).await?;
+    Ok(Json(RoomView::new(room_id, &room_state(&channel).await?)))
+}
+
+async fn change_visibility(
+    state: AppState,
+    room_id: RoomId,
+    headers: HeaderMap,
+    visibility: EstimateVisibility,
+) -> Result<Json<RoomView>, ApiError> {
+    let room_id = checked_room_id(room_id)?;
+    let session_id = authorize(&state, &room_id, &headers)?;
+    let channel = channel(&state, &room_id).await?;
+    let request = RoomRequest::SetVisibility(session_id, visibility);
+    update(room_id, &channel, request).await
+}
+
+async fn reveal(
+    State(state): State<AppState>,
+    Path(room_id): Path<RoomId>,
+    headers: HeaderMap,
+) -> Result<Json<RoomView>, ApiError> {
+    change_visibility(state, room_id, headers, EstimateVisibility::Visible).await
+}
+
+async fn hide(
+    State(state): State<AppState>,
+    Path(room_id): Path<RoomId>,
+    headers: HeaderMap,
+) -> Result<Json<RoomView>, ApiError> {
+    change_visibility(state, room_id, headers, EstimateVisibility::Hidden).await
+}
+
+/// Deletes the estimates of the round, like the facilitator's delete button.
+async fn reset(
+    State(state): State<AppState>,
+    Path(room_id): Path<RoomId>,
+    headers: HeaderMap,
+) -> Result<Json<RoomView>, ApiError> {
+    let room_id = checked_room_id(room_id)?;
+    let session_id = authorize(&state, &room_id, &headers)?;
+    let channel = channel(&state, &room_id).await?;
+    update(room_id, &channel, RoomRequest::DeleteEstimates(session_id)).await
+}
+
+/// Server-Sent Events of the room, one JSON encoded `RoomEvent` per message.
+async fn room_events(
+    State(state): State<AppState>,
+    Path(room_id): Path<RoomId>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    let room_id = checked_room_id(room_id)?;
+    let events = RoomEvents::subscribe(state.room_pool.clone(), room_id).await?;
+    let stream = stream::unfold(events, |mut events| async move {
+        let event = events.next().await?;
+        let event = Event::default().json_data(&event).unwrap_or_else(|err| {
+            tracing::error!("Failed to serialize room event, error: {}", err);
+            Event::default().comment("unserializable event")
+        });
+        Some((Ok(event), events))
+    });
+    let stream = stream.take_until(state.shutdown.closing());
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+async fn room_socket(
+    State(state): State<AppState>,
+    Path(room_id): Path<RoomId>,
+    ws: WebSocketUpgrade,
+) -> Result<Response, ApiError> {
+    let room_id = checked_room_id(room_id)?;
+    Ok(ws.on_upgrade(move |socket| socket::serve(socket, state, room_id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{estimate::CardDeck, room::RoomSettings, story::StoryQueue};
+    use std::collections::HashMap;
+
+    #[test]
+    fn tokens_are_bound_to_secret_and_room() {
+        let session_id = Uuid::from_u128(7);
+        let token = participant_token("secret", "room1", session_id);
+        assert_eq!(verify_token("secret", "room1", &token), Some(session_id));
+        assert_eq!(verify_token("other", "room1", &token), None);
+        assert_eq!(verify_token("secret", "room2", &token), None);
+
+        let forged = token.replacen("00000000", "00000001", 1);
+        assert_eq!(verify_token("secret", "room1", &forged), None);
+        assert_eq!(verify_token("secret", "room1", "garbage"), None);
+    }
+
+    #[test]
+    fn estimates_are_shown_once_revealed() {
+        let mut alice = Participant::new(Uuid::from_u128(1), Arc::from("Alice"));
+        alice.estimate = Estimate::from("5");
+        let bob = Participant::new(Uuid::from_u128(2), Arc::from("Bob"));
+        let mut state = RoomState {
+            participants: HashMap::from([(alice.session_id, alice), (bob.session_id, bob)]),
+            visibility: EstimateVisibility::Hidden,
+            deck: CardDeck::default(),
+            statistics: None,
+            settings: RoomSettings::default(),
+            timer: None,
+            stories: StoryQueue::default(),
+            rounds: Vec::new(),
+            jira: None,
+            github: None,
+            webhook: None,
+            chat: false,
+        };
+
+        let hidden = RoomView::new(Arc::from("room1"), &state);
+        assert!(hidden.participants[0].voted);
+        assert_eq!(hidden.participants[0].estimate, None);
+        assert!(!hidden.participants[1].voted);
+
+        state.visibility = EstimateVisibility::Visible;
+        let revealed = RoomView::new(Arc::from("room1"), &state);
+        assert_eq!(revealed.participants[0].estimate, Some(Estimate::from("5")));
+        assert_eq!(revealed.participants[1].estimate, None);
+    }
+}
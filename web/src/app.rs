@@ -1,14 +1,28 @@
-use crate::actions::{DeleteEstimatesButton, DeleteEstimatesModal, ShowEstimatesButton};
+use crate::actions::{
+    DeleteEstimatesButton, DeleteEstimatesModal, ObserverToggle, ShowEstimatesButton,
+};
 use crate::channel::{EstimateVisibility, RoomBroadcastMessage, RoomRequest, RoomResponse};
-use crate::deck::Deck;
-use crate::estimate::Estimate;
+use crate::deck::{Deck, DeckSettings};
+use crate::estimate::{CardDeck, Estimate};
+use crate::integrations::{
+    ChatSettings, GitHubSettings, IssueKeyInput, JiraSettings, WebhookSettings,
+};
+use crate::metrics;
 use crate::name::Name;
-use crate::room::Participant;
-use crate::table::Table;
+use crate::past_rounds::PastRounds;
+use crate::room::{Participant, RoomSettings};
+use crate::round::RoundRecord;
+use crate::settings::{AutoRevealCountdown, AutoRevealSettings};
+use crate::statistics::RoundStatistics;
+use crate::stories::{CurrentStory, FinalEstimatePicker, FinalizedStories, StoryQueuePanel};
+use crate::story::StoryQueue;
+use crate::table::{StatisticsPanel, Table};
+use crate::timer::{RoundTimerDisplay, TimerControls};
 use crate::{username, AppProps};
 use dioxus::prelude::*;
 use std::collections::HashMap;
 use std::sync::Arc;
+use tokio::sync::broadcast;
 use uuid::Uuid;
 
 pub fn use_app_props() -> Signal<AppProps> {
@@ -22,6 +36,31 @@ pub fn App(props: AppProps) -> Element {
     let mut username = use_signal(|| username::get_username(&app_props().session));
     let mut participants = use_signal(|| HashMap::<Uuid, Participant>::new());
     let mut estimate_visibility = use_signal(|| EstimateVisibility::Hidden);
+    let mut deck = use_signal(CardDeck::default);
+    let mut statistics = use_signal(|| Option::<RoundStatistics>::None);
+    let mut settings = use_signal(RoomSettings::default);
+    let mut auto_reveal_countdown = use_signal(|| Option::<u64>::None);
+    let mut timer = use_signal(|| Option::<u64>::None);
+    let mut stories = use_signal(StoryQueue::default);
+    let mut rounds = use_signal(Vec::<RoundRecord>::new);
+    let mut jira = use_signal(|| Option::<Arc<str>>::None);
+    let mut github = use_signal(|| Option::<Arc<str>>::None);
+    let mut webhook = use_signal(|| Option::<Arc<str>>::None);
+    let mut chat = use_signal(|| false);
+    let mut issues_pulled = use_signal(|| Option::<Result<usize, Arc<str>>>::None);
+    let mut notice = use_signal(|| Option::<Arc<str>>::None);
+    let is_facilitator = use_memo(move || {
+        participants
+            .read()
+            .get(&app_props().session_id)
+            .is_some_and(|p| p.is_facilitator())
+    });
+    let is_observer = use_memo(move || {
+        participants
+            .read()
+            .get(&app_props().session_id)
+            .is_some_and(|p| p.observer)
+    });
 
     use_drop(move || {
         let channel = app_props().channel;
@@ -36,7 +75,8 @@ pub fn App(props: AppProps) -> Element {
     });
 
     use_future(move || {
-        let participant = Participant::new(app_props().session_id, Arc::from(username()));
+        let mut participant = Participant::new(app_props().session_id, Arc::from(username()));
+        participant.observer = app_props().session.get("observer").unwrap_or(false);
         let add_participant = RoomRequest::Join(participant);
 
         async move {
@@ -45,11 +85,12 @@ pub fn App(props: AppProps) -> Element {
 
             match result {
                 Ok(response) => match response {
-                    RoomResponse::RoomState(participants_list, room_visibility) => {
-                        if let Some(my_participant) = participants_list.get(&app_props().session_id)
+                    RoomResponse::RoomState(room_state) => {
+                        if let Some(my_participant) =
+                            room_state.participants.get(&app_props().session_id)
                         {
-                            let index = i32::from(my_participant.estimate.clone());
-                            if index > -1 {
+                            if let Some(index) = room_state.deck.position(&my_participant.estimate)
+                            {
                                 let card_select_eval = document::eval(
                                     r#"
                                     let index = await dioxus.recv();
@@ -62,9 +103,22 @@ pub fn App(props: AppProps) -> Element {
                             }
                         }
 
-                        *participants.write() = participants_list;
+                        *participants.write() = room_state.participants;
 
-                        estimate_visibility.set(room_visibility);
+                        estimate_visibility.set(room_state.visibility);
+                        deck.set(room_state.deck);
+                        statistics.set(room_state.statistics);
+                        settings.set(room_state.settings);
+                        timer.set(room_state.timer);
+                        stories.set(room_state.stories);
+                        rounds.set(room_state.rounds);
+                        jira.set(room_state.jira);
+                        github.set(room_state.github);
+                        webhook.set(room_state.webhook);
+                        chat.set(room_state.chat);
+                    }
+                    other => {
+                        tracing::warn!("Unexpected join response {:?}", other);
                     }
                 },
                 Err(err) => {
@@ -92,25 +146,84 @@ pub fn App(props: AppProps) -> Element {
                             }
                         }
                         RoomBroadcastMessage::ChangedVisibility(v) => {
+                            auto_reveal_countdown.set(None);
+                            if !v.is_visible() {
+                                statistics.set(None);
+                            }
                             estimate_visibility.set(v);
                         }
+                        RoomBroadcastMessage::StatisticsUpdate(s) => {
+                            statistics.set(Some(s));
+                        }
                         RoomBroadcastMessage::EstimatesDeleted => {
                             for (_, p) in participants.write().iter_mut() {
                                 p.estimate = Estimate::None;
                             }
                             estimate_visibility.set(EstimateVisibility::Hidden);
-                            let _card_deselect = document::eval(
-                                r#"
-                                var cardInputs = document.getElementsByName("card-radio-input");
-                                for (var i = 0; i < cardInputs.length; i++) {
-                                    cardInputs[i].checked = false;
-                                }
-                            "#,
-                            );
+                            statistics.set(None);
+                            deselect_cards();
+                        }
+                        RoomBroadcastMessage::DeckChanged(new_deck) => {
+                            for (_, p) in participants.write().iter_mut() {
+                                p.estimate = Estimate::None;
+                            }
+                            estimate_visibility.set(EstimateVisibility::Hidden);
+                            statistics.set(None);
+                            deck.set(new_deck);
+                            deselect_cards();
+                        }
+                        RoomBroadcastMessage::SettingsChanged(s) => {
+                            settings.set(s);
+                        }
+                        RoomBroadcastMessage::AutoRevealCountdown(remaining) => {
+                            auto_reveal_countdown.set(Some(remaining));
+                        }
+                        RoomBroadcastMessage::AutoRevealCancelled => {
+                            auto_reveal_countdown.set(None);
+                        }
+                        RoomBroadcastMessage::TimerStarted(remaining)
+                        | RoomBroadcastMessage::TimerTick(remaining) => {
+                            timer.set(Some(remaining));
+                        }
+                        RoomBroadcastMessage::TimerExpired => {
+                            timer.set(Some(0));
+                        }
+                        RoomBroadcastMessage::TimerStopped => {
+                            timer.set(None);
+                        }
+                        RoomBroadcastMessage::StoriesChanged(s) => {
+                            stories.set(s);
+                        }
+                        RoomBroadcastMessage::RoundsChanged(r) => {
+                            rounds.set(r);
+                        }
+                        RoomBroadcastMessage::JiraChanged(base_url) => {
+                            jira.set(base_url);
+                        }
+                        RoomBroadcastMessage::GitHubChanged(repository) => {
+                            github.set(repository);
+                            issues_pulled.set(None);
+                        }
+                        RoomBroadcastMessage::WebhookChanged(url) => {
+                            webhook.set(url);
+                        }
+                        RoomBroadcastMessage::ChatChanged(enabled) => {
+                            chat.set(enabled);
+                        }
+                        RoomBroadcastMessage::IssuesPulled(result) => {
+                            issues_pulled.set(Some(result));
                         }
                         RoomBroadcastMessage::Left(session_id) => {
                             participants.write().remove(&session_id);
                         }
+                        RoomBroadcastMessage::Notice(message) => {
+                            notice.set(Some(message));
+                        }
+                        RoomBroadcastMessage::Closed => {
+                            notice.set(Some(Arc::from(
+                                "This room was closed. Reload the page to open it again.",
+                            )));
+                        }
                         RoomBroadcastMessage::RoomRequestedHeartbeat => {
                             _ = app_props()
                                 .channel
@@ -118,11 +231,18 @@ pub fn App(props: AppProps) -> Element {
                                 .await;
                         }
                     },
-                    Err(err) => tracing::info!(
-                        "Failed to get room event, room_id: {}, error {:?}",
-                        app_props().room_id,
-                        err
-                    ),
+                    Err(err) => {
+                        if let broadcast::error::RecvError::Lagged(_) = err {
+                            metrics::BROADCAST_LAGS
+                                .with_label_values(&["liveview"])
+                                .inc();
+                        }
+                        tracing::info!(
+                            "Failed to get room event, room_id: {}, error {:?}",
+                            app_props().room_id,
+                            err
+                        )
+                    }
                 }
             }
         }
@@ -144,26 +264,101 @@ pub fn App(props: AppProps) -> Element {
             div { class: "absolute inset-0 bg-[url({GRID_SVG_PATH})] bg-center [mask-image:linear-gradient(180deg,white,rgba(255,255,255,0))]" }
 
             div { class: "mx-auto max-w-4xl",
+                if let Some(notice) = notice() {
+                    div { class: "relative flex px-10 pb-6",
+                        p { class: "w-full rounded-lg bg-yellow-100 px-4 py-2 text-yellow-700 shadow-md",
+                            "{notice}"
+                        }
+                    }
+                }
                 div { class: "relative flex px-10",
                     Name { username }
                 }
-                div { class: "sm:mx-auto sm:max-w-4x px-10 sm:py-10",
-                    div { class: "divide-y divide-gray-300/50 ", Deck {} }
+                div { class: "relative flex px-10",
+                    ObserverToggle { is_observer }
+                }
+                if is_facilitator() {
+                    div { class: "relative flex flex-row-reverse flex-wrap px-10 pt-6 gap-4",
+                        DeckSettings { deck }
+                        AutoRevealSettings { settings }
+                        TimerControls { timer, settings }
+                        JiraSettings { jira }
+                        GitHubSettings { github, issues_pulled }
+                        WebhookSettings { webhook }
+                        ChatSettings {
+                            chat,
+                            participants,
+                            deck,
+                            stories,
+                        }
+                    }
+                }
+                div { class: "relative flex justify-center px-10 pt-6",
+                    RoundTimerDisplay { timer }
+                }
+                div { class: "sm:mx-auto sm:max-w-4x px-10 pt-6",
+                    CurrentStory { stories }
+                }
+                if !is_observer() {
+                    div { class: "sm:mx-auto sm:max-w-4x px-10 sm:py-10",
+                        div { class: "divide-y divide-gray-300/50 ", Deck { deck } }
+                    }
                 }
-                div { class: "relative flex px-10 pt-6 pb-0",
+                div { class: "relative flex items-center px-10 pt-6 pb-0 gap-x-4",
                     h1 { class: "text-slate-600 text-lg font-semibold", "Results" }
+                    AutoRevealCountdown { auto_reveal_countdown }
                 }
-                div { class: "relative flex flex-row-reverse px-11 py-5 gap-x-8 md:gap-x-28",
-                    ShowEstimatesButton { estimate_visibility }
-                    DeleteEstimatesButton { estimate_visibility, show_delete_modal }
+                if is_facilitator() {
+                    div { class: "relative flex flex-row-reverse px-11 py-5 gap-x-8 md:gap-x-28",
+                        ShowEstimatesButton { estimate_visibility }
+                        DeleteEstimatesButton { estimate_visibility, show_delete_modal }
+                    }
+                }
+                if is_facilitator() && estimate_visibility().is_visible() {
+                    div { class: "m:mx-auto sm:max-w-4x px-10 pt-5",
+                        FinalEstimatePicker { deck }
+                    }
+                    div { class: "m:mx-auto sm:max-w-4x px-10 pt-5",
+                        IssueKeyInput { rounds }
+                    }
+                }
+                if let Some(statistics) = statistics() {
+                    div { class: "m:mx-auto sm:max-w-4x px-10 pt-5",
+                        StatisticsPanel { statistics }
+                    }
                 }
                 div { class: "m:mx-auto sm:max-w-4x px-10 sm:py-10",
                     div { class: "relative flex overflow-x-auto shadow-md rounded-lg",
-                        Table { participants, estimate_visibility }
+                        Table {
+                            participants,
+                            estimate_visibility,
+                            deck,
+                            is_facilitator,
+                        }
                     }
                 }
+                div { class: "m:mx-auto sm:max-w-4x px-10 pb-10",
+                    StoryQueuePanel { stories, is_facilitator }
+                }
+                div { class: "m:mx-auto sm:max-w-4x px-10 pb-10",
+                    FinalizedStories { rounds, is_facilitator }
+                }
+                div { class: "m:mx-auto sm:max-w-4x px-10 pb-10",
+                    PastRounds { rounds }
+                }
                 DeleteEstimatesModal { show_modal: show_delete_modal }
             }
         }
     }
 }
+
+fn deselect_cards() {
+    let _card_deselect = document::eval(
+        r#"
+        var cardInputs = document.getElementsByName("card-radio-input");
+        for (var i = 0; i < cardInputs.length; i++) {
+            cardInputs[i].checked = false;
+        }
+    "#,
+    );
+}
@@ -1,36 +1,241 @@
-use crate::{error::ScError, estimate::Estimate, room::Participant};
+use crate::{
+    error::ScError,
+    estimate::{CardDeck, Estimate},
+    github::GitHubConfig,
+    jira::JiraConfig,
+    metrics,
+    room::{Participant, RoomSettings},
+    round::{RoundRecord, SyncStatus},
+    statistics::RoundStatistics,
+    story::{Story, StoryId, StoryQueue},
+    webhook::WebhookConfig,
+};
+use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, sync::Arc};
 use tokio::sync::{broadcast, mpsc, oneshot};
 use uuid::Uuid;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum RoomRequest {
     Join(Participant),
     Leave(Uuid),
     Remove(Uuid),
     SendEstimate(Uuid, Estimate),
-    ChangeVisibility,
-    DeleteEstimates,
+    ChangeVisibility(Uuid),
+    SetVisibility(Uuid, EstimateVisibility),
+    DeleteEstimates(Uuid),
     Heartbeat(Uuid),
     NameChange(Uuid, Arc<str>),
+    ChangeObserver(Uuid, bool),
+    TransferFacilitator(Uuid, Uuid),
+    ChangeDeck(Uuid, CardDeck),
+    ChangeSettings(Uuid, RoomSettings),
+    AutoReveal,
+    StartTimer(Uuid, u64),
+    StopTimer(Uuid),
+    TimerExpired,
+    AddStory(Uuid, Story),
+    ImportStories(Uuid, Vec<Story>),
+    RemoveStory(Uuid, StoryId),
+    MoveStory(Uuid, StoryId, usize),
+    SelectStory(Uuid, StoryId),
+    NextStory(Uuid),
+    FinalizeEstimate(Uuid, Estimate),
+    AttachIssueKey(Uuid, Arc<str>),
+    ChangeJiraConfig(Uuid, Option<JiraConfig>),
+    ChangeGitHubConfig(Uuid, Option<Box<GitHubConfig>>),
+    ChangeWebhook(Uuid, Option<Box<WebhookConfig>>),
+    ChangeChatWebhook(Uuid, Option<Arc<str>>),
+    /// Adds the open issues of the connected GitHub repository to the story queue.
+    PullIssues(Uuid),
+    IssuesPulled(Result<Vec<Story>, Arc<str>>),
+    /// Retries writing the final estimate of the round with the given id to its issue.
+    SyncEstimate(Uuid, Arc<str>),
+    SyncFinished(Arc<str>, SyncStatus),
+    /// Current state of the room, without joining it.
+    State,
 }
 
-#[derive(Clone, Debug)]
+impl RoomRequest {
+    /// Name of the variant, used as metric label.
+    pub fn name(&self) -> &'static str {
+        match self {
+            RoomRequest::Join(_) => "join",
+            RoomRequest::Leave(_) => "leave",
+            RoomRequest::Remove(_) => "remove",
+            RoomRequest::SendEstimate(_, _) => "send_estimate",
+            RoomRequest::ChangeVisibility(_) => "change_visibility",
+            RoomRequest::SetVisibility(_, _) => "set_visibility",
+            RoomRequest::DeleteEstimates(_) => "delete_estimates",
+            RoomRequest::Heartbeat(_) => "heartbeat",
+            RoomRequest::NameChange(_, _) => "name_change",
+            RoomRequest::ChangeObserver(_, _) => "change_observer",
+            RoomRequest::TransferFacilitator(_, _) => "transfer_facilitator",
+            RoomRequest::ChangeDeck(_, _) => "change_deck",
+            RoomRequest::ChangeSettings(_, _) => "change_settings",
+            RoomRequest::AutoReveal => "auto_reveal",
+            RoomRequest::StartTimer(_, _) => "start_timer",
+            RoomRequest::StopTimer(_) => "stop_timer",
+            RoomRequest::TimerExpired => "timer_expired",
+            RoomRequest::AddStory(_, _) => "add_story",
+            RoomRequest::ImportStories(_, _) => "import_stories",
+            RoomRequest::RemoveStory(_, _) => "remove_story",
+            RoomRequest::MoveStory(_, _, _) => "move_story",
+            RoomRequest::SelectStory(_, _) => "select_story",
+            RoomRequest::NextStory(_) => "next_story",
+            RoomRequest::FinalizeEstimate(_, _) => "finalize_estimate",
+            RoomRequest::AttachIssueKey(_, _) => "attach_issue_key",
+            RoomRequest::ChangeJiraConfig(_, _) => "change_jira_config",
+            RoomRequest::ChangeGitHubConfig(_, _) => "change_github_config",
+            RoomRequest::ChangeWebhook(_, _) => "change_webhook",
+            RoomRequest::ChangeChatWebhook(_, _) => "change_chat_webhook",
+            RoomRequest::PullIssues(_) => "pull_issues",
+            RoomRequest::IssuesPulled(_) => "issues_pulled",
+            RoomRequest::SyncEstimate(_, _) => "sync_estimate",
+            RoomRequest::SyncFinished(_, _) => "sync_finished",
+            RoomRequest::State => "state",
+        }
+    }
+
+    /// Session id of the sender for requests only the facilitator may send.
+    pub fn facilitator_session_id(&self) -> Option<Uuid> {
+        match self {
+            RoomRequest::ChangeVisibility(session_id)
+            | RoomRequest::SetVisibility(session_id, _)
+            | RoomRequest::DeleteEstimates(session_id)
+            | RoomRequest::TransferFacilitator(session_id, _)
+            | RoomRequest::ChangeDeck(session_id, _)
+            | RoomRequest::ChangeSettings(session_id, _)
+            | RoomRequest::StartTimer(session_id, _)
+            | RoomRequest::StopTimer(session_id)
+            | RoomRequest::AddStory(session_id, _)
+            | RoomRequest::ImportStories(session_id, _)
+            | RoomRequest::RemoveStory(session_id, _)
+            | RoomRequest::MoveStory(session_id, _, _)
+            | RoomRequest::SelectStory(session_id, _)
+            | RoomRequest::NextStory(session_id)
+            | RoomRequest::FinalizeEstimate(session_id, _)
+            | RoomRequest::AttachIssueKey(session_id, _)
+            | RoomRequest::ChangeJiraConfig(session_id, _)
+            | RoomRequest::ChangeGitHubConfig(session_id, _)
+            | RoomRequest::ChangeWebhook(session_id, _)
+            | RoomRequest::ChangeChatWebhook(session_id, _)
+            | RoomRequest::PullIssues(session_id)
+            | RoomRequest::SyncEstimate(session_id, _) => Some(*session_id),
+            _ => None,
+        }
+    }
+
+    /// Requests with credentials of an integration, which are not forwarded to a room another
+    /// instance runs. Disconnecting an integration carries none.
+    pub fn carries_credentials(&self) -> bool {
+        match self {
+            RoomRequest::ChangeJiraConfig(_, config) => config.is_some(),
+            RoomRequest::ChangeGitHubConfig(_, config) => config.is_some(),
+            RoomRequest::ChangeWebhook(_, config) => config.is_some(),
+            RoomRequest::ChangeChatWebhook(_, url) => url.is_some(),
+            _ => false,
+        }
+    }
+
+    /// Session id of the participant that sent the request.
+    pub fn session_id(&self) -> Option<Uuid> {
+        match self {
+            RoomRequest::SendEstimate(session_id, _)
+            | RoomRequest::Heartbeat(session_id)
+            | RoomRequest::NameChange(session_id, _)
+            | RoomRequest::ChangeObserver(session_id, _) => Some(*session_id),
+            _ => self.facilitator_session_id(),
+        }
+    }
+
+    /// Requests a participant made, as opposed to heartbeats, reads and the room's own tasks.
+    pub fn is_activity(&self) -> bool {
+        !matches!(
+            self,
+            RoomRequest::Heartbeat(_)
+                | RoomRequest::AutoReveal
+                | RoomRequest::TimerExpired
+                | RoomRequest::IssuesPulled(_)
+                | RoomRequest::SyncFinished(_, _)
+                | RoomRequest::State
+        )
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RoomState {
+    pub participants: HashMap<Uuid, Participant>,
+    pub visibility: EstimateVisibility,
+    pub deck: CardDeck,
+    pub statistics: Option<RoundStatistics>,
+    pub settings: RoomSettings,
+    /// Seconds left on the round timer, if one is running.
+    pub timer: Option<u64>,
+    pub stories: StoryQueue,
+    pub rounds: Vec<RoundRecord>,
+    /// Base URL of the Jira instance final estimates are written to.
+    pub jira: Option<Arc<str>>,
+    /// `owner/repo` of the connected GitHub repository.
+    pub github: Option<Arc<str>>,
+    /// URL of the webhook configured for the room.
+    pub webhook: Option<Arc<str>>,
+    /// Revealed rounds are posted to a chat channel.
+    pub chat: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum RoomResponse {
-    RoomState(HashMap<Uuid, Participant>, EstimateVisibility),
+    RoomState(Box<RoomState>),
+    /// The request was handled, it has nothing to reply.
+    Accepted,
+    PermissionDenied,
+    Rejected(Rejection),
 }
 
-#[derive(Clone, Debug)]
+/// Why the room turned down a request it otherwise allows.
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub enum Rejection {
+    NotInRoom,
+    Observer,
+    NotInDeck(Estimate),
+    /// The room runs on another instance, which credentials are not sent to.
+    NotForwarded,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum RoomBroadcastMessage {
     Joined(Participant),
     ParticipantUpdate(Participant),
     ChangedVisibility(EstimateVisibility),
+    StatisticsUpdate(RoundStatistics),
     EstimatesDeleted,
+    DeckChanged(CardDeck),
+    SettingsChanged(RoomSettings),
+    AutoRevealCountdown(u64),
+    AutoRevealCancelled,
+    TimerStarted(u64),
+    TimerTick(u64),
+    TimerExpired,
+    TimerStopped,
+    StoriesChanged(StoryQueue),
+    RoundsChanged(Vec<RoundRecord>),
+    JiraChanged(Option<Arc<str>>),
+    GitHubChanged(Option<Arc<str>>),
+    WebhookChanged(Option<Arc<str>>),
+    ChatChanged(bool),
+    /// Number of issues added to the queue, or why pulling them failed.
+    IssuesPulled(Result<usize, Arc<str>>),
     Left(Uuid),
     RoomRequestedHeartbeat,
+    /// Notice of an operator, e.g. about upcoming maintenance.
+    Notice(Arc<str>),
+    /// An operator closed the room or it moved to another instance, it no longer answers
+    /// requests here.
+    Closed,
 }
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub enum EstimateVisibility {
     Visible,
     Hidden,
@@ -59,6 +264,7 @@ pub struct RoomChannel {
 
 impl RoomChannel {
     pub async fn send(&self, msg: RoomRequest) -> Result<RoomResponse, ScError> {
+        let _timer = metrics::ROOM_REQUEST_DURATION.start_timer();
         let handle: tokio::task::JoinHandle<Result<RoomResponse, ScError>> =
             self.spawn_send(msg).await;
 
@@ -81,7 +287,7 @@ impl RoomChannel {
                     Err(err) => Err(ScError::OneshotRecieveError(err)),
                 },
                 Err(err) => {
-                    tracing::error!("Error sending message: {:?}, error: {}", msg, err);
+                    tracing::error!("Error sending message: {}, error: {}", msg.name(), err);
                     Err(ScError::RoomMessageSendError(err))
                 }
             }
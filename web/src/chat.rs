@@ -0,0 +1,166 @@
+use crate::{
+    connector::{self, Retry},
+    error::ScError,
+    estimate::CardDeck,
+    room::{Participant, RoomId},
+    round,
+    statistics::{Consensus, RoundStatistics},
+    story::Story,
+};
+use itertools::Itertools;
+use serde_json::json;
+use std::{collections::HashMap, env, sync::Arc};
+use uuid::Uuid;
+
+/// Validates the incoming webhook URL of a Slack or Mattermost channel.
+pub fn webhook_url(url: &str) -> Option<Arc<str>> {
+    let url = url.trim();
+    connector::public_url(url).map(|_| Arc::from(url))
+}
+
+/// Public address of the room, from `PUBLIC_URL` or else the http flavour of `WS_ADDRESS`.
+pub fn room_url(room_id: &RoomId) -> String {
+    let base_url = env::var("PUBLIC_URL").unwrap_or_else(|_| {
+        env::var("WS_ADDRESS")
+            .unwrap_or("ws://127.0.0.1:3030".into())
+            .replacen("ws", "http", 1)
+    });
+    format!("{}/{}", base_url.trim_end_matches('/'), room_id)
+}
+
+/// Slack and Mattermost treat these as markup in message text.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn number(value: f64) -> String {
+    format!("{value:.1}")
+}
+
+/// Compact summary of a revealed round in the markup Slack and Mattermost share.
+pub fn summary(
+    room_id: &RoomId,
+    story: Option<&Story>,
+    participants: &HashMap<Uuid, Participant>,
+    deck: &CardDeck,
+) -> String {
+    let votes = round::votes(participants, deck);
+    let statistics = RoundStatistics::new(participants, deck);
+    let mut lines = vec![format!(
+        "*Estimates revealed* in <{}|room {}>",
+        room_url(room_id),
+        room_id
+    )];
+
+    if let Some(story) = story {
+        let title = match &story.key {
+            Some(key) => format!("{} {}", key, story.title),
+            None => story.title.to_string(),
+        };
+        lines.push(match &story.link {
+            Some(link) => format!("<{}|{}>", link, escape(&title)),
+            None => escape(&title),
+        });
+    }
+
+    if votes.is_empty() {
+        lines.push("No votes".to_string());
+        return lines.join("\n");
+    }
+    lines.push(format!(
+        "Votes: {}",
+        votes
+            .iter()
+            .map(|vote| format!("{} *{}*", escape(&vote.name), vote.estimate))
+            .join(", ")
+    ));
+    lines.push(format!(
+        "Distribution: {}",
+        votes
+            .iter()
+            .dedup_by_with_count(|a, b| a.estimate == b.estimate)
+            .map(|(count, vote)| format!("{} ×{}", vote.estimate, count))
+            .join(" · ")
+    ));
+
+    let mut spread = Vec::new();
+    match (&statistics.min, &statistics.max) {
+        (Some(min), Some(max)) if min != max => spread.push(format!("Spread {min}–{max}")),
+        (Some(min), Some(_)) => spread.push(format!("Spread {min}")),
+        _ => {}
+    }
+    if let Some(average) = statistics.average {
+        spread.push(format!("Average {}", number(average)));
+    }
+    if let Some(median) = statistics.median {
+        spread.push(format!("Median {}", number(median)));
+    }
+    match statistics.consensus {
+        Consensus::Unanimous => spread.push("Unanimous".to_string()),
+        Consensus::Close => spread.push("Close".to_string()),
+        Consensus::Split => spread.push("Split".to_string()),
+        Consensus::NoVotes => {}
+    }
+    if !spread.is_empty() {
+        lines.push(spread.join(" · "));
+    }
+    lines.join("\n")
+}
+
+/// Posts the message with the `{"text": ...}` payload of incoming webhooks.
+pub async fn post(webhook_url: &str, text: &str) -> Result<(), ScError> {
+    let http = connector::http_client();
+    let body = json!({ "text": text });
+    Retry::default()
+        .send(|| http.post(webhook_url).json(&body))
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarizes_revealed_round() {
+        let participants: HashMap<Uuid, Participant> =
+            [("Bob", "8"), ("Alice", "5"), ("Carol", "5")]
+                .into_iter()
+                .enumerate()
+                .map(|(i, (name, estimate))| {
+                    let mut participant =
+                        Participant::new(Uuid::from_u128(i as u128), Arc::from(name));
+                    participant.estimate = estimate.into();
+                    (participant.session_id, participant)
+                })
+                .collect();
+        let story = Story::new(
+            "Checkout <v2>",
+            "https://jira.example.com/browse/SHOP-1",
+            "",
+        )
+        .unwrap()
+        .with_key("SHOP-1");
+
+        let summary = summary(
+            &Arc::from("abc123"),
+            Some(&story),
+            &participants,
+            &CardDeck::ModifiedFibonacci,
+        );
+        let lines: Vec<&str> = summary.lines().collect();
+        assert!(lines[0].starts_with("*Estimates revealed* in <http"));
+        assert!(lines[0].ends_with("/abc123|room abc123>"));
+        assert_eq!(
+            lines[1..],
+            [
+                "<https://jira.example.com/browse/SHOP-1|SHOP-1 Checkout &lt;v2&gt;>",
+                "Votes: Alice *5*, Carol *5*, Bob *8*",
+                "Distribution: 5 ×2 · 8 ×1",
+                "Spread 5–8 · Average 6.0 · Median 5.0 · Close",
+            ]
+        );
+    }
+}
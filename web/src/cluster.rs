@@ -0,0 +1,816 @@
+use crate::{
+    channel::{
+        Rejection, RoomBroadcastMessage, RoomChannel, RoomMessage, RoomRequest, RoomResponse,
+    },
+    database::{self, Pool},
+    error::ScError,
+    metrics,
+    room::RoomId,
+    room_pool::{self, CtrlMessage, CtrlRequest, RoomPoolChannel},
+};
+use futures::{future::BoxFuture, stream::BoxStream, FutureExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, env, sync::Arc, time::Duration};
+use surrealdb::{Action, Notification};
+use tokio::sync::{broadcast, mpsc, oneshot};
+use uuid::Uuid;
+
+/// How long a room stays with its owner unless the owner renews the lease.
+const LEASE: Duration = Duration::from_secs(15);
+/// How often an instance standing in for a room checks that the owner is still around.
+const OWNER_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// Time the owner gets to answer a forwarded request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+const OWNER_TABLE: &str = "room_owner";
+const MESSAGE_TABLE: &str = "cluster_message";
+/// Messages are only delivered live, older ones are pruned. Requests with the credentials of
+/// integrations are not forwarded, the messages are stored in plain text.
+const MESSAGE_TTL: Duration = Duration::from_secs(60);
+
+pub type InstanceId = Arc<str>;
+
+/// Coordinates the instances of a deployment: which instance owns a room, and topics the
+/// instances exchange messages on.
+pub trait ClusterBus: Send + Sync {
+    /// Makes `instance` the owner of the room for the lease, unless another instance holds a
+    /// lease that has not expired. Returns the owner, owners renew their lease by claiming again.
+    fn claim<'a>(
+        &'a self,
+        room_id: &'a RoomId,
+        instance: &'a InstanceId,
+        lease: Duration,
+    ) -> BoxFuture<'a, Result<InstanceId, ScError>>;
+
+    /// Owner of the room, `None` once its lease expired.
+    fn owner<'a>(
+        &'a self,
+        room_id: &'a RoomId,
+    ) -> BoxFuture<'a, Result<Option<InstanceId>, ScError>>;
+
+    /// Gives up the room, if `instance` still owns it.
+    fn release<'a>(
+        &'a self,
+        room_id: &'a RoomId,
+        instance: &'a InstanceId,
+    ) -> BoxFuture<'a, Result<(), ScError>>;
+
+    fn publish<'a>(&'a self, topic: &'a str, payload: String)
+        -> BoxFuture<'a, Result<(), ScError>>;
+
+    /// Messages published to the topic from now on.
+    fn subscribe<'a>(
+        &'a self,
+        topic: &'a str,
+    ) -> BoxFuture<'a, Result<BoxStream<'static, String>, ScError>>;
+}
+
+#[derive(Deserialize)]
+struct MessageRecord {
+    payload: String,
+}
+
+/// Bus on the database all instances share. Leases are records of `room_owner`, messages are
+/// created in `cluster_message` and delivered by live queries, which need a websocket connection
+/// to the database.
+pub struct SurrealBus {
+    pool: Arc<Pool>,
+}
+
+impl SurrealBus {
+    pub fn new(pool: Arc<Pool>) -> SurrealBus {
+        let prune_pool = pool.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(MESSAGE_TTL);
+            loop {
+                interval.tick().await;
+                if let Err(err) = prune_messages(&prune_pool).await {
+                    tracing::warn!("Failed to prune cluster messages, error: {}", err);
+                }
+            }
+        });
+        SurrealBus { pool }
+    }
+}
+
+pub async fn init(pool: &Pool) -> Result<(), ScError> {
+    let db = database::connection(pool).await?;
+    db.query(format!(
+        "DEFINE TABLE IF NOT EXISTS {OWNER_TABLE} SCHEMALESS;
+         DEFINE TABLE IF NOT EXISTS {MESSAGE_TABLE} SCHEMALESS;
+         DEFINE INDEX IF NOT EXISTS {MESSAGE_TABLE}_topic ON {MESSAGE_TABLE} FIELDS topic;"
+    ))
+    .await?;
+    Ok(())
+}
+
+async fn prune_messages(pool: &Pool) -> Result<(), ScError> {
+    let db = database::connection(pool).await?;
+    db.query("DELETE type::table($table) WHERE created_at < time::now() - type::duration($ttl)")
+        .bind(("table", MESSAGE_TABLE))
+        .bind(("ttl", format!("{}s", MESSAGE_TTL.as_secs())))
+        .await?;
+    Ok(())
+}
+
+impl ClusterBus for SurrealBus {
+    fn claim<'a>(
+        &'a self,
+        room_id: &'a RoomId,
+        instance: &'a InstanceId,
+        lease: Duration,
+    ) -> BoxFuture<'a, Result<InstanceId, ScError>> {
+        async move {
+            let db = database::connection(&self.pool).await?;
+            let mut response = db
+                .query(
+                    "UPSERT type::thing($table, $room_id)
+                         SET owner = $instance, expires_at = time::now() + type::duration($lease)
+                         WHERE owner = NONE OR owner = $instance OR expires_at < time::now();
+                     SELECT VALUE owner FROM type::thing($table, $room_id);",
+                )
+                .bind(("table", OWNER_TABLE))
+                .bind(("room_id", room_id.clone()))
+                .bind(("instance", instance.clone()))
+                .bind(("lease", format!("{}ms", lease.as_millis())))
+                .await?;
+            let owner: Option<InstanceId> = response.take(1)?;
+            owner.ok_or(ScError::UnexpectedResponse)
+        }
+        .boxed()
+    }
+
+    fn owner<'a>(
+        &'a self,
+        room_id: &'a RoomId,
+    ) -> BoxFuture<'a, Result<Option<InstanceId>, ScError>> {
+        async move {
+            let db = database::connection(&self.pool).await?;
+            let mut response = db
+                .query(
+                    "SELECT VALUE owner FROM type::thing($table, $room_id)
+                     WHERE expires_at > time::now()",
+                )
+                .bind(("table", OWNER_TABLE))
+                .bind(("room_id", room_id.clone()))
+                .await?;
+            Ok(response.take(0)?)
+        }
+        .boxed()
+    }
+
+    fn release<'a>(
+        &'a self,
+        room_id: &'a RoomId,
+        instance: &'a InstanceId,
+    ) -> BoxFuture<'a, Result<(), ScError>> {
+        async move {
+            let db = database::connection(&self.pool).await?;
+            db.query("DELETE type::thing($table, $room_id) WHERE owner = $instance")
+                .bind(("table", OWNER_TABLE))
+                .bind(("room_id", room_id.clone()))
+                .bind(("instance", instance.clone()))
+                .await?;
+            Ok(())
+        }
+        .boxed()
+    }
+
+    fn publish<'a>(
+        &'a self,
+        topic: &'a str,
+        payload: String,
+    ) -> BoxFuture<'a, Result<(), ScError>> {
+        async move {
+            let db = database::connection(&self.pool).await?;
+            db.query(
+                "CREATE type::table($table)
+                 CONTENT { topic: $topic, payload: $payload, created_at: time::now() }",
+            )
+            .bind(("table", MESSAGE_TABLE))
+            .bind(("topic", topic.to_owned()))
+            .bind(("payload", payload))
+            .await?;
+            Ok(())
+        }
+        .boxed()
+    }
+
+    fn subscribe<'a>(
+        &'a self,
+        topic: &'a str,
+    ) -> BoxFuture<'a, Result<BoxStream<'static, String>, ScError>> {
+        async move {
+            let db = database::connection(&self.pool).await?;
+            let mut response = db
+                .query(format!(
+                    "LIVE SELECT * FROM {MESSAGE_TABLE} WHERE topic = $topic"
+                ))
+                .bind(("topic", topic.to_owned()))
+                .await?;
+            let notifications = response.stream::<Notification<MessageRecord>>(0)?;
+            let messages = notifications.filter_map(|notification| async move {
+                match notification {
+                    Ok(notification) if matches!(notification.action, Action::Create) => {
+                        Some(notification.data.payload)
+                    }
+                    Ok(_) => None,
+                    Err(err) => {
+                        tracing::warn!("Failed to receive cluster message, error: {}", err);
+                        None
+                    }
+                }
+            });
+            Ok(messages.boxed())
+        }
+        .boxed()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClusterMessage {
+    Request {
+        id: Uuid,
+        reply_to: String,
+        request: RoomRequest,
+    },
+    Response {
+        id: Uuid,
+        response: RoomResponse,
+    },
+    Broadcast {
+        message: RoomBroadcastMessage,
+    },
+}
+
+fn requests_topic(room_id: &RoomId) -> String {
+    format!("room:{room_id}:requests")
+}
+
+fn events_topic(room_id: &RoomId) -> String {
+    format!("room:{room_id}:events")
+}
+
+/// Instances of a deployment sharing their rooms. A room runs on the instance that claimed it,
+/// the other instances stand in for it: they forward the requests of their participants to the
+/// owner and rebroadcast the messages of the room, so every participant sees the same room.
+#[derive(Clone)]
+pub struct Cluster {
+    bus: Arc<dyn ClusterBus>,
+    instance: InstanceId,
+}
+
+impl Cluster {
+    pub fn new(bus: Arc<dyn ClusterBus>, instance: InstanceId) -> Cluster {
+        Cluster { bus, instance }
+    }
+
+    /// Clustering over the database when `CLUSTER` is `surreal`, off when it is not set. Fly
+    /// machines are named by `FLY_MACHINE_ID`, other instances get a random name.
+    pub fn from_env(pool: Arc<Pool>) -> Option<Cluster> {
+        match env::var("CLUSTER").unwrap_or_default().trim() {
+            "" => None,
+            "surreal" => {
+                let instance = env::var("FLY_MACHINE_ID")
+                    .map(Arc::from)
+                    .unwrap_or_else(|_| Arc::from(nanoid::nanoid!(12)));
+                tracing::info!("Clustering over the database as instance {}", instance);
+                Some(Cluster::new(Arc::new(SurrealBus::new(pool)), instance))
+            }
+            backend => {
+                tracing::warn!(
+                    "Unknown CLUSTER backend {}, clustering is disabled",
+                    backend
+                );
+                None
+            }
+        }
+    }
+
+    pub fn instance(&self) -> &InstanceId {
+        &self.instance
+    }
+
+    /// Owner of the room, which is this instance unless another one runs the room already.
+    pub async fn claim(&self, room_id: &RoomId) -> Result<InstanceId, ScError> {
+        self.bus.claim(room_id, &self.instance, LEASE).await
+    }
+
+    async fn publish(&self, topic: &str, message: &ClusterMessage) {
+        let result = match serde_json::to_string(message) {
+            Ok(payload) => self.bus.publish(topic, payload).await,
+            Err(err) => Err(err.into()),
+        };
+        if let Err(err) = result {
+            tracing::error!("Failed to publish to {}, error: {}", topic, err);
+        }
+    }
+
+    /// Answers the requests other instances forward to a room this instance owns and publishes
+    /// its broadcasts. Renews the lease until the room shuts down, and hands the room over if
+    /// another instance took it meanwhile, or when the requests stop arriving because the
+    /// subscription ended.
+    ///
+    /// The lease is renewed by a task of its own, so slow publishing does not let it expire.
+    pub async fn serve(
+        &self,
+        room_id: RoomId,
+        channel: RoomChannel,
+        ctrl: mpsc::Sender<CtrlMessage>,
+    ) -> Result<(), ScError> {
+        let mut requests = self.bus.subscribe(&requests_topic(&room_id)).await?;
+        let mut broadcasts = channel.broadcast.subscribe();
+        let renewal = tokio::spawn(self.clone().renew(
+            room_id.clone(),
+            channel.tx.clone(),
+            ctrl.clone(),
+        ));
+        let cluster = self.clone();
+        tokio::spawn(async move {
+            let events = events_topic(&room_id);
+            loop {
+                tokio::select! {
+                    biased;
+                    message = broadcasts.recv() => match message {
+                        Ok(message) => {
+                            cluster.publish(&events, &ClusterMessage::Broadcast { message }).await;
+                        }
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            metrics::BROADCAST_LAGS.with_label_values(&["cluster"]).inc();
+                            tracing::warn!(
+                                "Cluster missed {} messages of room {}",
+                                skipped,
+                                room_id
+                            );
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                    payload = requests.next() => {
+                        let Some(payload) = payload else {
+                            tracing::error!("Requests to room {} stopped arriving", room_id);
+                            renewal.abort();
+                            _ = room_pool::control(&ctrl, CtrlRequest::Handover).await;
+                            break;
+                        };
+                        let (id, reply_to, request) = match serde_json::from_str(&payload) {
+                            Ok(ClusterMessage::Request { id, reply_to, request }) => {
+                                (id, reply_to, request)
+                            }
+                            Ok(_) => continue,
+                            Err(err) => {
+                                tracing::warn!("Failed to decode cluster request, error: {}", err);
+                                continue;
+                            }
+                        };
+                        match channel.send(request).await {
+                            Ok(response) => {
+                                let reply = ClusterMessage::Response { id, response };
+                                cluster.publish(&reply_to, &reply).await;
+                            }
+                            Err(err) => tracing::warn!(
+                                "Room {} did not answer forwarded request, error: {}",
+                                room_id,
+                                err
+                            ),
+                        }
+                    }
+                    _ = channel.tx.closed() => break,
+                }
+            }
+            renewal.abort();
+            if let Err(err) = cluster.bus.release(&room_id, &cluster.instance).await {
+                tracing::warn!("Failed to release room {}, error: {}", room_id, err);
+            }
+        });
+        Ok(())
+    }
+
+    /// Claims the room again every third of the lease while it runs.
+    async fn renew(
+        self,
+        room_id: RoomId,
+        room_tx: mpsc::Sender<RoomMessage>,
+        ctrl: mpsc::Sender<CtrlMessage>,
+    ) {
+        let mut renew = tokio::time::interval(LEASE / 3);
+        renew.tick().await;
+        loop {
+            tokio::select! {
+                _ = renew.tick() => match self.claim(&room_id).await {
+                    Ok(owner) if owner == self.instance => {}
+                    Ok(owner) => {
+                        tracing::error!("Instance {} took over room {}", owner, room_id);
+                        _ = room_pool::control(&ctrl, CtrlRequest::Handover).await;
+                        return;
+                    }
+                    Err(err) => tracing::warn!(
+                        "Failed to renew lease of room {}, error: {}",
+                        room_id,
+                        err
+                    ),
+                },
+                _ = room_tx.closed() => return,
+            }
+        }
+    }
+
+    /// Stands in for a room another instance owns. Ends once no participant here listens, or
+    /// when the owner is gone, which closes the room for the participants here so they come back
+    /// to whichever instance runs it next. Requests wait for their replies side by side, matched
+    /// by their id.
+    pub async fn proxy(
+        &self,
+        room_id: RoomId,
+        owner: InstanceId,
+        mut room_rx: mpsc::Receiver<RoomMessage>,
+        broadcast: broadcast::Sender<RoomBroadcastMessage>,
+        room_pool: RoomPoolChannel,
+    ) -> Result<(), ScError> {
+        let reply_to = format!("room:{room_id}:replies:{}", self.instance);
+        let mut replies = self.bus.subscribe(&reply_to).await?;
+        let mut events = self.bus.subscribe(&events_topic(&room_id)).await?;
+
+        let relay_tx = broadcast.clone();
+        let relay = tokio::spawn(async move {
+            while let Some(payload) = events.next().await {
+                match serde_json::from_str(&payload) {
+                    Ok(ClusterMessage::Broadcast { message }) => {
+                        _ = relay_tx.send(message);
+                    }
+                    Ok(_) => {}
+                    Err(err) => tracing::warn!("Failed to decode cluster event, error: {}", err),
+                }
+            }
+        });
+
+        let cluster = self.clone();
+        tokio::spawn(async move {
+            let requests = requests_topic(&room_id);
+            let mut pending: HashMap<Uuid, oneshot::Sender<RoomResponse>> = HashMap::new();
+            let (expired_tx, mut expired_rx) = mpsc::unbounded_channel::<Uuid>();
+            let mut check = tokio::time::interval(OWNER_CHECK_INTERVAL);
+            check.tick().await;
+            loop {
+                tokio::select! {
+                    Some((request, response)) = room_rx.recv() => {
+                        if request.carries_credentials() {
+                            _ = response.send(RoomResponse::Rejected(Rejection::NotForwarded));
+                            continue;
+                        }
+                        let id = Uuid::new_v4();
+                        let request = ClusterMessage::Request {
+                            id,
+                            reply_to: reply_to.clone(),
+                            request,
+                        };
+                        pending.insert(id, response);
+                        let expired_tx = expired_tx.clone();
+                        tokio::spawn(async move {
+                            tokio::time::sleep(REQUEST_TIMEOUT).await;
+                            _ = expired_tx.send(id);
+                        });
+                        cluster.publish(&requests, &request).await;
+                    }
+                    payload = replies.next() => match payload.map(|p| serde_json::from_str(&p)) {
+                        Some(Ok(ClusterMessage::Response { id, response })) => {
+                            if let Some(tx) = pending.remove(&id) {
+                                _ = tx.send(response);
+                            }
+                        }
+                        Some(Ok(_)) => {}
+                        Some(Err(err)) => {
+                            tracing::warn!("Failed to decode cluster reply, error: {}", err);
+                        }
+                        None => {
+                            _ = broadcast.send(RoomBroadcastMessage::Closed);
+                            break;
+                        }
+                    },
+                    Some(id) = expired_rx.recv() => {
+                        if pending.remove(&id).is_some() {
+                            tracing::warn!(
+                                "Instance {} did not answer request to room {}",
+                                owner,
+                                room_id
+                            );
+                        }
+                    }
+                    _ = check.tick() => {
+                        if broadcast.receiver_count() == 0 {
+                            break;
+                        }
+                        let gone = match cluster.bus.owner(&room_id).await {
+                            Ok(current) => current.as_ref() != Some(&owner),
+                            Err(err) => {
+                                tracing::warn!(
+                                    "Failed to look up owner of room {}, error: {}",
+                                    room_id,
+                                    err
+                                );
+                                false
+                            }
+                        };
+                        if gone || relay.is_finished() {
+                            tracing::info!("Room {} left instance {}", room_id, owner);
+                            _ = broadcast.send(RoomBroadcastMessage::Closed);
+                            break;
+                        }
+                    }
+                }
+            }
+            relay.abort();
+            _ = room_pool.shutdown(&room_id).await;
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::room_pool::CtrlResponse;
+    use futures::{future, stream};
+    use std::{collections::HashMap, sync::Mutex, time::Instant};
+    use tokio::time::timeout;
+
+    const BUFFER_SIZE: usize = 256;
+
+    /// Bus between instances running in the same process.
+    #[derive(Default)]
+    struct MemoryBus {
+        owners: Mutex<HashMap<RoomId, (InstanceId, Instant)>>,
+        topics: Mutex<HashMap<String, broadcast::Sender<String>>>,
+    }
+
+    impl ClusterBus for MemoryBus {
+        fn claim<'a>(
+            &'a self,
+            room_id: &'a RoomId,
+            instance: &'a InstanceId,
+            lease: Duration,
+        ) -> BoxFuture<'a, Result<InstanceId, ScError>> {
+            let now = Instant::now();
+            let mut owners = self.owners.lock().unwrap();
+            let owner = match owners.get(room_id) {
+                Some((owner, expires_at)) if owner != instance && *expires_at > now => {
+                    owner.clone()
+                }
+                _ => {
+                    owners.insert(room_id.clone(), (instance.clone(), now + lease));
+                    instance.clone()
+                }
+            };
+            future::ready(Ok(owner)).boxed()
+        }
+
+        fn owner<'a>(
+            &'a self,
+            room_id: &'a RoomId,
+        ) -> BoxFuture<'a, Result<Option<InstanceId>, ScError>> {
+            let owners = self.owners.lock().unwrap();
+            let owner = owners
+                .get(room_id)
+                .filter(|(_, expires_at)| *expires_at > Instant::now())
+                .map(|(owner, _)| owner.clone());
+            future::ready(Ok(owner)).boxed()
+        }
+
+        fn release<'a>(
+            &'a self,
+            room_id: &'a RoomId,
+            instance: &'a InstanceId,
+        ) -> BoxFuture<'a, Result<(), ScError>> {
+            let mut owners = self.owners.lock().unwrap();
+            if owners
+                .get(room_id)
+                .is_some_and(|(owner, _)| owner == instance)
+            {
+                owners.remove(room_id);
+            }
+            future::ready(Ok(())).boxed()
+        }
+
+        fn publish<'a>(
+            &'a self,
+            topic: &'a str,
+            payload: String,
+        ) -> BoxFuture<'a, Result<(), ScError>> {
+            let mut topics = self.topics.lock().unwrap();
+            if let Some(tx) = topics.get(topic) {
+                if tx.send(payload).is_err() {
+                    topics.remove(topic);
+                }
+            }
+            future::ready(Ok(())).boxed()
+        }
+
+        fn subscribe<'a>(
+            &'a self,
+            topic: &'a str,
+        ) -> BoxFuture<'a, Result<BoxStream<'static, String>, ScError>> {
+            let rx = self
+                .topics
+                .lock()
+                .unwrap()
+                .entry(topic.to_owned())
+                .or_insert_with(|| broadcast::channel(BUFFER_SIZE).0)
+                .subscribe();
+            let messages = stream::unfold(rx, |mut rx| async move {
+                loop {
+                    match rx.recv().await {
+                        Ok(payload) => return Some((payload, rx)),
+                        Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(broadcast::error::RecvError::Closed) => return None,
+                    }
+                }
+            });
+            future::ready(Ok(messages.boxed())).boxed()
+        }
+    }
+
+    #[tokio::test]
+    async fn rooms_belong_to_one_instance_until_the_lease_expires() {
+        let bus = MemoryBus::default();
+        let room_id: RoomId = Arc::from("room");
+        let a: InstanceId = Arc::from("a");
+        let b: InstanceId = Arc::from("b");
+        let lease = Duration::from_millis(50);
+
+        assert_eq!(bus.claim(&room_id, &a, lease).await.unwrap(), a);
+        assert_eq!(bus.claim(&room_id, &b, lease).await.unwrap(), a);
+        assert_eq!(bus.owner(&room_id).await.unwrap(), Some(a.clone()));
+
+        bus.release(&room_id, &b).await.unwrap();
+        assert_eq!(bus.owner(&room_id).await.unwrap(), Some(a.clone()));
+        bus.release(&room_id, &a).await.unwrap();
+        assert_eq!(bus.owner(&room_id).await.unwrap(), None);
+
+        assert_eq!(bus.claim(&room_id, &b, lease).await.unwrap(), b);
+        tokio::time::sleep(lease).await;
+        assert_eq!(bus.owner(&room_id).await.unwrap(), None);
+        assert_eq!(bus.claim(&room_id, &a, lease).await.unwrap(), a);
+    }
+
+    #[tokio::test]
+    async fn participants_elsewhere_share_the_room() {
+        let bus: Arc<dyn ClusterBus> = Arc::new(MemoryBus::default());
+        let owner = Cluster::new(bus.clone(), Arc::from("a"));
+        let other = Cluster::new(bus, Arc::from("b"));
+        let room_id: RoomId = Arc::from("room");
+        assert_eq!(owner.claim(&room_id).await.unwrap(), owner.instance);
+
+        let (tx, mut room_rx) = mpsc::channel::<RoomMessage>(BUFFER_SIZE);
+        let (room_broadcast, _) = broadcast::channel(BUFFER_SIZE);
+        let room = RoomChannel {
+            tx,
+            broadcast: room_broadcast.clone(),
+        };
+        tokio::spawn(async move {
+            while let Some((request, response)) = room_rx.recv().await {
+                let reply = match request {
+                    RoomRequest::Heartbeat(_) => RoomResponse::Accepted,
+                    _ => RoomResponse::PermissionDenied,
+                };
+                _ = response.send(reply);
+            }
+        });
+        let (ctrl, _ctrl_rx) = mpsc::channel(1);
+        owner.serve(room_id.clone(), room, ctrl).await.unwrap();
+
+        let (tx, proxy_rx) = mpsc::channel::<RoomMessage>(BUFFER_SIZE);
+        let (proxy_broadcast, mut events) = broadcast::channel(BUFFER_SIZE);
+        let (request_tx, _room_pool_rx) = mpsc::channel(1);
+        let room_pool = RoomPoolChannel { request_tx };
+        other
+            .proxy(
+                room_id,
+                owner.instance.clone(),
+                proxy_rx,
+                proxy_broadcast.clone(),
+                room_pool,
+            )
+            .await
+            .unwrap();
+        let proxy = RoomChannel {
+            tx,
+            broadcast: proxy_broadcast,
+        };
+
+        let response = proxy.send(RoomRequest::Heartbeat(Uuid::new_v4())).await;
+        assert!(matches!(response, Ok(RoomResponse::Accepted)));
+        let response = proxy.send(RoomRequest::State).await;
+        assert!(matches!(response, Ok(RoomResponse::PermissionDenied)));
+        let webhook = Some(Arc::from("https://hooks.slack.com/services/secret"));
+        let response = proxy
+            .send(RoomRequest::ChangeChatWebhook(Uuid::new_v4(), webhook))
+            .await;
+        assert!(matches!(
+            response,
+            Ok(RoomResponse::Rejected(Rejection::NotForwarded))
+        ));
+
+        room_broadcast
+            .send(RoomBroadcastMessage::TimerStarted(60))
+            .unwrap();
+        let message = timeout(REQUEST_TIMEOUT, events.recv()).await.unwrap();
+        assert!(matches!(
+            message,
+            Ok(RoomBroadcastMessage::TimerStarted(60))
+        ));
+    }
+
+    #[tokio::test]
+    async fn forwarded_requests_wait_side_by_side() {
+        let bus: Arc<dyn ClusterBus> = Arc::new(MemoryBus::default());
+        let owner = Cluster::new(bus.clone(), Arc::from("a"));
+        let other = Cluster::new(bus.clone(), Arc::from("b"));
+        let room_id: RoomId = Arc::from("room");
+        assert_eq!(owner.claim(&room_id).await.unwrap(), owner.instance);
+
+        // The owner answers the second request before the first one.
+        let mut requests = bus.subscribe(&requests_topic(&room_id)).await.unwrap();
+        let answering = owner.clone();
+        tokio::spawn(async move {
+            let mut received = Vec::new();
+            while received.len() < 2 {
+                let payload = requests.next().await.unwrap();
+                if let Ok(ClusterMessage::Request {
+                    id,
+                    reply_to,
+                    request,
+                }) = serde_json::from_str(&payload)
+                {
+                    received.push((id, reply_to, request));
+                }
+            }
+            for (id, reply_to, request) in received.into_iter().rev() {
+                let response = match request {
+                    RoomRequest::Heartbeat(_) => RoomResponse::Accepted,
+                    _ => RoomResponse::PermissionDenied,
+                };
+                answering
+                    .publish(&reply_to, &ClusterMessage::Response { id, response })
+                    .await;
+            }
+        });
+
+        let (tx, proxy_rx) = mpsc::channel::<RoomMessage>(BUFFER_SIZE);
+        let (proxy_broadcast, _events) = broadcast::channel(BUFFER_SIZE);
+        let (request_tx, _room_pool_rx) = mpsc::channel(1);
+        other
+            .proxy(
+                room_id,
+                owner.instance.clone(),
+                proxy_rx,
+                proxy_broadcast.clone(),
+                RoomPoolChannel { request_tx },
+            )
+            .await
+            .unwrap();
+        let proxy = RoomChannel {
+            tx,
+            broadcast: proxy_broadcast,
+        };
+
+        let (heartbeat, state) = tokio::join!(
+            proxy.send(RoomRequest::Heartbeat(Uuid::new_v4())),
+            proxy.send(RoomRequest::State)
+        );
+        assert!(matches!(heartbeat, Ok(RoomResponse::Accepted)));
+        assert!(matches!(state, Ok(RoomResponse::PermissionDenied)));
+    }
+
+    #[tokio::test]
+    async fn room_is_handed_over_when_requests_stop_arriving() {
+        let bus = Arc::new(MemoryBus::default());
+        let owner = Cluster::new(bus.clone(), Arc::from("a"));
+        let room_id: RoomId = Arc::from("room");
+        assert_eq!(owner.claim(&room_id).await.unwrap(), owner.instance);
+
+        let (tx, _room_rx) = mpsc::channel::<RoomMessage>(BUFFER_SIZE);
+        let (broadcast, _) = broadcast::channel(BUFFER_SIZE);
+        let (ctrl, mut ctrl_rx) = mpsc::channel::<CtrlMessage>(1);
+        owner
+            .serve(room_id.clone(), RoomChannel { tx, broadcast }, ctrl)
+            .await
+            .unwrap();
+
+        // Dropping the topic ends the subscription, as a lost database connection does.
+        bus.topics.lock().unwrap().remove(&requests_topic(&room_id));
+        let (request, response) = timeout(REQUEST_TIMEOUT, ctrl_rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(matches!(request, CtrlRequest::Handover));
+        _ = response.send(CtrlResponse::Done);
+
+        timeout(REQUEST_TIMEOUT, async {
+            while bus.owner(&room_id).await.unwrap().is_some() {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+}
@@ -0,0 +1,245 @@
+use crate::{
+    error::ScError,
+    estimate::Estimate,
+    github::{GitHubClient, GitHubConfig},
+    jira::{JiraClient, JiraConfig},
+};
+use reqwest::{
+    dns::{Addrs, Name, Resolve, Resolving},
+    redirect, RequestBuilder, Response, StatusCode, Url,
+};
+use std::{
+    env,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+
+/// Timeout of a single request to an issue tracker.
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+const MAX_REDIRECTS: usize = 10;
+
+/// Host names of the local network, besides `localhost`.
+const LOCAL_DOMAINS: [&str; 3] = [".localhost", ".local", ".internal"];
+
+/// Client of the integrations. It only connects to public addresses, host names resolving to
+/// local ones and redirects to them are refused.
+pub fn http_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .user_agent(concat!("scrum-poker/", env!("CARGO_PKG_VERSION")))
+        .dns_resolver(Arc::new(PublicResolver))
+        .redirect(redirect::Policy::custom(|attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                attempt.error("too many redirects")
+            } else if public_url(attempt.url().as_str()).is_none() {
+                attempt.error("redirect to a local address")
+            } else {
+                attempt.follow()
+            }
+        }))
+        .build()
+        .expect("Unable to build HTTP client")
+}
+
+/// Validates an address entered for an integration: https, or http as well when
+/// `INTEGRATIONS_ALLOW_HTTP` is `true`, on a host outside the local network.
+pub fn public_url(url: &str) -> Option<Url> {
+    let url = Url::parse(url.trim()).ok()?;
+    let scheme_allowed = match url.scheme() {
+        "https" => true,
+        "http" => env::var("INTEGRATIONS_ALLOW_HTTP").is_ok_and(|allow| allow.trim() == "true"),
+        _ => false,
+    };
+    let host = url.host_str()?.trim_end_matches('.').to_ascii_lowercase();
+    let host_allowed = match host.trim_start_matches('[').trim_end_matches(']').parse() {
+        Ok(ip) => is_public(ip),
+        Err(_) => host != "localhost" && !LOCAL_DOMAINS.iter().any(|local| host.ends_with(local)),
+    };
+    (scheme_allowed && host_allowed).then_some(url)
+}
+
+/// Whether the address is reachable from the internet, not loopback, private, link-local or
+/// otherwise reserved.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_v4(ip),
+            None => is_public_v6(ip),
+        },
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        || a == 0
+        // Shared address space of carrier-grade NAT, 100.64.0.0/10.
+        || (a == 100 && (b & 0b1100_0000) == 64))
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        // Unique local fc00::/7 and link-local fe80::/10 addresses.
+        || (first & 0xfe00) == 0xfc00
+        || (first & 0xffc0) == 0xfe80)
+}
+
+/// Resolves host names like the system does, keeping only public addresses, so a DNS record
+/// cannot point an integration at a service of the local network.
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|addr| is_public(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{} has no public address", name.as_str()).into());
+            }
+            let addrs: Addrs = Box::new(addrs.into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+/// Retries requests that failed on the network, were rate limited or hit a server error,
+/// doubling the wait after every attempt.
+#[derive(Debug, Clone, Copy)]
+pub struct Retry {
+    pub attempts: u32,
+    pub backoff: Duration,
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Retry {
+            attempts: 3,
+            backoff: Duration::from_millis(500),
+        }
+    }
+}
+
+impl Retry {
+    /// Sends the request built by `request` until it succeeds or runs out of attempts.
+    /// Responses with a non success status are turned into `ScError::HttpStatus`.
+    pub async fn send(&self, request: impl Fn() -> RequestBuilder) -> Result<Response, ScError> {
+        let mut backoff = self.backoff;
+        let mut attempt = 1;
+        loop {
+            let result = request().send().await;
+            let retryable = match &result {
+                Ok(response) => {
+                    response.status().is_server_error()
+                        || response.status() == StatusCode::TOO_MANY_REQUESTS
+                }
+                Err(err) => err.is_connect() || err.is_timeout() || err.is_request(),
+            };
+            if !retryable || attempt >= self.attempts {
+                let response = result?;
+                let status = response.status();
+                if status.is_success() {
+                    return Ok(response);
+                }
+                let body = response.text().await.unwrap_or_default();
+                return Err(ScError::HttpStatus(status, body));
+            }
+            tracing::warn!(
+                "Request attempt {} of {} failed, retrying in {:?}",
+                attempt,
+                self.attempts,
+                backoff
+            );
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+            attempt += 1;
+        }
+    }
+}
+
+/// Issue tracker a final estimate is written to.
+#[derive(Debug, Clone)]
+pub enum Tracker {
+    Jira(JiraConfig),
+    GitHub(GitHubConfig),
+}
+
+impl Tracker {
+    pub async fn write_estimate(
+        &self,
+        issue_key: &str,
+        estimate: &Estimate,
+    ) -> Result<(), ScError> {
+        match self {
+            Tracker::Jira(config) => {
+                let points = estimate.value().ok_or_else(|| {
+                    ScError::ConnectorError(format!("{estimate} is not a number of story points"))
+                })?;
+                JiraClient::new(config.clone())
+                    .update_story_points(issue_key, points)
+                    .await
+            }
+            Tracker::GitHub(config) => {
+                let number = issue_key.trim_start_matches('#').parse().map_err(|_| {
+                    ScError::ConnectorError(format!("{issue_key} is not a GitHub issue"))
+                })?;
+                GitHubClient::new(config.clone())
+                    .write_estimate(number, estimate)
+                    .await
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn integrations_only_reach_public_https_urls() {
+        assert!(public_url("https://jira.example.com").is_some());
+        assert!(public_url("https://93.184.215.14/hooks").is_some());
+        for url in [
+            "http://jira.example.com",
+            "ftp://jira.example.com",
+            "jira.example.com",
+            "https://localhost:8000",
+            "https://db.internal/",
+            "https://printer.local",
+            "https://127.0.0.1",
+            "https://2130706433",
+            "https://10.0.0.8",
+            "https://172.16.0.1",
+            "https://192.168.1.1",
+            "https://169.254.169.254/latest/meta-data",
+            "https://100.64.0.1",
+            "https://0.0.0.0",
+            "https://[::1]",
+            "https://[::ffff:127.0.0.1]",
+            "https://[fd00::1]",
+            "https://[fe80::1]",
+        ] {
+            assert!(public_url(url).is_none(), "{url}");
+        }
+    }
+
+    #[tokio::test]
+    async fn host_names_resolving_to_local_addresses_are_refused() {
+        let name = Name::from_str("localhost").unwrap();
+        assert!(PublicResolver.resolve(name).await.is_err());
+    }
+}
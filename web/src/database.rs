@@ -1,5 +1,7 @@
 use std::env;
 
+use crate::{error::ScError, metrics};
+
 use deadpool::managed;
 use surrealdb::engine::any;
 use surrealdb::engine::any::Any;
@@ -9,6 +11,15 @@ use surrealdb::Surreal;
 
 pub type Pool = managed::Pool<Manager>;
 
+/// Connection from the pool. Failures are counted, they mean the database is out of reach.
+pub async fn connection(pool: &Pool) -> Result<managed::Object<Manager>, ScError> {
+    pool.get().await.map_err(|err| {
+        metrics::DATABASE_POOL_ERRORS.inc();
+        ScError::from(err)
+    })
+}
+
+#[derive(Debug)]
 pub struct Manager {}
 
 impl managed::Manager for Manager {
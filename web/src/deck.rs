@@ -1,29 +1,91 @@
-use crate::{app::use_app_props, channel::RoomRequest, estimate::Estimate};
+use crate::{
+    app::use_app_props,
+    channel::RoomRequest,
+    estimate::{CardDeck, Estimate},
+    validate,
+};
 use dioxus::prelude::*;
 
 const LOGO_TRANS_PNG_PATH: &str = "/assets/logo_trans.png";
 
 #[component]
-pub fn Deck() -> Element {
+pub fn Deck(deck: Signal<CardDeck>) -> Element {
     rsx! {
         div {
             class: "flex flex-wrap justify-around gap-4",
             oninput: move |evt| {
                 println!("{evt:?}");
             },
-            Card { estimate: Estimate::QuestionMark }
-            Card { estimate: Estimate::Coffe }
-            Card { estimate: Estimate::Zero }
-            Card { estimate: Estimate::Half }
-            Card { estimate: Estimate::One }
-            Card { estimate: Estimate::Two }
-            Card { estimate: Estimate::Three }
-            Card { estimate: Estimate::Five }
-            Card { estimate: Estimate::Eight }
-            Card { estimate: Estimate::Thirteen }
-            Card { estimate: Estimate::Twenty }
-            Card { estimate: Estimate::Fourty }
-            Card { estimate: Estimate::Hundred }
+            for estimate in deck().cards() {
+                Card { key: "{estimate}", estimate }
+            }
+        }
+    }
+}
+
+const CUSTOM_DECK_OPTION: &str = "custom";
+
+#[component]
+pub fn DeckSettings(deck: Signal<CardDeck>) -> Element {
+    let app_props = use_app_props();
+    let mut custom_selected = use_signal(|| matches!(deck(), CardDeck::Custom(_)));
+    let mut custom_cards = use_signal(String::new);
+
+    let send_deck = move |new_deck: CardDeck| async move {
+        _ = app_props()
+            .channel
+            .send(RoomRequest::ChangeDeck(app_props().session_id, new_deck))
+            .await;
+    };
+
+    rsx! {
+        div { class: "flex flex-wrap items-center gap-x-4 gap-y-2 text-slate-600",
+            select {
+                class: "bg-white border border-slate-300 rounded-full px-4 py-2 focus:outline-none focus:ring-2 focus:ring-slate-600",
+                onchange: move |evt| {
+                    let value = evt.value();
+                    let preset = CardDeck::PRESETS.into_iter().find(|d| d.name() == value);
+                    custom_selected.set(preset.is_none());
+                    async move {
+                        if let Some(preset) = preset {
+                            send_deck(preset).await;
+                        }
+                    }
+                },
+                for preset in CardDeck::PRESETS {
+                    option {
+                        value: preset.name(),
+                        selected: !custom_selected() && deck() == preset,
+                        "{preset.name()}"
+                    }
+                }
+                option {
+                    value: CUSTOM_DECK_OPTION,
+                    selected: custom_selected(),
+                    "{CardDeck::Custom(Vec::new()).name()}"
+                }
+            }
+            if custom_selected() {
+                input {
+                    r#type: "text",
+                    class: "bg-white border border-slate-300 rounded-full px-4 py-2 focus:outline-none focus:ring-2 focus:ring-slate-600",
+                    placeholder: "?, 1, 2, 3, ☕️",
+                    autocomplete: "off",
+                    oninput: move |evt| custom_cards.set(evt.value()),
+                }
+                button {
+                    class: "rounded-full px-4 py-2 font-bold text-white bg-slate-600 hover:bg-slate-500",
+                    onclick: move |_| {
+                        let cards = validate::custom_deck(&custom_cards());
+                        async move {
+                            if !cards.is_empty() {
+                                send_deck(CardDeck::Custom(cards)).await;
+                            }
+                        }
+                    },
+                    "Use deck"
+                }
+            }
         }
     }
 }
@@ -6,6 +6,20 @@ use tokio::sync::{mpsc, oneshot};
 pub enum ScError {
     #[error("failed to retrieve from database")]
     DatabaseError(#[from] surrealdb::Error),
+    #[error("database pool error: {0}")]
+    DatabasePoolError(#[from] deadpool::managed::PoolError<surrealdb::Error>),
+    #[error("csv error: {0}")]
+    CsvError(#[from] csv::Error),
+    #[error("json error: {0}")]
+    JsonError(#[from] serde_json::Error),
+    #[error("HTTP request failed: {0}")]
+    HttpError(#[from] reqwest::Error),
+    #[error("unexpected HTTP status {0}: {1}")]
+    HttpStatus(reqwest::StatusCode, String),
+    #[error("connector error: {0}")]
+    ConnectorError(String),
+    #[error("invalid URL: {0}")]
+    InvalidUrl(String),
     #[error("RoomMessage send error: {0}")]
     RoomMessageSendError(#[from] mpsc::error::SendError<RoomMessage>),
     #[error("RoomPoolMessage send error: {0}")]
@@ -16,4 +30,10 @@ pub enum ScError {
     TokioJoinError(#[from] tokio::task::JoinError),
     #[error("Unexpected response from server")]
     UnexpectedResponse,
+    #[error("server is shutting down")]
+    ShuttingDown,
+    #[error("invalid room id")]
+    InvalidRoomId,
+    #[error("room is unavailable")]
+    RoomUnavailable,
 }
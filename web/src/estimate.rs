@@ -1,21 +1,23 @@
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
-#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(into = "Arc<str>", from = "Arc<str>")]
 pub enum Estimate {
     None,
     QuestionMark,
     Coffe,
-    Zero,
-    Half,
-    One,
-    Two,
-    Three,
-    Five,
-    Eight,
-    Thirteen,
-    Twenty,
-    Fourty,
-    Hundred,
+    Card(Arc<str>),
+}
+
+impl Estimate {
+    /// Numeric value of the card, `None` for special and non numeric cards like T-shirt sizes.
+    pub fn value(&self) -> Option<f64> {
+        match self {
+            Estimate::Card(card) => card.parse::<f64>().ok().filter(|value| value.is_finite()),
+            _ => None,
+        }
+    }
 }
 
 impl std::fmt::Display for Estimate {
@@ -31,38 +33,88 @@ impl From<Estimate> for Arc<str> {
             Estimate::None => "".into(),
             Estimate::QuestionMark => "?".into(),
             Estimate::Coffe => "☕️".into(),
-            Estimate::Zero => "0".into(),
-            Estimate::Half => "0.5".into(),
-            Estimate::One => "1".into(),
-            Estimate::Two => "2".into(),
-            Estimate::Three => "3".into(),
-            Estimate::Five => "5".into(),
-            Estimate::Eight => "8".into(),
-            Estimate::Thirteen => "13".into(),
-            Estimate::Twenty => "20".into(),
-            Estimate::Fourty => "40".into(),
-            Estimate::Hundred => "100".into(),
+            Estimate::Card(card) => card,
         }
     }
 }
 
-impl From<Estimate> for i32 {
-    fn from(estimate: Estimate) -> i32 {
-        match estimate {
-            Estimate::None => -1,
-            Estimate::QuestionMark => 0,
-            Estimate::Coffe => 1,
-            Estimate::Zero => 2,
-            Estimate::Half => 3,
-            Estimate::One => 4,
-            Estimate::Two => 5,
-            Estimate::Three => 6,
-            Estimate::Five => 7,
-            Estimate::Eight => 8,
-            Estimate::Thirteen => 9,
-            Estimate::Twenty => 10,
-            Estimate::Fourty => 11,
-            Estimate::Hundred => 12,
+impl From<&str> for Estimate {
+    fn from(card: &str) -> Estimate {
+        match card.trim() {
+            "" => Estimate::None,
+            "?" => Estimate::QuestionMark,
+            "☕️" | "☕" => Estimate::Coffe,
+            card => Estimate::Card(Arc::from(card)),
         }
     }
 }
+
+impl From<Arc<str>> for Estimate {
+    fn from(card: Arc<str>) -> Estimate {
+        Estimate::from(card.as_ref())
+    }
+}
+
+/// Set of cards the participants of a room vote with.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum CardDeck {
+    Fibonacci,
+    #[default]
+    ModifiedFibonacci,
+    PowersOfTwo,
+    TShirt,
+    Hours,
+    Custom(Vec<Arc<str>>),
+}
+
+impl CardDeck {
+    pub const PRESETS: [CardDeck; 5] = [
+        CardDeck::Fibonacci,
+        CardDeck::ModifiedFibonacci,
+        CardDeck::PowersOfTwo,
+        CardDeck::TShirt,
+        CardDeck::Hours,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CardDeck::Fibonacci => "Fibonacci",
+            CardDeck::ModifiedFibonacci => "Modified Fibonacci",
+            CardDeck::PowersOfTwo => "Powers of 2",
+            CardDeck::TShirt => "T-shirt sizes",
+            CardDeck::Hours => "Hours",
+            CardDeck::Custom(_) => "Custom",
+        }
+    }
+
+    /// Cards in the order they are dealt and results are sorted by.
+    pub fn cards(&self) -> Vec<Estimate> {
+        let values: &[&str] = match self {
+            CardDeck::Fibonacci => &["0", "1", "2", "3", "5", "8", "13", "21", "34", "55", "89"],
+            CardDeck::ModifiedFibonacci => {
+                &["0", "0.5", "1", "2", "3", "5", "8", "13", "20", "40", "100"]
+            }
+            CardDeck::PowersOfTwo => &["0", "1", "2", "4", "8", "16", "32", "64"],
+            CardDeck::TShirt => &["XS", "S", "M", "L", "XL", "XXL"],
+            CardDeck::Hours => &["0.5", "1", "2", "3", "4", "6", "8", "12", "16", "24", "40"],
+            CardDeck::Custom(cards) => {
+                return cards
+                    .iter()
+                    .map(|card| Estimate::from(card.as_ref()))
+                    .collect();
+            }
+        };
+        [Estimate::QuestionMark, Estimate::Coffe]
+            .into_iter()
+            .chain(values.iter().map(|value| Estimate::from(*value)))
+            .collect()
+    }
+
+    pub fn position(&self, estimate: &Estimate) -> Option<usize> {
+        self.cards().iter().position(|card| card == estimate)
+    }
+
+    pub fn contains(&self, estimate: &Estimate) -> bool {
+        *estimate == Estimate::None || self.position(estimate).is_some()
+    }
+}
@@ -0,0 +1,271 @@
+use crate::{
+    api::{participant_id, ParticipantView, RoomView},
+    channel::{RoomBroadcastMessage, RoomRequest, RoomResponse},
+    error::ScError,
+    estimate::Estimate,
+    metrics,
+    room::RoomId,
+    room_pool::RoomPoolChannel,
+    statistics::RoundStatistics,
+    story::Story,
+};
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+/// Room broadcasts as clients outside the liveview UI see them. Session ids, tracker and webhook
+/// settings stay on the server.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RoomEvent {
+    /// The whole room, sent first and whenever events were missed.
+    Snapshot {
+        room: RoomView,
+    },
+    ParticipantJoined {
+        participant: ParticipantView,
+    },
+    ParticipantUpdated {
+        participant: ParticipantView,
+    },
+    ParticipantLeft {
+        id: Uuid,
+    },
+    EstimatesDeleted,
+    Statistics {
+        statistics: RoundStatistics,
+    },
+    DeckChanged {
+        deck: Vec<Estimate>,
+    },
+    StoryChanged {
+        story: Option<Story>,
+    },
+    AutoRevealCountdown {
+        seconds: u64,
+    },
+    AutoRevealCancelled,
+    TimerStarted {
+        seconds: u64,
+    },
+    TimerTick {
+        seconds: u64,
+    },
+    TimerExpired,
+    TimerStopped,
+    Notice {
+        message: Arc<str>,
+    },
+    /// An operator closed the room, no events follow.
+    RoomClosed,
+}
+
+/// Follows a room as a sequence of [`RoomEvent`]s.
+///
+/// It holds no sender of the room's broadcast channel, so the events end when the room shuts
+/// down. Revealing or hiding estimates changes what every participant shows, so it is sent as a
+/// snapshot rather than as an event of its own.
+pub struct RoomEvents {
+    room_id: RoomId,
+    room_pool: RoomPoolChannel,
+    broadcast: broadcast::Receiver<RoomBroadcastMessage>,
+    revealed: bool,
+    story: Option<Story>,
+    resync: bool,
+    closed: bool,
+}
+
+impl RoomEvents {
+    /// Subscribes to the room, spawning it if needed. The first event is a snapshot.
+    pub async fn subscribe(room_pool: RoomPoolChannel, room_id: RoomId) -> Result<Self, ScError> {
+        let channel = room_pool.spawn(&room_id).await?;
+        Ok(RoomEvents {
+            broadcast: channel.subscribe(),
+            room_id,
+            room_pool,
+            revealed: false,
+            story: None,
+            resync: true,
+            closed: false,
+        })
+    }
+
+    /// Next event of the room, `None` once the room is gone. Cancel safe, so it can be raced
+    /// against the messages of a client.
+    pub async fn next(&mut self) -> Option<RoomEvent> {
+        loop {
+            if self.closed {
+                return None;
+            }
+            if self.resync {
+                let snapshot = self.snapshot().await;
+                self.resync = false;
+                return match snapshot {
+                    Ok(room) => {
+                        self.revealed = room.revealed;
+                        self.story = room.story.clone();
+                        Some(RoomEvent::Snapshot { room })
+                    }
+                    Err(err) => {
+                        tracing::error!(
+                            "Failed to get snapshot of room {}, error: {}",
+                            self.room_id,
+                            err
+                        );
+                        None
+                    }
+                };
+            }
+            match self.broadcast.recv().await {
+                Ok(msg) => {
+                    if let Some(event) = self.event(msg) {
+                        return Some(event);
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    metrics::BROADCAST_LAGS.with_label_values(&["events"]).inc();
+                    tracing::warn!(
+                        "Event subscriber of room {} skipped {} messages, resyncing",
+                        self.room_id,
+                        skipped
+                    );
+                    self.resync = true;
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    /// The room was closed by an operator, rather than shut down because it was empty.
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    async fn snapshot(&self) -> Result<RoomView, ScError> {
+        let channel = self.room_pool.spawn(&self.room_id).await?;
+        match channel.send(RoomRequest::State).await? {
+            RoomResponse::RoomState(state) => Ok(RoomView::new(self.room_id.clone(), &state)),
+            _ => Err(ScError::UnexpectedResponse),
+        }
+    }
+
+    fn event(&mut self, msg: RoomBroadcastMessage) -> Option<RoomEvent> {
+        let event = match msg {
+            RoomBroadcastMessage::Joined(p) => RoomEvent::ParticipantJoined {
+                participant: ParticipantView::new(&p, self.revealed),
+            },
+            RoomBroadcastMessage::ParticipantUpdate(p) => RoomEvent::ParticipantUpdated {
+                participant: ParticipantView::new(&p, self.revealed),
+            },
+            RoomBroadcastMessage::Left(session_id) => RoomEvent::ParticipantLeft {
+                id: participant_id(session_id),
+            },
+            RoomBroadcastMessage::ChangedVisibility(_) => {
+                self.resync = true;
+                return None;
+            }
+            RoomBroadcastMessage::EstimatesDeleted => RoomEvent::EstimatesDeleted,
+            RoomBroadcastMessage::StatisticsUpdate(statistics) => {
+                RoomEvent::Statistics { statistics }
+            }
+            RoomBroadcastMessage::DeckChanged(deck) => {
+                RoomEvent::DeckChanged { deck: deck.cards() }
+            }
+            RoomBroadcastMessage::StoriesChanged(stories) => {
+                let story = stories.current().cloned();
+                if story == self.story {
+                    return None;
+                }
+                self.story = story.clone();
+                RoomEvent::StoryChanged { story }
+            }
+            RoomBroadcastMessage::AutoRevealCountdown(seconds) => {
+                RoomEvent::AutoRevealCountdown { seconds }
+            }
+            RoomBroadcastMessage::AutoRevealCancelled => RoomEvent::AutoRevealCancelled,
+            RoomBroadcastMessage::TimerStarted(seconds) => RoomEvent::TimerStarted { seconds },
+            RoomBroadcastMessage::TimerTick(seconds) => RoomEvent::TimerTick { seconds },
+            RoomBroadcastMessage::TimerExpired => RoomEvent::TimerExpired,
+            RoomBroadcastMessage::TimerStopped => RoomEvent::TimerStopped,
+            RoomBroadcastMessage::Notice(message) => RoomEvent::Notice { message },
+            RoomBroadcastMessage::Closed => {
+                self.closed = true;
+                RoomEvent::RoomClosed
+            }
+            RoomBroadcastMessage::SettingsChanged(_)
+            | RoomBroadcastMessage::RoundsChanged(_)
+            | RoomBroadcastMessage::JiraChanged(_)
+            | RoomBroadcastMessage::GitHubChanged(_)
+            | RoomBroadcastMessage::WebhookChanged(_)
+            | RoomBroadcastMessage::ChatChanged(_)
+            | RoomBroadcastMessage::IssuesPulled(_)
+            | RoomBroadcastMessage::RoomRequestedHeartbeat => return None,
+        };
+        Some(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{channel::EstimateVisibility, room::Participant};
+    use tokio::sync::mpsc;
+
+    fn room_events(broadcast: broadcast::Receiver<RoomBroadcastMessage>) -> RoomEvents {
+        let (request_tx, _request_rx) = mpsc::channel(1);
+        RoomEvents {
+            room_id: Arc::from("room1"),
+            room_pool: RoomPoolChannel { request_tx },
+            broadcast,
+            revealed: false,
+            story: None,
+            resync: false,
+            closed: false,
+        }
+    }
+
+    #[test]
+    fn hides_estimates_and_session_ids() {
+        let (_tx, rx) = broadcast::channel(1);
+        let mut events = room_events(rx);
+        let mut alice = Participant::new(Uuid::from_u128(1), Arc::from("Alice"));
+        alice.estimate = Estimate::from("5");
+
+        let Some(RoomEvent::ParticipantUpdated { participant }) =
+            events.event(RoomBroadcastMessage::ParticipantUpdate(alice.clone()))
+        else {
+            panic!("expected a participant update");
+        };
+        assert!(participant.voted);
+        assert_eq!(participant.estimate, None);
+        assert_ne!(participant.id, alice.session_id);
+
+        let Some(RoomEvent::ParticipantLeft { id }) =
+            events.event(RoomBroadcastMessage::Left(alice.session_id))
+        else {
+            panic!("expected a participant left event");
+        };
+        assert_eq!(id, participant.id);
+    }
+
+    #[test]
+    fn visibility_changes_resync() {
+        let (_tx, rx) = broadcast::channel(1);
+        let mut events = room_events(rx);
+        let visible = RoomBroadcastMessage::ChangedVisibility(EstimateVisibility::Visible);
+        assert!(events.event(visible).is_none());
+        assert!(events.resync);
+    }
+
+    #[tokio::test]
+    async fn events_end_when_the_room_is_closed() {
+        let (tx, rx) = broadcast::channel(4);
+        let mut events = room_events(rx);
+        tx.send(RoomBroadcastMessage::Closed).unwrap();
+        tx.send(RoomBroadcastMessage::EstimatesDeleted).unwrap();
+        assert!(matches!(events.next().await, Some(RoomEvent::RoomClosed)));
+        assert!(events.next().await.is_none());
+        assert!(events.is_closed());
+    }
+}
@@ -0,0 +1,247 @@
+use crate::{error::ScError, round::RoundRecord};
+use chrono::{DateTime, Utc};
+use itertools::Itertools;
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    Markdown,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 3] = [
+        ExportFormat::Csv,
+        ExportFormat::Json,
+        ExportFormat::Markdown,
+    ];
+
+    pub fn from_extension(extension: &str) -> Option<ExportFormat> {
+        match extension {
+            "csv" => Some(ExportFormat::Csv),
+            "json" => Some(ExportFormat::Json),
+            "md" => Some(ExportFormat::Markdown),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Markdown => "md",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Json => "JSON",
+            ExportFormat::Markdown => "Markdown",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Json => "application/json",
+            ExportFormat::Markdown => "text/markdown; charset=utf-8",
+        }
+    }
+
+    pub fn render(&self, rounds: &[RoundRecord]) -> Result<String, ScError> {
+        match self {
+            ExportFormat::Csv => csv(rounds),
+            ExportFormat::Json => Ok(serde_json::to_string_pretty(rounds)?),
+            ExportFormat::Markdown => Ok(markdown(rounds)),
+        }
+    }
+}
+
+/// One row per vote so the results can be filtered and pivoted in a spreadsheet.
+fn csv(rounds: &[RoundRecord]) -> Result<String, ScError> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record([
+        "round",
+        "story",
+        "link",
+        "revealed_at",
+        "reset_at",
+        "name",
+        "estimate",
+        "average",
+        "median",
+        "final_estimate",
+    ])?;
+    for (number, round) in rounds.iter().enumerate() {
+        let round_columns = |name: &str, estimate: String| {
+            [
+                (number + 1).to_string(),
+                round
+                    .story
+                    .as_ref()
+                    .map(|story| story.title.to_string())
+                    .unwrap_or_default(),
+                round
+                    .story
+                    .as_ref()
+                    .and_then(|story| story.link.as_ref())
+                    .map(|link| link.to_string())
+                    .unwrap_or_default(),
+                timestamp(&round.revealed_at),
+                round.reset_at.as_ref().map(timestamp).unwrap_or_default(),
+                name.to_string(),
+                estimate,
+                number_column(round.statistics.average),
+                number_column(round.statistics.median),
+                round
+                    .final_estimate
+                    .as_ref()
+                    .map(|estimate| estimate.to_string())
+                    .unwrap_or_default(),
+            ]
+        };
+        if round.votes.is_empty() {
+            writer.write_record(round_columns("", String::new()))?;
+        }
+        for vote in &round.votes {
+            writer.write_record(round_columns(&vote.name, vote.estimate.to_string()))?;
+        }
+    }
+    let bytes = writer
+        .into_inner()
+        .map_err(|err| csv::Error::from(err.into_error()))?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// One row per round, ready to paste into Confluence or a pull request.
+fn markdown(rounds: &[RoundRecord]) -> String {
+    let mut table = String::from(
+        "| # | Story | Votes | Average | Median | Final | Revealed |\n\
+         |---|-------|-------|---------|--------|-------|----------|\n",
+    );
+    for (number, round) in rounds.iter().enumerate() {
+        let story = match &round.story {
+            Some(story) => match &story.link {
+                Some(link) => format!("[{}]({})", markdown_cell(&story.title), link),
+                None => markdown_cell(&story.title),
+            },
+            None => String::new(),
+        };
+        let votes = round
+            .votes
+            .iter()
+            .map(|vote| format!("{}: {}", vote.name, vote.estimate))
+            .join(", ");
+        table.push_str(&format!(
+            "| {} | {} | {} | {} | {} | {} | {} |\n",
+            number + 1,
+            story,
+            markdown_cell(&votes),
+            number_column(round.statistics.average),
+            number_column(round.statistics.median),
+            round
+                .final_estimate
+                .as_ref()
+                .map(|estimate| markdown_cell(&estimate.to_string()))
+                .unwrap_or_default(),
+            round.revealed_at.format("%Y-%m-%d %H:%M"),
+        ));
+    }
+    table
+}
+
+fn markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+fn timestamp(time: &DateTime<Utc>) -> String {
+    time.to_rfc3339()
+}
+
+fn number_column(value: Option<f64>) -> String {
+    value.map(|value| format!("{value:.1}")).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        estimate::{CardDeck, Estimate},
+        room::Participant,
+    };
+    use std::{collections::HashMap, sync::Arc};
+    use uuid::Uuid;
+
+    fn round(votes: &[(&str, &str)]) -> RoundRecord {
+        let participants: HashMap<Uuid, Participant> = votes
+            .iter()
+            .map(|(name, card)| {
+                let mut participant = Participant::new(Uuid::new_v4(), Arc::from(*name));
+                participant.estimate = Estimate::from(*card);
+                (participant.session_id, participant)
+            })
+            .collect();
+        RoundRecord::new(
+            Arc::from("room"),
+            None,
+            &participants,
+            &CardDeck::ModifiedFibonacci,
+        )
+    }
+
+    fn rows(csv: &str) -> Vec<Vec<String>> {
+        csv::ReaderBuilder::new()
+            .has_headers(false)
+            .from_reader(csv.as_bytes())
+            .records()
+            .map(|record| record.unwrap().iter().map(String::from).collect())
+            .collect()
+    }
+
+    #[test]
+    fn csv_header_lists_the_columns_in_order() {
+        let csv = ExportFormat::Csv.render(&[]).unwrap();
+        assert_eq!(
+            csv,
+            "round,story,link,revealed_at,reset_at,name,estimate,average,median,final_estimate\n"
+        );
+    }
+
+    #[test]
+    fn csv_quotes_names() {
+        let names = ["Doe, Jane", "Ace \"Pet\" Ventura", "two\nlines"];
+        let rounds = [round(&names.map(|name| (name, "3")))];
+        let csv = ExportFormat::Csv.render(&rounds).unwrap();
+        assert!(csv.contains("\"Doe, Jane\""));
+        assert!(csv.contains("\"Ace \"\"Pet\"\" Ventura\""));
+
+        let rows = rows(&csv);
+        assert_eq!(rows.len(), 4);
+        let mut exported: Vec<&str> = rows[1..].iter().map(|row| row[5].as_str()).collect();
+        exported.sort();
+        let mut expected = names.to_vec();
+        expected.sort();
+        assert_eq!(exported, expected);
+        assert!(rows[1..].iter().all(|row| row[6] == "3" && row[7] == "3.0"));
+    }
+
+    #[test]
+    fn csv_keeps_rounds_without_votes() {
+        let rounds = [round(&[]), round(&[("alice", "5")])];
+        let rows = rows(&ExportFormat::Csv.render(&rounds).unwrap());
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[1][0], "1");
+        assert_eq!(rows[1][5..], ["", "", "", "", ""]);
+        assert_eq!(rows[2][0], "2");
+        assert_eq!(rows[2][5..7], ["alice", "5"]);
+    }
+
+    #[test]
+    fn json_round_trips() {
+        let rounds = vec![round(&[]), round(&[("Doe, Jane", "?"), ("bob", "8")])];
+        let json = ExportFormat::Json.render(&rounds).unwrap();
+        let parsed: Vec<RoundRecord> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, rounds);
+    }
+}
@@ -0,0 +1,638 @@
+use crate::{
+    connector::{self, Retry},
+    error::ScError,
+    estimate::Estimate,
+    story::Story,
+};
+use reqwest::{RequestBuilder, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{env, fmt, sync::Arc};
+
+const DEFAULT_API_URL: &str = "https://api.github.com";
+const POINTS_LABEL_PREFIX: &str = "points: ";
+const ISSUES_PER_PAGE: usize = 100;
+const MAX_ISSUE_PAGES: usize = 5;
+
+/// Where the agreed estimate of an issue is written to.
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub enum EstimateTarget {
+    /// A `points: 5` label, replacing earlier points labels.
+    Label,
+    /// A number field of a GitHub Projects board.
+    ProjectField {
+        project_id: Arc<str>,
+        field_id: Arc<str>,
+    },
+}
+
+/// Repository a room pulls its issues from and writes estimates back to.
+#[derive(PartialEq, Clone, Serialize, Deserialize)]
+pub struct GitHubConfig {
+    /// REST endpoint, `GITHUB_API_URL` for GitHub Enterprise.
+    pub api_url: Arc<str>,
+    pub owner: Arc<str>,
+    pub repo: Arc<str>,
+    pub token: Arc<str>,
+    /// Only pull the open issues of this milestone number.
+    pub milestone: Option<u64>,
+    pub target: EstimateTarget,
+}
+
+impl GitHubConfig {
+    /// Validates the `owner/repo` and token entered by the facilitator. Estimates go to the
+    /// project field when both its project and field id are given, to a label otherwise.
+    pub fn new(
+        repository: &str,
+        token: &str,
+        milestone: &str,
+        project_id: &str,
+        field_id: &str,
+    ) -> Option<GitHubConfig> {
+        let (owner, repo) = repository.trim().split_once('/')?;
+        let valid_name = |name: &str| {
+            !name.is_empty()
+                && name != "."
+                && name != ".."
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+        };
+        let token = token.trim();
+        if !valid_name(owner) || !valid_name(repo) || token.is_empty() {
+            return None;
+        }
+        let target = match (project_id.trim(), field_id.trim()) {
+            ("", _) | (_, "") => EstimateTarget::Label,
+            (project_id, field_id) => EstimateTarget::ProjectField {
+                project_id: Arc::from(project_id),
+                field_id: Arc::from(field_id),
+            },
+        };
+        let api_url = env::var("GITHUB_API_URL").unwrap_or(DEFAULT_API_URL.into());
+        if connector::public_url(&api_url).is_none() {
+            tracing::warn!("GITHUB_API_URL {} is not a public https URL", api_url);
+            return None;
+        }
+        Some(GitHubConfig {
+            api_url: Arc::from(api_url.trim_end_matches('/')),
+            owner: Arc::from(owner),
+            repo: Arc::from(repo),
+            token: Arc::from(token),
+            milestone: milestone.trim().parse().ok(),
+            target,
+        })
+    }
+
+    pub fn repository(&self) -> Arc<str> {
+        Arc::from(format!("{}/{}", self.owner, self.repo))
+    }
+}
+
+impl fmt::Debug for GitHubConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GitHubConfig")
+            .field("api_url", &self.api_url)
+            .field("owner", &self.owner)
+            .field("repo", &self.repo)
+            .field("token", &"***")
+            .field("milestone", &self.milestone)
+            .field("target", &self.target)
+            .finish()
+    }
+}
+
+#[derive(Deserialize)]
+struct Issue {
+    number: u64,
+    title: String,
+    html_url: String,
+    body: Option<String>,
+    /// Only set on pull requests, which the issues endpoint lists as well.
+    pull_request: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct Label {
+    name: String,
+}
+
+pub struct GitHubClient {
+    config: GitHubConfig,
+    http: reqwest::Client,
+    retry: Retry,
+}
+
+impl GitHubClient {
+    pub fn new(config: GitHubConfig) -> GitHubClient {
+        GitHubClient {
+            config,
+            http: connector::http_client(),
+            retry: Retry::default(),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn with_retry(mut self, retry: Retry) -> GitHubClient {
+        self.retry = retry;
+        self
+    }
+
+    /// Open issues of the repository, or of its milestone, oldest first. Pull requests are left out.
+    pub async fn open_issues(&self) -> Result<Vec<Story>, ScError> {
+        let mut stories = Vec::new();
+        for page in 1..=MAX_ISSUE_PAGES {
+            let mut query = vec![
+                ("state", "open".to_string()),
+                ("sort", "created".to_string()),
+                ("direction", "asc".to_string()),
+                ("per_page", ISSUES_PER_PAGE.to_string()),
+                ("page", page.to_string()),
+            ];
+            if let Some(milestone) = self.config.milestone {
+                query.push(("milestone", milestone.to_string()));
+            }
+            let url = self.repo_url(&["issues"])?;
+            let issues: Vec<Issue> = self
+                .retry
+                .send(|| self.authorize(self.http.get(url.clone())).query(&query))
+                .await?
+                .json()
+                .await?;
+            let last_page = issues.len() < ISSUES_PER_PAGE;
+            stories.extend(
+                issues
+                    .into_iter()
+                    .filter(|issue| issue.pull_request.is_none())
+                    .filter_map(|issue| {
+                        Story::new(
+                            &issue.title,
+                            &issue.html_url,
+                            issue.body.as_deref().unwrap_or_default(),
+                        )
+                        .map(|story| story.with_key(&format!("#{}", issue.number)))
+                    }),
+            );
+            if last_page {
+                break;
+            }
+        }
+        Ok(stories)
+    }
+
+    /// Writes the agreed estimate to the issue with the configured target.
+    pub async fn write_estimate(&self, number: u64, estimate: &Estimate) -> Result<(), ScError> {
+        match &self.config.target {
+            EstimateTarget::Label => self.write_label(number, estimate).await,
+            EstimateTarget::ProjectField {
+                project_id,
+                field_id,
+            } => {
+                let points = estimate.value().ok_or_else(|| {
+                    ScError::ConnectorError(format!("{estimate} is not a number of story points"))
+                })?;
+                self.write_project_field(number, project_id, field_id, points)
+                    .await
+            }
+        }
+    }
+
+    async fn write_label(&self, number: u64, estimate: &Estimate) -> Result<(), ScError> {
+        let label = format!("{POINTS_LABEL_PREFIX}{estimate}");
+        let number = number.to_string();
+        let labels_url = self.repo_url(&["issues", &number, "labels"])?;
+        let labels: Vec<Label> = self
+            .retry
+            .send(|| self.authorize(self.http.get(labels_url.clone())))
+            .await?
+            .json()
+            .await?;
+        for old_label in labels
+            .iter()
+            .filter(|old| old.name.starts_with(POINTS_LABEL_PREFIX) && old.name != label)
+        {
+            let url = self.repo_url(&["issues", &number, "labels", &old_label.name])?;
+            self.retry
+                .send(|| self.authorize(self.http.delete(url.clone())))
+                .await?;
+        }
+        let body = json!({ "labels": [label] });
+        self.retry
+            .send(|| {
+                self.authorize(self.http.post(labels_url.clone()))
+                    .json(&body)
+            })
+            .await?;
+        Ok(())
+    }
+
+    async fn write_project_field(
+        &self,
+        number: u64,
+        project_id: &str,
+        field_id: &str,
+        points: f64,
+    ) -> Result<(), ScError> {
+        let issue = self
+            .graphql(
+                "query($owner: String!, $repo: String!, $number: Int!) {
+                    repository(owner: $owner, name: $repo) {
+                        issue(number: $number) {
+                            id
+                            projectItems(first: 50) { nodes { id project { id } } }
+                        }
+                    }
+                }",
+                json!({ "owner": self.config.owner, "repo": self.config.repo, "number": number }),
+            )
+            .await?;
+        let issue = &issue["repository"]["issue"];
+        let existing_item = issue["projectItems"]["nodes"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|item| item["project"]["id"] == project_id)
+            .and_then(|item| item["id"].as_str().map(str::to_string));
+        let item_id = match existing_item {
+            Some(item_id) => item_id,
+            None => {
+                let issue_id = issue["id"]
+                    .as_str()
+                    .ok_or_else(|| ScError::ConnectorError(format!("issue #{number} not found")))?;
+                let added = self
+                    .graphql(
+                        "mutation($project: ID!, $content: ID!) {
+                            addProjectV2ItemById(input: { projectId: $project, contentId: $content }) {
+                                item { id }
+                            }
+                        }",
+                        json!({ "project": project_id, "content": issue_id }),
+                    )
+                    .await?;
+                added["addProjectV2ItemById"]["item"]["id"]
+                    .as_str()
+                    .map(str::to_string)
+                    .ok_or_else(|| {
+                        ScError::ConnectorError(format!("could not add #{number} to the project"))
+                    })?
+            }
+        };
+        self.graphql(
+            "mutation($project: ID!, $item: ID!, $field: ID!, $points: Float!) {
+                updateProjectV2ItemFieldValue(input: {
+                    projectId: $project, itemId: $item, fieldId: $field, value: { number: $points }
+                }) { projectV2Item { id } }
+            }",
+            json!({ "project": project_id, "item": item_id, "field": field_id, "points": points }),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Runs a GraphQL query and returns its `data`. GitHub answers errors with status 200.
+    async fn graphql(&self, query: &str, variables: Value) -> Result<Value, ScError> {
+        let url = self.graphql_url()?;
+        let body = json!({ "query": query, "variables": variables });
+        let mut response: Value = self
+            .retry
+            .send(|| self.authorize(self.http.post(url.clone())).json(&body))
+            .await?
+            .json()
+            .await?;
+        if let Some(errors) = response["errors"].as_array().filter(|e| !e.is_empty()) {
+            let messages: Vec<&str> = errors
+                .iter()
+                .filter_map(|error| error["message"].as_str())
+                .collect();
+            return Err(ScError::ConnectorError(messages.join(", ")));
+        }
+        Ok(response["data"].take())
+    }
+
+    /// URL below the repository with every segment percent encoded.
+    fn repo_url(&self, segments: &[&str]) -> Result<Url, ScError> {
+        let invalid = || ScError::InvalidUrl(self.config.api_url.to_string());
+        let mut url = Url::parse(&self.config.api_url).map_err(|_| invalid())?;
+        url.path_segments_mut()
+            .map_err(|_| invalid())?
+            .pop_if_empty()
+            .extend(["repos", &self.config.owner, &self.config.repo])
+            .extend(segments);
+        Ok(url)
+    }
+
+    /// GraphQL endpoint next to the REST one, `/api/graphql` on GitHub Enterprise Server whose
+    /// REST endpoint is `/api/v3`.
+    fn graphql_url(&self) -> Result<Url, ScError> {
+        let invalid = || ScError::InvalidUrl(self.config.api_url.to_string());
+        let mut url = Url::parse(&self.config.api_url).map_err(|_| invalid())?;
+        let enterprise = url.path().trim_end_matches('/').ends_with("/api/v3");
+        {
+            let mut segments = url.path_segments_mut().map_err(|_| invalid())?;
+            segments.pop_if_empty();
+            if enterprise {
+                segments.pop();
+            }
+            segments.push("graphql");
+        }
+        Ok(url)
+    }
+
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        request
+            .bearer_auth(&self.config.token)
+            .header("Accept", "application/vnd.github+json")
+            .header("X-GitHub-Api-Version", "2022-11-28")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        extract::{Path, Query, State},
+        http::{HeaderMap, StatusCode},
+        routing::{delete, get, post},
+        Json, Router,
+    };
+    use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+    /// Requests the stand-in received, as "METHOD path" with the JSON body if there was one.
+    #[derive(Clone, Default)]
+    struct StandIn {
+        received: Arc<Mutex<Vec<(String, Value)>>>,
+        labels: Arc<Mutex<Vec<String>>>,
+        project_items: Arc<Mutex<Vec<Value>>>,
+    }
+
+    impl StandIn {
+        fn record(&self, request: String, body: Value) {
+            self.received.lock().unwrap().push((request, body));
+        }
+
+        fn requests(&self) -> Vec<String> {
+            self.received
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(request, _)| request.clone())
+                .collect()
+        }
+    }
+
+    fn issue(number: u64, pull_request: bool) -> Value {
+        let mut issue = json!({
+            "number": number,
+            "title": format!("Issue {number}"),
+            "html_url": format!("https://github.com/acme/shop/issues/{number}"),
+            "body": null,
+        });
+        if pull_request {
+            issue["pull_request"] = json!({});
+        }
+        issue
+    }
+
+    async fn list_issues(
+        State(stand_in): State<StandIn>,
+        headers: HeaderMap,
+        Query(query): Query<HashMap<String, String>>,
+    ) -> Result<Json<Value>, StatusCode> {
+        if headers.get("authorization").unwrap() != "Bearer secret" {
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+        stand_in.record(
+            format!("GET issues {:?}", query.get("milestone")),
+            Value::Null,
+        );
+        let page: u64 = query["page"].parse().unwrap();
+        let issues: Vec<Value> = match page {
+            // A full first page makes the client ask for the next one.
+            1 => (1..=ISSUES_PER_PAGE as u64)
+                .map(|number| issue(number, number % 2 == 0))
+                .collect(),
+            _ => vec![issue(101, false)],
+        };
+        Ok(Json(Value::from(issues)))
+    }
+
+    async fn get_labels(State(stand_in): State<StandIn>) -> Json<Value> {
+        let labels = stand_in.labels.lock().unwrap().clone();
+        Json(Value::from(
+            labels
+                .into_iter()
+                .map(|name| json!({ "name": name }))
+                .collect::<Vec<_>>(),
+        ))
+    }
+
+    async fn add_labels(
+        State(stand_in): State<StandIn>,
+        Path(number): Path<u64>,
+        Json(body): Json<Value>,
+    ) -> Json<Value> {
+        stand_in.record(format!("POST labels #{number}"), body);
+        Json(json!([]))
+    }
+
+    async fn remove_label(
+        State(stand_in): State<StandIn>,
+        Path((number, name)): Path<(u64, String)>,
+    ) -> Json<Value> {
+        stand_in.record(format!("DELETE label #{number} {name}"), Value::Null);
+        Json(json!([]))
+    }
+
+    async fn graphql(State(stand_in): State<StandIn>, Json(body): Json<Value>) -> Json<Value> {
+        let query = body["query"].as_str().unwrap();
+        let operation = ["addProjectV2ItemById", "updateProjectV2ItemFieldValue"]
+            .into_iter()
+            .find(|operation| query.contains(operation))
+            .unwrap_or("issue");
+        stand_in.record(format!("GRAPHQL {operation}"), body["variables"].clone());
+        let data = match operation {
+            "issue" => json!({ "repository": { "issue": {
+                "id": "I_1",
+                "projectItems": { "nodes": stand_in.project_items.lock().unwrap().clone() }
+            } } }),
+            "addProjectV2ItemById" => {
+                json!({ "addProjectV2ItemById": { "item": { "id": "PVTI_new" } } })
+            }
+            _ => {
+                json!({ "updateProjectV2ItemFieldValue": { "projectV2Item": { "id": "PVTI_new" } } })
+            }
+        };
+        Json(json!({ "data": data }))
+    }
+
+    async fn spawn_stand_in(target: EstimateTarget) -> (GitHubClient, StandIn) {
+        let stand_in = StandIn::default();
+        let app = Router::new()
+            .route("/repos/acme/shop/issues", get(list_issues))
+            .route(
+                "/repos/acme/shop/issues/:number/labels",
+                get(get_labels).post(add_labels),
+            )
+            .route(
+                "/repos/acme/shop/issues/:number/labels/:name",
+                delete(remove_label),
+            )
+            .route("/graphql", post(graphql))
+            .with_state(stand_in.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let config = GitHubConfig {
+            api_url: Arc::from(format!("http://{address}")),
+            target,
+            ..GitHubConfig::new("acme/shop", "secret", "3", "", "").unwrap()
+        };
+        let client = GitHubClient::new(config).with_retry(Retry {
+            attempts: 1,
+            backoff: Duration::from_millis(1),
+        });
+        (client, stand_in)
+    }
+
+    #[tokio::test]
+    async fn pulls_open_issues_without_pull_requests() {
+        let (client, stand_in) = spawn_stand_in(EstimateTarget::Label).await;
+        let stories = client.open_issues().await.unwrap();
+
+        assert_eq!(stories.len(), 51);
+        assert_eq!(stories[0].key.as_deref(), Some("#1"));
+        assert_eq!(&*stories[0].title, "Issue 1");
+        assert_eq!(
+            stories[0].link.as_deref(),
+            Some("https://github.com/acme/shop/issues/1")
+        );
+        assert_eq!(stories[50].key.as_deref(), Some("#101"));
+        assert_eq!(
+            stand_in.requests(),
+            vec!["GET issues Some(\"3\")", "GET issues Some(\"3\")"]
+        );
+    }
+
+    #[tokio::test]
+    async fn replaces_points_label() {
+        let (client, stand_in) = spawn_stand_in(EstimateTarget::Label).await;
+        *stand_in.labels.lock().unwrap() = vec!["bug".to_string(), "points: 3".to_string()];
+
+        client
+            .write_estimate(7, &Estimate::from("5"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            stand_in.requests(),
+            vec!["DELETE label #7 points: 3", "POST labels #7"]
+        );
+        assert_eq!(
+            stand_in.received.lock().unwrap()[1].1,
+            json!({ "labels": ["points: 5"] })
+        );
+    }
+
+    #[tokio::test]
+    async fn writes_project_field() {
+        let target = EstimateTarget::ProjectField {
+            project_id: Arc::from("PVT_1"),
+            field_id: Arc::from("PVTF_points"),
+        };
+        let (client, stand_in) = spawn_stand_in(target).await;
+        *stand_in.project_items.lock().unwrap() = vec![
+            json!({ "id": "PVTI_other", "project": { "id": "PVT_2" } }),
+            json!({ "id": "PVTI_1", "project": { "id": "PVT_1" } }),
+        ];
+
+        client
+            .write_estimate(7, &Estimate::from("8"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            stand_in.requests(),
+            vec!["GRAPHQL issue", "GRAPHQL updateProjectV2ItemFieldValue"]
+        );
+        assert_eq!(
+            stand_in.received.lock().unwrap()[1].1,
+            json!({ "project": "PVT_1", "item": "PVTI_1", "field": "PVTF_points", "points": 8.0 })
+        );
+    }
+
+    #[tokio::test]
+    async fn adds_issue_to_project_first() {
+        let target = EstimateTarget::ProjectField {
+            project_id: Arc::from("PVT_1"),
+            field_id: Arc::from("PVTF_points"),
+        };
+        let (client, stand_in) = spawn_stand_in(target).await;
+
+        client
+            .write_estimate(7, &Estimate::from("8"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            stand_in.requests(),
+            vec![
+                "GRAPHQL issue",
+                "GRAPHQL addProjectV2ItemById",
+                "GRAPHQL updateProjectV2ItemFieldValue"
+            ]
+        );
+        assert_eq!(
+            client
+                .write_estimate(7, &Estimate::QuestionMark)
+                .await
+                .map_err(|e| e.to_string()),
+            Err("connector error: ? is not a number of story points".to_string())
+        );
+    }
+
+    #[test]
+    fn validates_config() {
+        assert_eq!(GitHubConfig::new("acme", "secret", "", "", ""), None);
+        assert_eq!(GitHubConfig::new("acme/shop", "", "", "", ""), None);
+        assert_eq!(
+            GitHubConfig::new("acme/../shop", "secret", "", "", ""),
+            None
+        );
+        assert_eq!(GitHubConfig::new("acme/..", "secret", "", "", ""), None);
+        let config = GitHubConfig::new(" acme/shop ", "secret", "", "PVT_1", "").unwrap();
+        assert_eq!(&*config.repository(), "acme/shop");
+        assert_eq!(config.milestone, None);
+        assert_eq!(config.target, EstimateTarget::Label);
+    }
+
+    #[test]
+    fn finds_the_graphql_endpoint_next_to_the_rest_one() {
+        let client = |api_url: &str| {
+            GitHubClient::new(GitHubConfig {
+                api_url: Arc::from(api_url),
+                ..GitHubConfig::new("acme/shop", "secret", "", "", "").unwrap()
+            })
+        };
+        assert_eq!(
+            client("https://api.github.com")
+                .graphql_url()
+                .unwrap()
+                .as_str(),
+            "https://api.github.com/graphql"
+        );
+        assert_eq!(
+            client("https://github.acme.com/api/v3")
+                .graphql_url()
+                .unwrap()
+                .as_str(),
+            "https://github.acme.com/api/graphql"
+        );
+        let invalid = client("github.acme.com");
+        assert!(matches!(invalid.graphql_url(), Err(ScError::InvalidUrl(_))));
+        assert!(matches!(
+            invalid.repo_url(&["issues"]),
+            Err(ScError::InvalidUrl(_))
+        ));
+    }
+}
@@ -0,0 +1,173 @@
+use crate::{
+    database::{self, Pool},
+    error::ScError,
+    room::RoomId,
+    room_pool::{self, CtrlMessage, CtrlRequest, CtrlResponse, HealthStatus, RoomPoolChannel},
+    shutdown::Shutdown,
+};
+use serde::Serialize;
+use std::time::Duration;
+use tokio::{sync::mpsc, time::timeout};
+
+/// Time each dependency gets to answer, shorter than the timeout of the platform's health checks.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Only a ready instance should get traffic, the others answer `/readyz` with a 503.
+#[derive(PartialEq, Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Readiness {
+    Ready,
+    /// Some rooms do not answer or report themselves degraded.
+    Degraded,
+    /// The database or the room pool is out of reach.
+    Unavailable,
+    /// The server shuts down and spawns no more rooms.
+    Draining,
+}
+
+impl Readiness {
+    fn of(draining: bool, database: bool, room_pool: bool, degraded_rooms: &[RoomId]) -> Readiness {
+        if draining {
+            Readiness::Draining
+        } else if !database || !room_pool {
+            Readiness::Unavailable
+        } else if !degraded_rooms.is_empty() {
+            Readiness::Degraded
+        } else {
+            Readiness::Ready
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReadinessReport {
+    pub status: Readiness,
+    pub database: bool,
+    pub room_pool: bool,
+    pub rooms: usize,
+    /// Rooms that did not answer their health check in time or answered it degraded.
+    pub degraded_rooms: Vec<RoomId>,
+}
+
+async fn check_database(pool: &Pool) -> bool {
+    let check = async {
+        database::connection(pool).await?.health().await?;
+        Ok::<(), ScError>(())
+    };
+    match timeout(CHECK_TIMEOUT, check).await {
+        Ok(Ok(())) => true,
+        Ok(Err(err)) => {
+            tracing::warn!("Database health check failed, error: {}", err);
+            false
+        }
+        Err(_) => {
+            tracing::warn!("Database health check timed out");
+            false
+        }
+    }
+}
+
+async fn check_room(ctrl: mpsc::Sender<CtrlMessage>) -> bool {
+    let check = room_pool::control(&ctrl, CtrlRequest::HealthCheck);
+    matches!(
+        timeout(CHECK_TIMEOUT, check).await,
+        Ok(Some(CtrlResponse::Health(HealthStatus::Healthy)))
+    )
+}
+
+/// Checks the database and the room pool, and fans a health check out to every room.
+pub async fn readiness(
+    pool: &Pool,
+    room_pool: &RoomPoolChannel,
+    shutdown: &Shutdown,
+) -> ReadinessReport {
+    let (database, controls) = tokio::join!(
+        check_database(pool),
+        timeout(CHECK_TIMEOUT, room_pool.controls())
+    );
+    let controls = match controls {
+        Ok(Ok(controls)) => Some(controls),
+        Ok(Err(err)) => {
+            tracing::warn!("Room pool health check failed, error: {}", err);
+            None
+        }
+        Err(_) => {
+            tracing::warn!("Room pool health check timed out");
+            None
+        }
+    };
+    let room_pool = controls.is_some();
+    let controls = controls.unwrap_or_default();
+    let rooms = controls.len();
+
+    let checks = controls
+        .into_iter()
+        .map(|(room_id, ctrl)| async move { (room_id, check_room(ctrl).await) });
+    let degraded_rooms: Vec<RoomId> = futures::future::join_all(checks)
+        .await
+        .into_iter()
+        .filter(|(_, healthy)| !healthy)
+        .map(|(room_id, _)| room_id)
+        .collect();
+    if !degraded_rooms.is_empty() {
+        tracing::warn!("Rooms did not answer health check: {:?}", degraded_rooms);
+    }
+
+    ReadinessReport {
+        status: Readiness::of(shutdown.draining(), database, room_pool, &degraded_rooms),
+        database,
+        room_pool,
+        rooms,
+        degraded_rooms,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn rooms_that_do_not_answer_are_unhealthy() {
+        let (ctrl, mut ctrl_rx) = mpsc::channel::<CtrlMessage>(1);
+        tokio::spawn(async move {
+            while let Some((CtrlRequest::HealthCheck, tx)) = ctrl_rx.recv().await {
+                _ = tx.send(CtrlResponse::Health(HealthStatus::Healthy));
+            }
+        });
+        assert!(check_room(ctrl).await);
+
+        let (ctrl, _ctrl_rx) = mpsc::channel::<CtrlMessage>(1);
+        assert!(!check_room(ctrl).await);
+
+        let (ctrl, ctrl_rx) = mpsc::channel::<CtrlMessage>(1);
+        drop(ctrl_rx);
+        assert!(!check_room(ctrl).await);
+
+        let (ctrl, mut ctrl_rx) = mpsc::channel::<CtrlMessage>(1);
+        tokio::spawn(async move {
+            while let Some((CtrlRequest::HealthCheck, tx)) = ctrl_rx.recv().await {
+                _ = tx.send(CtrlResponse::Health(HealthStatus::Degraded));
+            }
+        });
+        assert!(!check_room(ctrl).await);
+    }
+
+    #[test]
+    fn readiness_follows_the_checks() {
+        let room_id: RoomId = "room1".into();
+        assert_eq!(Readiness::of(false, true, true, &[]), Readiness::Ready);
+        assert_eq!(
+            Readiness::of(false, true, true, std::slice::from_ref(&room_id)),
+            Readiness::Degraded
+        );
+        assert_eq!(
+            Readiness::of(false, false, true, &[room_id]),
+            Readiness::Unavailable
+        );
+        assert_eq!(
+            Readiness::of(false, true, false, &[]),
+            Readiness::Unavailable
+        );
+        assert_eq!(Readiness::of(true, true, true, &[]), Readiness::Draining);
+    }
+}
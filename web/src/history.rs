@@ -0,0 +1,62 @@
+use crate::{
+    database::{self, Pool},
+    error::ScError,
+    room::RoomId,
+    round::RoundRecord,
+};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+const ROUND_TABLE: &str = "round";
+
+pub async fn init(pool: &Pool) -> Result<(), ScError> {
+    let db = database::connection(pool).await?;
+    db.query(format!(
+        "DEFINE TABLE IF NOT EXISTS {ROUND_TABLE} SCHEMALESS;
+         DEFINE INDEX IF NOT EXISTS {ROUND_TABLE}_room_id ON {ROUND_TABLE} FIELDS room_id;"
+    ))
+    .await?;
+    Ok(())
+}
+
+pub async fn save_round(pool: &Pool, round: &RoundRecord) -> Result<(), ScError> {
+    let db = database::connection(pool).await?;
+    db.query("UPSERT type::thing($table, [$room_id, $round_id]) CONTENT $round")
+        .bind(("table", ROUND_TABLE))
+        .bind(("room_id", round.room_id.clone()))
+        .bind(("round_id", round.round_id.clone()))
+        .bind(("round", round.clone()))
+        .await?;
+    Ok(())
+}
+
+/// Every recorded round of the room, oldest first.
+pub async fn load_rounds(pool: &Pool, room_id: &RoomId) -> Result<Vec<RoundRecord>, ScError> {
+    let db = database::connection(pool).await?;
+    let mut response = db
+        .query("SELECT * FROM type::table($table) WHERE room_id = $room_id")
+        .bind(("table", ROUND_TABLE))
+        .bind(("room_id", room_id.clone()))
+        .await?;
+    let mut rounds: Vec<RoundRecord> = response.take(0)?;
+    rounds.sort_by_key(|round| round.revealed_at);
+    Ok(rounds)
+}
+
+/// Saves rounds in the background, in the order they are sent, so the room never waits on the database.
+pub fn spawn_writer(pool: Arc<Pool>) -> mpsc::UnboundedSender<RoundRecord> {
+    let (tx, mut rx) = mpsc::unbounded_channel::<RoundRecord>();
+    tokio::spawn(async move {
+        while let Some(round) = rx.recv().await {
+            if let Err(err) = save_round(&pool, &round).await {
+                tracing::error!(
+                    "Failed to save round {} of room {}, error: {}",
+                    round.round_id,
+                    round.room_id,
+                    err
+                );
+            }
+        }
+    });
+    tx
+}
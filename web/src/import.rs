@@ -0,0 +1,558 @@
+use crate::story::Story;
+use quick_xml::{events::Event, Reader};
+use serde_json::Value;
+use std::fmt;
+
+/// Upper bound on the stories taken from one file. Further rows are reported as skipped.
+pub const MAX_IMPORTED_STORIES: usize = 200;
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum ImportFormat {
+    /// Plain CSV with `key`, `title`, `link` and `description` columns.
+    Csv,
+    JiraCsv,
+    JiraXml,
+    GitHubJson,
+}
+
+impl ImportFormat {
+    /// Detects the format from the file extension, falling back to the content.
+    pub fn detect(file_name: &str, content: &str) -> ImportFormat {
+        let extension = file_name
+            .rsplit_once('.')
+            .map(|(_, extension)| extension.to_ascii_lowercase());
+        let content = content.trim_start_matches('\u{feff}').trim_start();
+        match extension.as_deref() {
+            Some("json") => ImportFormat::GitHubJson,
+            Some("xml") => ImportFormat::JiraXml,
+            _ if content.starts_with('[') || content.starts_with('{') => ImportFormat::GitHubJson,
+            _ if content.starts_with('<') => ImportFormat::JiraXml,
+            _ => {
+                let header = content.lines().next().unwrap_or_default();
+                if header.to_ascii_lowercase().contains("issue key") {
+                    ImportFormat::JiraCsv
+                } else {
+                    ImportFormat::Csv
+                }
+            }
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct ImportError {
+    /// Row of the file the error belongs to, `None` when the whole file could not be read.
+    pub row: Option<usize>,
+    pub message: String,
+}
+
+impl ImportError {
+    fn file(message: impl Into<String>) -> ImportError {
+        ImportError {
+            row: None,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.row {
+            Some(row) => write!(f, "Row {}: {}", row, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Stories parsed from a file together with the rows that had to be left out.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct Import {
+    pub stories: Vec<Story>,
+    pub errors: Vec<ImportError>,
+}
+
+impl Import {
+    fn push(&mut self, row: usize, story: Result<Story, String>) {
+        match story {
+            Ok(_) if self.stories.len() >= MAX_IMPORTED_STORIES => self.errors.push(ImportError {
+                row: Some(row),
+                message: format!("skipped, a file can add at most {MAX_IMPORTED_STORIES} stories"),
+            }),
+            Ok(story) => self.stories.push(story),
+            Err(message) => self.errors.push(ImportError {
+                row: Some(row),
+                message,
+            }),
+        }
+    }
+}
+
+pub fn parse(file_name: &str, content: &str) -> Import {
+    let content = content.trim_start_matches('\u{feff}');
+    match ImportFormat::detect(file_name, content) {
+        ImportFormat::Csv => parse_csv(content, &CSV_COLUMNS),
+        ImportFormat::JiraCsv => parse_csv(content, &JIRA_CSV_COLUMNS),
+        ImportFormat::JiraXml => parse_jira_xml(content),
+        ImportFormat::GitHubJson => parse_github_json(content),
+    }
+}
+
+fn story(key: &str, title: &str, link: &str, description: &str) -> Result<Story, String> {
+    Story::new(title, link, description)
+        .map(|story| story.with_key(key))
+        .ok_or_else(|| "missing title".to_string())
+}
+
+/// Accepted header names per story field, compared case insensitively.
+struct Columns {
+    key: &'static [&'static str],
+    title: &'static [&'static str],
+    link: &'static [&'static str],
+    description: &'static [&'static str],
+}
+
+const CSV_COLUMNS: Columns = Columns {
+    key: &["key"],
+    title: &["title"],
+    link: &["link", "url"],
+    description: &["description"],
+};
+
+const JIRA_CSV_COLUMNS: Columns = Columns {
+    key: &["issue key"],
+    title: &["summary"],
+    link: &[],
+    description: &["description"],
+};
+
+fn parse_csv(content: &str, columns: &Columns) -> Import {
+    let mut import = Import::default();
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(content.as_bytes());
+    let headers = match reader.headers() {
+        Ok(headers) => headers.clone(),
+        Err(err) => {
+            import.errors.push(ImportError::file(err.to_string()));
+            return import;
+        }
+    };
+    let column = |names: &[&str]| {
+        headers
+            .iter()
+            .position(|header| names.iter().any(|name| header.eq_ignore_ascii_case(name)))
+    };
+    let Some(title) = column(columns.title) else {
+        import.errors.push(ImportError::file(format!(
+            "Missing a \"{}\" column",
+            columns.title[0]
+        )));
+        return import;
+    };
+    let (key, link, description) = (
+        column(columns.key),
+        column(columns.link),
+        column(columns.description),
+    );
+
+    for (index, record) in reader.records().enumerate() {
+        // Row 1 is the header.
+        let row = index + 2;
+        let story = record.map_err(|err| err.to_string()).and_then(|record| {
+            let field = |column: Option<usize>| {
+                column
+                    .and_then(|column| record.get(column))
+                    .unwrap_or_default()
+            };
+            story(
+                field(key),
+                field(Some(title)),
+                field(link),
+                field(description),
+            )
+        });
+        import.push(row, story);
+    }
+    import
+}
+
+#[derive(Default)]
+struct JiraItem {
+    key: String,
+    title: String,
+    summary: String,
+    link: String,
+    description: String,
+}
+
+impl JiraItem {
+    fn field(&mut self, name: &[u8]) -> Option<&mut String> {
+        match name {
+            b"key" => Some(&mut self.key),
+            b"title" => Some(&mut self.title),
+            b"summary" => Some(&mut self.summary),
+            b"link" => Some(&mut self.link),
+            b"description" => Some(&mut self.description),
+            _ => None,
+        }
+    }
+
+    fn into_story(self) -> Result<Story, String> {
+        // The title is "[KEY] summary", prefer the bare summary when the export has it.
+        let title = if self.summary.trim().is_empty() {
+            &self.title
+        } else {
+            &self.summary
+        };
+        story(&self.key, title, &self.link, &strip_html(&self.description))
+    }
+}
+
+/// Parses the RSS flavoured XML of a Jira issue search export.
+fn parse_jira_xml(content: &str) -> Import {
+    let mut import = Import::default();
+    let mut reader = Reader::from_str(content);
+    reader.config_mut().trim_text(true);
+    let mut path: Vec<Vec<u8>> = Vec::new();
+    let mut item: Option<JiraItem> = None;
+    let mut items = 0;
+
+    loop {
+        let event = match reader.read_event() {
+            Ok(event) => event,
+            Err(err) => {
+                import.errors.push(ImportError::file(format!(
+                    "Invalid XML at position {}: {}",
+                    reader.error_position(),
+                    err
+                )));
+                break;
+            }
+        };
+        let text = match event {
+            Event::Start(start) => {
+                let name = start.local_name().as_ref().to_vec();
+                if name == b"item" {
+                    item = Some(JiraItem::default());
+                    items += 1;
+                }
+                path.push(name);
+                continue;
+            }
+            Event::End(_) => {
+                if path.pop().as_deref() == Some(b"item") {
+                    if let Some(item) = item.take() {
+                        import.push(items, item.into_story());
+                    }
+                }
+                continue;
+            }
+            Event::Text(text) => text
+                .unescape()
+                .map(|text| text.into_owned())
+                .unwrap_or_else(|_| String::from_utf8_lossy(&text).into_owned()),
+            Event::CData(data) => String::from_utf8_lossy(&data).into_owned(),
+            Event::Eof => break,
+            _ => continue,
+        };
+        // Only direct children of an item, nested elements like comments have their own fields.
+        if let (Some(item), [.., parent, name]) = (item.as_mut(), path.as_slice()) {
+            if parent.as_slice() == b"item" {
+                if let Some(field) = item.field(name) {
+                    field.push_str(&text);
+                }
+            }
+        }
+    }
+
+    if items == 0 && import.errors.is_empty() {
+        import
+            .errors
+            .push(ImportError::file("No Jira issues found in the XML file"));
+    }
+    import
+}
+
+/// Parses the JSON array returned by the GitHub issues API or `gh issue list --json`.
+fn parse_github_json(content: &str) -> Import {
+    let mut import = Import::default();
+    let issues = match serde_json::from_str::<Value>(content) {
+        Ok(Value::Array(issues)) => issues,
+        Ok(_) => {
+            import
+                .errors
+                .push(ImportError::file("Expected a JSON array of issues"));
+            return import;
+        }
+        Err(err) => {
+            import
+                .errors
+                .push(ImportError::file(format!("Invalid JSON: {err}")));
+            return import;
+        }
+    };
+
+    for (index, issue) in issues.iter().enumerate() {
+        let row = index + 1;
+        if !issue.is_object() {
+            import.push(row, Err("expected an issue object".to_string()));
+            continue;
+        }
+        let text = |field: &str| issue.get(field).and_then(Value::as_str).unwrap_or_default();
+        let key = issue
+            .get("number")
+            .and_then(Value::as_u64)
+            .map(|number| format!("#{number}"))
+            .unwrap_or_default();
+        // The REST API puts the web page in `html_url` and the API endpoint in `url`.
+        let link = match text("html_url") {
+            "" => text("url"),
+            html_url => html_url,
+        };
+        import.push(row, story(&key, text("title"), link, text("body")));
+    }
+    import
+}
+
+/// Drops the markup and common entities from an HTML description and collapses the blank lines
+/// it leaves behind.
+fn strip_html(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_format() {
+        assert_eq!(
+            ImportFormat::detect("issues.json", ""),
+            ImportFormat::GitHubJson
+        );
+        assert_eq!(
+            ImportFormat::detect("SearchRequest.XML", ""),
+            ImportFormat::JiraXml
+        );
+        assert_eq!(
+            ImportFormat::detect("stories", "  [{}]"),
+            ImportFormat::GitHubJson
+        );
+        assert_eq!(
+            ImportFormat::detect("stories", "<rss>"),
+            ImportFormat::JiraXml
+        );
+        assert_eq!(
+            ImportFormat::detect("export.csv", "Summary,Issue key,Issue id\n"),
+            ImportFormat::JiraCsv
+        );
+        assert_eq!(
+            ImportFormat::detect("stories.csv", "key,title\n"),
+            ImportFormat::Csv
+        );
+    }
+
+    #[test]
+    fn csv_in_any_column_order() {
+        let import = parse(
+            "stories.csv",
+            "\u{feff}Title,Description,Key,Link\n\
+             Login page,\"Users can log in,\nwith a password\",WEB-1,https://example.com/WEB-1\n\
+             Logout,,WEB-2,not a link\n",
+        );
+        assert_eq!(import.errors, vec![]);
+        assert_eq!(import.stories.len(), 2);
+        let login = &import.stories[0];
+        assert_eq!(&*login.title, "Login page");
+        assert_eq!(login.key.as_deref(), Some("WEB-1"));
+        assert_eq!(login.link.as_deref(), Some("https://example.com/WEB-1"));
+        assert_eq!(
+            login.description.as_deref(),
+            Some("Users can log in,\nwith a password")
+        );
+        let logout = &import.stories[1];
+        assert_eq!(logout.link, None);
+        assert_eq!(logout.description, None);
+    }
+
+    #[test]
+    fn csv_reports_rows_without_title() {
+        let import = parse("stories.csv", "key,title\nA-1,First\nA-2,\nA-3,Third\n");
+        assert_eq!(import.stories.len(), 2);
+        assert_eq!(
+            import.errors,
+            vec![ImportError {
+                row: Some(3),
+                message: "missing title".to_string()
+            }]
+        );
+        assert_eq!(import.errors[0].to_string(), "Row 3: missing title");
+    }
+
+    #[test]
+    fn csv_drops_keys_that_are_no_issue_keys() {
+        let import = parse(
+            "stories.csv",
+            "key,title\n../../rest/api/2/user?x=1,First\n A-2 ,Second\n",
+        );
+        assert_eq!(import.stories[0].key, None);
+        assert_eq!(import.stories[1].key.as_deref(), Some("A-2"));
+    }
+
+    #[test]
+    fn csv_without_title_column() {
+        let import = parse("stories.csv", "key,name\nA-1,First\n");
+        assert!(import.stories.is_empty());
+        assert_eq!(
+            import.errors,
+            vec![ImportError::file("Missing a \"title\" column")]
+        );
+    }
+
+    #[test]
+    fn jira_csv() {
+        let import = parse(
+            "Jira.csv",
+            "Summary,Issue key,Issue id,Issue Type,Description\n\
+             Checkout flow,SHOP-12,10012,Story,Pay with a card\n",
+        );
+        assert_eq!(import.errors, vec![]);
+        let story = &import.stories[0];
+        assert_eq!(&*story.title, "Checkout flow");
+        assert_eq!(story.key.as_deref(), Some("SHOP-12"));
+        assert_eq!(story.description.as_deref(), Some("Pay with a card"));
+    }
+
+    #[test]
+    fn jira_xml() {
+        let import = parse(
+            "SearchRequest.xml",
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+            <rss version="0.92">
+              <channel>
+                <title>Jira</title>
+                <link>https://jira.example.com</link>
+                <item>
+                  <title>[SHOP-12] Checkout flow</title>
+                  <link>https://jira.example.com/browse/SHOP-12</link>
+                  <key id="10012">SHOP-12</key>
+                  <summary>Checkout flow</summary>
+                  <description>&lt;p&gt;Pay with a card &amp;amp; PayPal&lt;/p&gt;</description>
+                  <comments>
+                    <comment id="1">&lt;p&gt;Not a description&lt;/p&gt;</comment>
+                  </comments>
+                </item>
+                <item>
+                  <title>[SHOP-13] Refunds</title>
+                  <key id="10013">SHOP-13</key>
+                </item>
+                <item>
+                  <key id="10014">SHOP-14</key>
+                </item>
+              </channel>
+            </rss>"#,
+        );
+        assert_eq!(import.stories.len(), 2);
+        let checkout = &import.stories[0];
+        assert_eq!(&*checkout.title, "Checkout flow");
+        assert_eq!(checkout.key.as_deref(), Some("SHOP-12"));
+        assert_eq!(
+            checkout.link.as_deref(),
+            Some("https://jira.example.com/browse/SHOP-12")
+        );
+        assert_eq!(
+            checkout.description.as_deref(),
+            Some("Pay with a card & PayPal")
+        );
+        assert_eq!(&*import.stories[1].title, "[SHOP-13] Refunds");
+        assert_eq!(
+            import.errors,
+            vec![ImportError {
+                row: Some(3),
+                message: "missing title".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn invalid_jira_xml() {
+        let import = parse("SearchRequest.xml", "<rss><channel><item></channel></rss>");
+        assert!(import.stories.is_empty());
+        assert_eq!(import.errors.len(), 1);
+        assert_eq!(import.errors[0].row, None);
+    }
+
+    #[test]
+    fn github_json() {
+        let import = parse(
+            "issues.json",
+            r#"[
+                {"number": 42, "title": "Dark mode", "html_url": "https://github.com/o/r/issues/42",
+                 "url": "https://api.github.com/repos/o/r/issues/42", "body": "Please"},
+                {"number": 43, "title": "Flaky test", "url": "https://github.com/o/r/issues/43", "body": null},
+                {"number": 44, "title": ""},
+                "not an issue"
+            ]"#,
+        );
+        assert_eq!(import.stories.len(), 2);
+        let dark_mode = &import.stories[0];
+        assert_eq!(dark_mode.key.as_deref(), Some("#42"));
+        assert_eq!(
+            dark_mode.link.as_deref(),
+            Some("https://github.com/o/r/issues/42")
+        );
+        assert_eq!(dark_mode.description.as_deref(), Some("Please"));
+        assert_eq!(
+            import.stories[1].link.as_deref(),
+            Some("https://github.com/o/r/issues/43")
+        );
+        assert_eq!(
+            import.errors.iter().map(|e| e.row).collect::<Vec<_>>(),
+            vec![Some(3), Some(4)]
+        );
+    }
+
+    #[test]
+    fn github_json_not_an_array() {
+        let import = parse("issues.json", r#"{"message": "Not Found"}"#);
+        assert_eq!(
+            import.errors,
+            vec![ImportError::file("Expected a JSON array of issues")]
+        );
+        let import = parse("issues.json", "[");
+        assert!(import.errors[0].message.starts_with("Invalid JSON"));
+    }
+
+    #[test]
+    fn import_is_capped() {
+        let content = (0..=MAX_IMPORTED_STORIES).fold("title\n".to_string(), |csv, i| {
+            csv + &format!("Story {i}\n")
+        });
+        let import = parse("stories.csv", &content);
+        assert_eq!(import.stories.len(), MAX_IMPORTED_STORIES);
+        assert_eq!(import.errors.len(), 1);
+        assert_eq!(import.errors[0].row, Some(MAX_IMPORTED_STORIES + 2));
+    }
+}
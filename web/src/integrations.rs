@@ -0,0 +1,522 @@
+use crate::{
+    app::use_app_props,
+    channel::{Rejection, RoomRequest, RoomResponse},
+    chat,
+    error::ScError,
+    estimate::CardDeck,
+    github::GitHubConfig,
+    jira::JiraConfig,
+    room::Participant,
+    round::{RoundRecord, SyncStatus},
+    story::StoryQueue,
+    validate,
+    webhook::{WebhookConfig, WebhookEvent},
+};
+use dioxus::prelude::*;
+use std::{collections::HashMap, sync::Arc};
+use uuid::Uuid;
+
+const INPUT_STYLE: &str = "bg-white border border-slate-300 rounded-full px-4 py-2 focus:outline-none focus:ring-2 focus:ring-slate-600";
+const BUTTON_STYLE: &str = "rounded-full px-4 py-2 font-bold text-slate-600 bg-white border border-slate-300 hover:bg-slate-100";
+const NOT_FORWARDED: &str =
+    "The room runs on another server, credentials can't be changed from here. Try again later.";
+
+/// Whether the room took the change, credentials are not forwarded to a room on another instance.
+fn forwarded(response: Result<RoomResponse, ScError>) -> bool {
+    !matches!(
+        response,
+        Ok(RoomResponse::Rejected(Rejection::NotForwarded))
+    )
+}
+
+/// Connects the room to the Jira instance final estimates are written to.
+#[component]
+pub fn JiraSettings(jira: Signal<Option<Arc<str>>>) -> Element {
+    let app_props = use_app_props();
+    let mut editing = use_signal(|| false);
+    let mut base_url = use_signal(String::new);
+    let mut email = use_signal(String::new);
+    let mut token = use_signal(String::new);
+    let mut story_points_field = use_signal(String::new);
+    let mut not_forwarded = use_signal(|| false);
+
+    let send_config = move |config: Option<JiraConfig>| async move {
+        let response = app_props()
+            .channel
+            .send(RoomRequest::ChangeJiraConfig(
+                app_props().session_id,
+                config,
+            ))
+            .await;
+        not_forwarded.set(!forwarded(response));
+        !not_forwarded()
+    };
+
+    rsx! {
+        div { class: "flex flex-wrap items-center gap-x-4 gap-y-2 text-slate-600",
+            if let Some(base_url) = jira() {
+                span { class: "text-sm", "Jira: {base_url}" }
+            }
+            button {
+                class: "{BUTTON_STYLE}",
+                onclick: move |_| editing.toggle(),
+                if jira().is_some() {
+                    "Change Jira"
+                } else {
+                    "Connect Jira"
+                }
+            }
+            if jira().is_some() {
+                button {
+                    class: "{BUTTON_STYLE}",
+                    onclick: move |_| async move {
+                        send_config(None).await;
+                    },
+                    "Disconnect"
+                }
+            }
+            if not_forwarded() {
+                span { class: "text-sm text-red-600", "{NOT_FORWARDED}" }
+            }
+            if editing() {
+                div { class: "flex flex-wrap items-center gap-2 w-full",
+                    input {
+                        r#type: "url",
+                        class: "flex-1 {INPUT_STYLE}",
+                        placeholder: "https://your-company.atlassian.net",
+                        autocomplete: "off",
+                        oninput: move |evt| base_url.set(evt.value()),
+                    }
+                    input {
+                        r#type: "email",
+                        class: "flex-1 {INPUT_STYLE}",
+                        placeholder: "Email (Jira Cloud)",
+                        autocomplete: "off",
+                        oninput: move |evt| email.set(evt.value()),
+                    }
+                    input {
+                        r#type: "password",
+                        class: "flex-1 {INPUT_STYLE}",
+                        placeholder: "API token",
+                        autocomplete: "off",
+                        oninput: move |evt| token.set(evt.value()),
+                    }
+                    input {
+                        r#type: "text",
+                        class: "flex-1 {INPUT_STYLE}",
+                        placeholder: "customfield_10016",
+                        autocomplete: "off",
+                        oninput: move |evt| story_points_field.set(evt.value()),
+                    }
+                    button {
+                        class: "rounded-full px-4 py-2 font-bold text-white bg-slate-600 hover:bg-slate-500",
+                        onclick: move |_| {
+                            let config = JiraConfig::new(
+                                &base_url(),
+                                &email(),
+                                &token(),
+                                &story_points_field(),
+                            );
+                            async move {
+                                if config.is_some() && send_config(config).await {
+                                    editing.set(false);
+                                }
+                            }
+                        },
+                        "Save"
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Connects the room to the GitHub repository its stories come from.
+#[component]
+pub fn GitHubSettings(
+    github: Signal<Option<Arc<str>>>,
+    issues_pulled: Signal<Option<Result<usize, Arc<str>>>>,
+) -> Element {
+    let app_props = use_app_props();
+    let mut editing = use_signal(|| false);
+    let mut repository = use_signal(String::new);
+    let mut token = use_signal(String::new);
+    let mut milestone = use_signal(String::new);
+    let mut project_id = use_signal(String::new);
+    let mut field_id = use_signal(String::new);
+    let mut not_forwarded = use_signal(|| false);
+
+    let send = move |request: RoomRequest| async move {
+        let response = app_props().channel.send(request).await;
+        not_forwarded.set(!forwarded(response));
+        !not_forwarded()
+    };
+
+    rsx! {
+        div { class: "flex flex-wrap items-center gap-x-4 gap-y-2 text-slate-600",
+            if let Some(repository) = github() {
+                span { class: "text-sm", "GitHub: {repository}" }
+                button {
+                    class: "rounded-full px-4 py-2 font-bold text-white bg-slate-600 hover:bg-slate-500",
+                    onclick: move |_| {
+                        issues_pulled.set(None);
+                        async move {
+                            send(RoomRequest::PullIssues(app_props().session_id)).await;
+                        }
+                    },
+                    "Pull issues"
+                }
+            }
+            match issues_pulled() {
+                Some(Ok(added)) => rsx! {
+                    span { class: "text-sm text-gray-500", "Added {added} issues" }
+                },
+                Some(Err(reason)) => rsx! {
+                    span { class: "text-sm text-red-600", title: "{reason}", "Pulling issues failed" }
+                },
+                None => rsx! {},
+            }
+            button {
+                class: "{BUTTON_STYLE}",
+                onclick: move |_| editing.toggle(),
+                if github().is_some() {
+                    "Change GitHub"
+                } else {
+                    "Connect GitHub"
+                }
+            }
+            if github().is_some() {
+                button {
+                    class: "{BUTTON_STYLE}",
+                    onclick: move |_| async move {
+                        send(RoomRequest::ChangeGitHubConfig(app_props().session_id, None)).await;
+                    },
+                    "Disconnect"
+                }
+            }
+            if not_forwarded() {
+                span { class: "text-sm text-red-600", "{NOT_FORWARDED}" }
+            }
+            if editing() {
+                div { class: "flex flex-wrap items-center gap-2 w-full",
+                    input {
+                        r#type: "text",
+                        class: "flex-1 {INPUT_STYLE}",
+                        placeholder: "owner/repo",
+                        autocomplete: "off",
+                        oninput: move |evt| repository.set(evt.value()),
+                    }
+                    input {
+                        r#type: "password",
+                        class: "flex-1 {INPUT_STYLE}",
+                        placeholder: "Token",
+                        autocomplete: "off",
+                        oninput: move |evt| token.set(evt.value()),
+                    }
+                    input {
+                        r#type: "number",
+                        class: "w-32 {INPUT_STYLE}",
+                        placeholder: "Milestone",
+                        min: "1",
+                        oninput: move |evt| milestone.set(evt.value()),
+                    }
+                    input {
+                        r#type: "text",
+                        class: "flex-1 {INPUT_STYLE}",
+                        placeholder: "Project id (optional)",
+                        autocomplete: "off",
+                        oninput: move |evt| project_id.set(evt.value()),
+                    }
+                    input {
+                        r#type: "text",
+                        class: "flex-1 {INPUT_STYLE}",
+                        placeholder: "Points field id (optional)",
+                        autocomplete: "off",
+                        oninput: move |evt| field_id.set(evt.value()),
+                    }
+                    button {
+                        class: "rounded-full px-4 py-2 font-bold text-white bg-slate-600 hover:bg-slate-500",
+                        onclick: move |_| {
+                            let config = GitHubConfig::new(
+                                &repository(),
+                                &token(),
+                                &milestone(),
+                                &project_id(),
+                                &field_id(),
+                            );
+                            async move {
+                                if config.is_some()
+                                    && send(RoomRequest::ChangeGitHubConfig(app_props().session_id, config.map(Box::new)))
+                                        .await
+                                {
+                                    editing.set(false);
+                                }
+                            }
+                        },
+                        "Save"
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Posts the selected room events to an endpoint of the team's own tooling.
+#[component]
+pub fn WebhookSettings(webhook: Signal<Option<Arc<str>>>) -> Element {
+    let app_props = use_app_props();
+    let mut editing = use_signal(|| false);
+    let mut url = use_signal(String::new);
+    let mut secret = use_signal(String::new);
+    let mut events = use_signal(|| WebhookEvent::ALL.to_vec());
+    let mut not_forwarded = use_signal(|| false);
+
+    let send_config = move |config: Option<WebhookConfig>| async move {
+        let response = app_props()
+            .channel
+            .send(RoomRequest::ChangeWebhook(
+                app_props().session_id,
+                config.map(Box::new),
+            ))
+            .await;
+        not_forwarded.set(!forwarded(response));
+        !not_forwarded()
+    };
+
+    rsx! {
+        div { class: "flex flex-wrap items-center gap-x-4 gap-y-2 text-slate-600",
+            if let Some(url) = webhook() {
+                span { class: "text-sm", "Webhook: {url}" }
+            }
+            button {
+                class: "{BUTTON_STYLE}",
+                onclick: move |_| editing.toggle(),
+                if webhook().is_some() {
+                    "Change webhook"
+                } else {
+                    "Add webhook"
+                }
+            }
+            if webhook().is_some() {
+                button {
+                    class: "{BUTTON_STYLE}",
+                    onclick: move |_| async move {
+                        send_config(None).await;
+                    },
+                    "Remove"
+                }
+            }
+            if not_forwarded() {
+                span { class: "text-sm text-red-600", "{NOT_FORWARDED}" }
+            }
+            if editing() {
+                div { class: "flex flex-wrap items-center gap-2 w-full",
+                    input {
+                        r#type: "url",
+                        class: "flex-1 {INPUT_STYLE}",
+                        placeholder: "https://",
+                        autocomplete: "off",
+                        oninput: move |evt| url.set(evt.value()),
+                    }
+                    input {
+                        r#type: "password",
+                        class: "flex-1 {INPUT_STYLE}",
+                        placeholder: "Signing secret",
+                        autocomplete: "off",
+                        oninput: move |evt| secret.set(evt.value()),
+                    }
+                    for event in WebhookEvent::ALL {
+                        label { class: "inline-flex items-center gap-x-2 text-sm cursor-pointer select-none",
+                            input {
+                                r#type: "checkbox",
+                                class: "w-4 h-4 accent-slate-600",
+                                checked: events.read().contains(&event),
+                                onchange: move |evt| {
+                                    if evt.checked() {
+                                        events.write().push(event);
+                                    } else {
+                                        events.write().retain(|e| *e != event);
+                                    }
+                                },
+                            }
+                            "{event.name()}"
+                        }
+                    }
+                    button {
+                        class: "rounded-full px-4 py-2 font-bold text-white bg-slate-600 hover:bg-slate-500",
+                        onclick: move |_| {
+                            let config = WebhookConfig::new(&url(), &secret(), events());
+                            async move {
+                                if config.is_some() && send_config(config).await {
+                                    editing.set(false);
+                                }
+                            }
+                        },
+                        "Save"
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Posts a summary of every revealed round to a Slack or Mattermost channel. The facilitator
+/// previews the message for the current round before enabling it.
+#[component]
+pub fn ChatSettings(
+    chat: Signal<bool>,
+    participants: Signal<HashMap<Uuid, Participant>>,
+    deck: Signal<CardDeck>,
+    stories: Signal<StoryQueue>,
+) -> Element {
+    let app_props = use_app_props();
+    let mut editing = use_signal(|| false);
+    let mut webhook_url = use_signal(String::new);
+    let mut not_forwarded = use_signal(|| false);
+
+    let send_webhook_url = move |url: Option<Arc<str>>| async move {
+        let response = app_props()
+            .channel
+            .send(RoomRequest::ChangeChatWebhook(app_props().session_id, url))
+            .await;
+        not_forwarded.set(!forwarded(response));
+        !not_forwarded()
+    };
+    let preview = chat::summary(
+        &app_props().room_id,
+        stories.read().current(),
+        &participants.read(),
+        &deck.read(),
+    );
+
+    rsx! {
+        div { class: "flex flex-wrap items-center gap-x-4 gap-y-2 text-slate-600",
+            if chat() {
+                span { class: "text-sm", "Chat notifications on" }
+                button {
+                    class: "{BUTTON_STYLE}",
+                    onclick: move |_| async move {
+                        send_webhook_url(None).await;
+                    },
+                    "Disable"
+                }
+            } else {
+                button {
+                    class: "{BUTTON_STYLE}",
+                    onclick: move |_| editing.toggle(),
+                    "Chat notifications"
+                }
+            }
+            if not_forwarded() {
+                span { class: "text-sm text-red-600", "{NOT_FORWARDED}" }
+            }
+            if editing() && !chat() {
+                div { class: "flex flex-col gap-2 w-full",
+                    input {
+                        r#type: "url",
+                        class: "{INPUT_STYLE}",
+                        placeholder: "https://hooks.slack.com/services/...",
+                        autocomplete: "off",
+                        oninput: move |evt| webhook_url.set(evt.value()),
+                    }
+                    span { class: "text-xs uppercase text-gray-500", "Preview" }
+                    p { class: "text-sm whitespace-pre-line bg-white rounded-lg shadow-md px-4 py-2",
+                        "{preview}"
+                    }
+                    button {
+                        class: "self-start rounded-full px-4 py-2 font-bold text-white bg-slate-600 hover:bg-slate-500",
+                        onclick: move |_| {
+                            let url = chat::webhook_url(&webhook_url());
+                            async move {
+                                if url.is_some() && send_webhook_url(url).await {
+                                    editing.set(false);
+                                }
+                            }
+                        },
+                        "Enable"
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Lets the facilitator link the revealed round to the issue its estimate belongs to.
+#[component]
+pub fn IssueKeyInput(rounds: Signal<Vec<RoundRecord>>) -> Element {
+    let app_props = use_app_props();
+    let current_key = rounds
+        .read()
+        .iter()
+        .rev()
+        .find(|round| round.reset_at.is_none())
+        .and_then(|round| round.issue_key.clone());
+    let mut typed_key = use_signal(String::new);
+
+    rsx! {
+        div { class: "flex flex-wrap items-center gap-2 text-slate-600",
+            span { class: "font-semibold", "Issue" }
+            input {
+                r#type: "text",
+                class: "w-40 {INPUT_STYLE}",
+                placeholder: "PROJ-123 or #42",
+                value: current_key.as_deref().unwrap_or_default(),
+                autocomplete: "off",
+                oninput: move |evt| typed_key.set(evt.value()),
+            }
+            button {
+                class: "{BUTTON_STYLE}",
+                onclick: move |_| {
+                    let issue_key = validate::issue_key(&typed_key());
+                    async move {
+                        if let Some(issue_key) = issue_key {
+                            _ = app_props()
+                                .channel
+                                .send(RoomRequest::AttachIssueKey(app_props().session_id, issue_key))
+                                .await;
+                        }
+                    }
+                },
+                "Attach"
+            }
+        }
+    }
+}
+
+#[component]
+pub fn SyncStatusBadge(round: RoundRecord, is_facilitator: Memo<bool>) -> Element {
+    let app_props = use_app_props();
+    let Some(status) = round.sync.clone() else {
+        return rsx! {};
+    };
+    let round_id = round.round_id.clone();
+
+    rsx! {
+        match status {
+            SyncStatus::Pending => rsx! {
+                span { class: "text-xs text-gray-500 animate-pulse", "Sending…" }
+            },
+            SyncStatus::Synced => rsx! {
+                span { class: "text-xs text-green-600", "Synced" }
+            },
+            SyncStatus::Failed(reason) => rsx! {
+                span { class: "text-xs text-red-600", title: "{reason}", "Sync failed" }
+                if is_facilitator() {
+                    button {
+                        class: "ml-2 text-xs underline text-slate-600",
+                        onclick: move |_| {
+                            let round_id = round_id.clone();
+                            async move {
+                                _ = app_props()
+                                    .channel
+                                    .send(RoomRequest::SyncEstimate(app_props().session_id, round_id))
+                                    .await;
+                            }
+                        },
+                        "Retry"
+                    }
+                }
+            },
+        }
+    }
+}
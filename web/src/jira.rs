@@ -0,0 +1,342 @@
+use crate::{
+    connector::{self, Retry},
+    error::ScError,
+};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{env, fmt, sync::Arc};
+
+const DEFAULT_STORY_POINTS_FIELD: &str = "customfield_10016";
+
+/// Connection to the Jira instance final estimates are written to.
+#[derive(PartialEq, Clone, Serialize, Deserialize)]
+pub struct JiraConfig {
+    pub base_url: Arc<str>,
+    /// Account email for Jira Cloud API tokens. Personal access tokens of Jira Server go without.
+    pub email: Option<Arc<str>>,
+    pub token: Arc<str>,
+    /// Id of the story points field, e.g. `customfield_10016`.
+    pub story_points_field: Arc<str>,
+    /// Keys of the projects whose issues may be written to, any project when empty.
+    #[serde(default)]
+    pub projects: Vec<Arc<str>>,
+}
+
+impl JiraConfig {
+    /// Validates the connection details entered by the facilitator.
+    pub fn new(
+        base_url: &str,
+        email: &str,
+        token: &str,
+        story_points_field: &str,
+    ) -> Option<JiraConfig> {
+        let base_url = base_url.trim().trim_end_matches('/');
+        let token = token.trim();
+        if connector::public_url(base_url).is_none() || token.is_empty() {
+            return None;
+        }
+        let email = email.trim();
+        let story_points_field = match story_points_field.trim() {
+            "" => DEFAULT_STORY_POINTS_FIELD,
+            field => field,
+        };
+        Some(JiraConfig {
+            base_url: Arc::from(base_url),
+            email: (!email.is_empty()).then(|| Arc::from(email)),
+            token: Arc::from(token),
+            story_points_field: Arc::from(story_points_field),
+            projects: Vec::new(),
+        })
+    }
+
+    /// Deployment wide connection from `JIRA_BASE_URL`, `JIRA_EMAIL`, `JIRA_TOKEN` and
+    /// `JIRA_STORY_POINTS_FIELD`, used by rooms that have not configured their own.
+    ///
+    /// Anyone opening a room facilitates it, so the connection only writes to the projects of
+    /// the comma separated `JIRA_PROJECTS`, and is off without them.
+    pub fn from_env() -> Option<JiraConfig> {
+        let var = |name: &str| env::var(name).unwrap_or_default();
+        let config = JiraConfig::new(
+            &var("JIRA_BASE_URL"),
+            &var("JIRA_EMAIL"),
+            &var("JIRA_TOKEN"),
+            &var("JIRA_STORY_POINTS_FIELD"),
+        )?;
+        let projects: Vec<Arc<str>> = var("JIRA_PROJECTS")
+            .split(',')
+            .map(str::trim)
+            .filter(|project| !project.is_empty())
+            .map(Arc::from)
+            .collect();
+        if projects.is_empty() {
+            tracing::warn!("JIRA_PROJECTS is not set, the Jira connection of JIRA_TOKEN is off");
+            return None;
+        }
+        Some(JiraConfig { projects, ..config })
+    }
+
+    /// Whether estimates may be written to the issue, by the project key before its `-`.
+    pub fn allows(&self, issue_key: &str) -> bool {
+        let project = issue_key.split_once('-').map(|(project, _)| project);
+        self.projects.is_empty()
+            || project.is_some_and(|project| {
+                self.projects
+                    .iter()
+                    .any(|allowed| allowed.eq_ignore_ascii_case(project))
+            })
+    }
+}
+
+impl fmt::Debug for JiraConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JiraConfig")
+            .field("base_url", &self.base_url)
+            .field("email", &self.email)
+            .field("token", &"***")
+            .field("story_points_field", &self.story_points_field)
+            .field("projects", &self.projects)
+            .finish()
+    }
+}
+
+pub struct JiraClient {
+    config: JiraConfig,
+    http: reqwest::Client,
+    retry: Retry,
+}
+
+impl JiraClient {
+    pub fn new(config: JiraConfig) -> JiraClient {
+        JiraClient {
+            config,
+            http: connector::http_client(),
+            retry: Retry::default(),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn with_retry(mut self, retry: Retry) -> JiraClient {
+        self.retry = retry;
+        self
+    }
+
+    /// Sets the story points field of the issue.
+    pub async fn update_story_points(&self, issue_key: &str, points: f64) -> Result<(), ScError> {
+        let url = self.issue_url(issue_key)?;
+        let body = json!({ "fields": { self.config.story_points_field.as_ref(): points } });
+        self.retry
+            .send(|| self.authorize(self.http.put(url.clone())).json(&body))
+            .await?;
+        Ok(())
+    }
+
+    /// URL of the issue, with the key percent encoded as a single path segment.
+    fn issue_url(&self, issue_key: &str) -> Result<Url, ScError> {
+        let invalid = || ScError::InvalidUrl(self.config.base_url.to_string());
+        let mut url = Url::parse(&self.config.base_url).map_err(|_| invalid())?;
+        url.path_segments_mut()
+            .map_err(|_| invalid())?
+            .pop_if_empty()
+            .extend(["rest", "api", "2", "issue", issue_key]);
+        Ok(url)
+    }
+
+    fn authorize(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.config.email {
+            Some(email) => request.basic_auth(email, Some(&self.config.token)),
+            None => request.bearer_auth(&self.config.token),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        extract::{Path, State},
+        http::{HeaderMap, StatusCode},
+        routing::put,
+        Json, Router,
+    };
+    use serde_json::Value;
+    use std::{sync::Mutex, time::Duration};
+
+    #[derive(Debug, Clone)]
+    struct Received {
+        issue_key: String,
+        authorization: String,
+        body: Value,
+    }
+
+    #[derive(Clone, Default)]
+    struct MockJira {
+        /// Statuses to answer with, in order. Answers 204 once they ran out.
+        statuses: Arc<Mutex<Vec<StatusCode>>>,
+        received: Arc<Mutex<Vec<Received>>>,
+    }
+
+    async fn update_issue(
+        State(mock): State<MockJira>,
+        Path(issue_key): Path<String>,
+        headers: HeaderMap,
+        Json(body): Json<Value>,
+    ) -> StatusCode {
+        mock.received.lock().unwrap().push(Received {
+            issue_key,
+            authorization: headers
+                .get("authorization")
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or_default()
+                .to_string(),
+            body,
+        });
+        let mut statuses = mock.statuses.lock().unwrap();
+        if statuses.is_empty() {
+            StatusCode::NO_CONTENT
+        } else {
+            statuses.remove(0)
+        }
+    }
+
+    async fn spawn_mock(statuses: Vec<StatusCode>) -> (JiraClient, MockJira, JiraConfig) {
+        let mock = MockJira {
+            statuses: Arc::new(Mutex::new(statuses)),
+            ..MockJira::default()
+        };
+        let app = Router::new()
+            .route("/rest/api/2/issue/:issue_key", put(update_issue))
+            .with_state(mock.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let config = JiraConfig {
+            base_url: Arc::from(format!("http://{address}")),
+            ..JiraConfig::new(
+                "https://jira.example.com",
+                "po@example.com",
+                "secret",
+                "customfield_10002",
+            )
+            .unwrap()
+        };
+        let client = JiraClient::new(config.clone()).with_retry(Retry {
+            attempts: 3,
+            backoff: Duration::from_millis(1),
+        });
+        (client, mock, config)
+    }
+
+    #[tokio::test]
+    async fn writes_story_points() {
+        let (client, mock, _) = spawn_mock(vec![]).await;
+        client.update_story_points("SHOP-12", 5.0).await.unwrap();
+
+        let received = mock.received.lock().unwrap().clone();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].issue_key, "SHOP-12");
+        // base64 of "po@example.com:secret"
+        assert_eq!(
+            received[0].authorization,
+            "Basic cG9AZXhhbXBsZS5jb206c2VjcmV0"
+        );
+        assert_eq!(
+            received[0].body,
+            json!({ "fields": { "customfield_10002": 5.0 } })
+        );
+    }
+
+    #[tokio::test]
+    async fn keeps_the_issue_key_in_one_path_segment() {
+        let (client, mock, _) = spawn_mock(vec![]).await;
+        client
+            .update_story_points("SHOP/12?x=1", 5.0)
+            .await
+            .unwrap();
+        assert_eq!(mock.received.lock().unwrap()[0].issue_key, "SHOP/12?x=1");
+    }
+
+    #[tokio::test]
+    async fn uses_bearer_token_without_email() {
+        let (_, mock, config) = spawn_mock(vec![]).await;
+        let config = JiraConfig {
+            email: None,
+            ..config
+        };
+        JiraClient::new(config)
+            .update_story_points("SHOP-12", 3.0)
+            .await
+            .unwrap();
+        assert_eq!(
+            mock.received.lock().unwrap()[0].authorization,
+            "Bearer secret"
+        );
+    }
+
+    #[tokio::test]
+    async fn retries_server_errors() {
+        let (client, mock, _) = spawn_mock(vec![
+            StatusCode::SERVICE_UNAVAILABLE,
+            StatusCode::TOO_MANY_REQUESTS,
+        ])
+        .await;
+        client.update_story_points("SHOP-12", 8.0).await.unwrap();
+        assert_eq!(mock.received.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_last_attempt() {
+        let (client, mock, _) = spawn_mock(vec![StatusCode::BAD_GATEWAY; 5]).await;
+        let result = client.update_story_points("SHOP-12", 8.0).await;
+        assert!(matches!(
+            result,
+            Err(ScError::HttpStatus(StatusCode::BAD_GATEWAY, _))
+        ));
+        assert_eq!(mock.received.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_client_errors() {
+        let (client, mock, _) = spawn_mock(vec![StatusCode::BAD_REQUEST]).await;
+        let result = client.update_story_points("SHOP-12", 8.0).await;
+        assert!(matches!(
+            result,
+            Err(ScError::HttpStatus(StatusCode::BAD_REQUEST, _))
+        ));
+        assert_eq!(mock.received.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn writes_only_to_allowed_projects() {
+        let config = JiraConfig::new("https://jira.example.com", "", "token", "").unwrap();
+        assert!(config.allows("ANY-1"));
+
+        let config = JiraConfig {
+            projects: vec![Arc::from("SHOP")],
+            ..config
+        };
+        assert!(config.allows("SHOP-12"));
+        assert!(config.allows("shop-12"));
+        assert!(!config.allows("SHOPPING-12"));
+        assert!(!config.allows("HR-3"));
+        assert!(!config.allows("SHOP"));
+    }
+
+    #[test]
+    fn rejects_incomplete_config() {
+        assert_eq!(JiraConfig::new("jira.example.com", "", "token", ""), None);
+        assert_eq!(
+            JiraConfig::new("https://jira.internal", "", "token", ""),
+            None
+        );
+        assert_eq!(
+            JiraConfig::new("https://jira.example.com", "", " ", ""),
+            None
+        );
+        let config = JiraConfig::new("https://jira.example.com/", "", "token", "").unwrap();
+        assert_eq!(&*config.base_url, "https://jira.example.com");
+        assert_eq!(config.email, None);
+        assert_eq!(&*config.story_points_field, DEFAULT_STORY_POINTS_FIELD);
+    }
+}
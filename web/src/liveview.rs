@@ -0,0 +1,95 @@
+use crate::{app::App, metrics, shutdown, state::AppState, AppProps};
+use axum::extract::ws::{Message, WebSocket};
+use dioxus_liveview::LiveViewError;
+use futures::{channel::mpsc, Sink, SinkExt, Stream, StreamExt};
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+const BUFFER_SIZE: usize = 64;
+
+/// End of the websocket the liveview talks to. The websocket itself stays with [`serve`], so it
+/// can still be closed with a restart frame when the server shuts down.
+struct ViewSocket {
+    incoming: mpsc::UnboundedReceiver<Result<Vec<u8>, LiveViewError>>,
+    outgoing: mpsc::Sender<Vec<u8>>,
+}
+
+impl Stream for ViewSocket {
+    type Item = Result<Vec<u8>, LiveViewError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.incoming.poll_next_unpin(cx)
+    }
+}
+
+impl Sink<Vec<u8>> for ViewSocket {
+    type Error = LiveViewError;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.outgoing
+            .poll_ready_unpin(cx)
+            .map_err(|_| LiveViewError::SendingFailed)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: Vec<u8>) -> Result<(), Self::Error> {
+        self.outgoing
+            .start_send_unpin(item)
+            .map_err(|_| LiveViewError::SendingFailed)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.outgoing
+            .poll_flush_unpin(cx)
+            .map_err(|_| LiveViewError::SendingFailed)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.outgoing
+            .poll_close_unpin(cx)
+            .map_err(|_| LiveViewError::SendingFailed)
+    }
+}
+
+/// Runs the app over the websocket until either side is done or the server shuts down.
+pub async fn serve(mut socket: WebSocket, state: AppState, app_props: AppProps) {
+    let _socket = metrics::WebSocketGuard::open("liveview");
+    let closing = state.shutdown.closing();
+    tokio::pin!(closing);
+
+    // Unbounded, the messages of the client must not wait for the app while it is not polled.
+    let (incoming_tx, incoming) = mpsc::unbounded();
+    let (outgoing, mut outgoing_rx) = mpsc::channel(BUFFER_SIZE);
+    let view =
+        state
+            .view
+            .launch_with_props::<AppProps>(ViewSocket { incoming, outgoing }, App, app_props);
+    tokio::pin!(view);
+
+    loop {
+        tokio::select! {
+            _ = &mut view => break,
+            _ = &mut closing => {
+                _ = socket.send(Message::Close(Some(shutdown::restart_frame()))).await;
+                break;
+            }
+            Some(data) = outgoing_rx.next() => {
+                if socket.send(Message::Binary(data)).await.is_err() {
+                    break;
+                }
+            }
+            message = socket.recv() => {
+                let data = match message {
+                    Some(Ok(Message::Text(text))) => text.into_bytes(),
+                    Some(Ok(Message::Binary(data))) => data,
+                    Some(Ok(Message::Ping(_) | Message::Pong(_))) => continue,
+                    Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+                };
+                if incoming_tx.unbounded_send(Ok(data)).is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
@@ -1,38 +1,64 @@
 #![allow(non_snake_case)]
 
-use crate::{app::App, state::AppState, validate::ALPHABET_AND_NUMBERS};
+use crate::{state::AppState, validate::ALPHABET_AND_NUMBERS};
 use axum::{
-    extract::{
-        ws::{WebSocket, WebSocketUpgrade},
-        Path, State,
-    },
+    extract::{ws::WebSocketUpgrade, Path, State},
+    http::{header, StatusCode},
     response::{Html, IntoResponse, Redirect, Response},
     routing::{get, get_service},
-    Router,
+    Json, Router,
 };
 use axum_session::{SessionConfig, SessionLayer, SessionStore};
 use axum_session_surreal::{SessionSurrealPool, SessionSurrealSession};
 use channel::RoomChannel;
+use export::ExportFormat;
 use room::RoomId;
+use std::sync::Arc;
 use surrealdb::engine::any::Any;
 use tower_http::services::ServeDir;
 use uuid::Uuid;
 
 mod actions;
+mod admin;
+mod api;
 mod app;
 mod channel;
+mod chat;
+mod cluster;
+mod connector;
 mod database;
 mod deck;
 mod error;
 mod estimate;
+mod events;
+mod export;
+mod github;
+mod health;
+mod history;
+mod import;
+mod integrations;
+mod jira;
+mod liveview;
 mod logs;
+mod metrics;
 mod name;
+mod past_rounds;
 mod room;
 mod room_pool;
+mod round;
+mod settings;
+mod shutdown;
+mod snapshot;
+mod socket;
 mod state;
+mod statistics;
+mod stories;
+mod story;
 mod table;
+mod timer;
 mod username;
 mod validate;
+mod webhook;
 
 #[derive(Clone)]
 pub struct AppProps {
@@ -40,6 +66,7 @@ pub struct AppProps {
     pub session_id: Uuid,
     pub room_id: RoomId,
     pub channel: RoomChannel,
+    pub pool: Arc<database::Pool>,
 }
 
 const FAVICON_ICO_PATH: &str = "/assets/favicon.ico";
@@ -49,6 +76,7 @@ const SP_JS_PATH: &str = "/assets/sp.js";
 async fn main() {
     dotenvy::dotenv().expect(".env file not found");
     logs::init_tracing();
+    metrics::init();
     let app_state = AppState::new();
     let addr = app_state.addr;
     // Axum session
@@ -60,11 +88,28 @@ async fn main() {
         .await
         .unwrap();
 
+    if let Err(err) = history::init(&app_state.pool).await {
+        tracing::error!("Failed to initialize round history tables, error: {}", err);
+    }
+    if let Err(err) = cluster::init(&app_state.pool).await {
+        tracing::error!("Failed to initialize cluster tables, error: {}", err);
+    }
+
+    let graceful_shutdown =
+        shutdown::graceful(app_state.room_pool.clone(), app_state.shutdown.clone());
+
     let routes = Router::new()
         .nest_service("/assets", get_service(ServeDir::new("../../assets")))
         .route("/", get(root))
+        .route("/metrics", get(metrics_handler))
+        .route("/healthz", get(healthz_handler))
+        .route("/readyz", get(readyz_handler))
         .route("/:room_id", get(room_handler))
+        .route("/:room_id/history", get(history_handler))
+        .route("/:room_id/export/:format", get(export_handler))
         .route("/ws/:room_id", get(ws_handler))
+        .nest("/api/v1", api::router())
+        .nest("/admin", admin::router())
         .with_state(app_state)
         .layer(SessionLayer::new(session_store));
 
@@ -73,8 +118,10 @@ async fn main() {
     let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
 
     axum::serve(listener, routes.into_make_service())
+        .with_graceful_shutdown(graceful_shutdown)
         .await
         .unwrap();
+    tracing::info!("Shut down");
 }
 
 async fn root() -> Redirect {
@@ -83,6 +130,29 @@ async fn root() -> Redirect {
     Redirect::to(format!("/{room_id}").as_str())
 }
 
+async fn metrics_handler() -> Response {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics::render(),
+    )
+        .into_response()
+}
+
+async fn healthz_handler() -> &'static str {
+    "ok"
+}
+
+async fn readyz_handler(State(state): State<AppState>) -> Response {
+    let report = health::readiness(&state.pool, &state.room_pool, &state.shutdown).await;
+    let status = match report.status {
+        health::Readiness::Ready => StatusCode::OK,
+        health::Readiness::Degraded
+        | health::Readiness::Unavailable
+        | health::Readiness::Draining => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    (status, Json(report)).into_response()
+}
+
 async fn room_handler(State(state): State<AppState>, Path(room_id): Path<RoomId>) -> Response {
     let validated_room_id = validate::room_id(room_id.clone());
 
@@ -117,6 +187,58 @@ async fn room_handler(State(state): State<AppState>, Path(room_id): Path<RoomId>
     .into_response()
 }
 
+async fn history_handler(State(state): State<AppState>, Path(room_id): Path<RoomId>) -> Response {
+    match history::load_rounds(&state.pool, &validate::room_id(room_id.clone())).await {
+        Ok(rounds) => Json(rounds).into_response(),
+        Err(err) => {
+            tracing::error!(
+                "Failed to load history of room_id: {}, error: {}",
+                room_id,
+                err
+            );
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+async fn export_handler(
+    State(state): State<AppState>,
+    Path((room_id, format)): Path<(RoomId, String)>,
+) -> Response {
+    let Some(format) = ExportFormat::from_extension(&format) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let room_id = validate::room_id(room_id);
+    let export = history::load_rounds(&state.pool, &room_id)
+        .await
+        .and_then(|rounds| format.render(&rounds));
+    match export {
+        Ok(body) => (
+            [
+                (header::CONTENT_TYPE, format.content_type().to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!(
+                        "attachment; filename=\"{}-rounds.{}\"",
+                        room_id,
+                        format.extension()
+                    ),
+                ),
+            ],
+            body,
+        )
+            .into_response(),
+        Err(err) => {
+            tracing::error!(
+                "Failed to export rounds of room_id: {}, error: {}",
+                room_id,
+                err
+            );
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
 async fn ws_handler(
     Path(room_id): Path<RoomId>,
     ws: WebSocketUpgrade,
@@ -134,14 +256,8 @@ async fn ws_handler(
         session_id,
         room_id,
         channel,
+        pool: state.pool.clone(),
     };
 
-    ws.on_upgrade(move |socket| websocket(socket, state, app_props))
-}
-
-async fn websocket(stream: WebSocket, state: AppState, app_props: AppProps) {
-    _ = state
-        .view
-        .launch_with_props::<AppProps>(dioxus_liveview::axum_socket(stream), App, app_props)
-        .await;
+    ws.on_upgrade(move |socket| liveview::serve(socket, state, app_props))
 }
@@ -0,0 +1,196 @@
+use prometheus::{
+    core::Collector, Encoder, Histogram, HistogramOpts, IntCounter, IntCounterVec, IntGaugeVec,
+    Opts, Registry, TextEncoder,
+};
+use std::sync::LazyLock;
+
+static REGISTRY: LazyLock<Registry> = LazyLock::new(Registry::new);
+
+fn register<M: Collector + Clone + 'static>(metric: M) -> M {
+    REGISTRY
+        .register(Box::new(metric.clone()))
+        .expect("metric names are unique");
+    metric
+}
+
+/// Rooms in the room pool, by `role`: `owner` rooms run here, `proxy` rooms stand in for rooms
+/// another instance of the cluster owns.
+pub static ROOMS: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register(
+        IntGaugeVec::new(
+            Opts::new("scrum_poker_rooms", "Rooms in the room pool"),
+            &["role"],
+        )
+        .unwrap(),
+    )
+});
+
+pub static PARTICIPANTS: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register(
+        IntGaugeVec::new(
+            Opts::new("scrum_poker_participants", "Participants of all rooms"),
+            &["status"],
+        )
+        .unwrap(),
+    )
+});
+
+pub static WEBSOCKETS: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register(
+        IntGaugeVec::new(
+            Opts::new("scrum_poker_websockets", "Open websockets"),
+            &["kind"],
+        )
+        .unwrap(),
+    )
+});
+
+pub static ROOM_REQUESTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(
+        IntCounterVec::new(
+            Opts::new(
+                "scrum_poker_room_requests_total",
+                "Requests processed by rooms",
+            ),
+            &["request"],
+        )
+        .unwrap(),
+    )
+});
+
+pub static ROOM_SPAWNS: LazyLock<IntCounter> = LazyLock::new(|| {
+    register(IntCounter::new("scrum_poker_room_spawns_total", "Rooms spawned").unwrap())
+});
+
+pub static ROOM_SHUTDOWNS: LazyLock<IntCounter> = LazyLock::new(|| {
+    register(IntCounter::new("scrum_poker_room_shutdowns_total", "Rooms shut down").unwrap())
+});
+
+pub static BROADCAST_LAGS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(
+        IntCounterVec::new(
+            Opts::new(
+                "scrum_poker_broadcast_lag_total",
+                "Times a subscriber fell behind the broadcasts of a room",
+            ),
+            &["subscriber"],
+        )
+        .unwrap(),
+    )
+});
+
+pub static DATABASE_POOL_ERRORS: LazyLock<IntCounter> = LazyLock::new(|| {
+    register(
+        IntCounter::new(
+            "scrum_poker_database_pool_errors_total",
+            "Failures to get a SurrealDB connection from the pool",
+        )
+        .unwrap(),
+    )
+});
+
+pub static ROOM_REQUEST_DURATION: LazyLock<Histogram> = LazyLock::new(|| {
+    register(
+        Histogram::with_opts(
+            HistogramOpts::new(
+                "scrum_poker_room_request_duration_seconds",
+                "Time from sending a request to a room until its response",
+            )
+            .buckets(vec![
+                0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0,
+            ]),
+        )
+        .unwrap(),
+    )
+});
+
+/// Registers every metric, so they are scraped before their first use.
+pub fn init() {
+    LazyLock::force(&ROOMS);
+    LazyLock::force(&PARTICIPANTS);
+    LazyLock::force(&WEBSOCKETS);
+    LazyLock::force(&ROOM_REQUESTS);
+    LazyLock::force(&ROOM_SPAWNS);
+    LazyLock::force(&ROOM_SHUTDOWNS);
+    LazyLock::force(&BROADCAST_LAGS);
+    LazyLock::force(&DATABASE_POOL_ERRORS);
+    LazyLock::force(&ROOM_REQUEST_DURATION);
+}
+
+/// All metrics in the Prometheus text format.
+pub fn render() -> String {
+    let mut buffer = Vec::new();
+    if let Err(err) = TextEncoder::new().encode(&REGISTRY.gather(), &mut buffer) {
+        tracing::error!("Failed to encode metrics, error: {}", err);
+    }
+    String::from_utf8(buffer).unwrap_or_default()
+}
+
+/// Participants of one room by status. The room reports its counts after every request and
+/// takes them back out of the gauges when it is dropped.
+#[derive(Debug, Default)]
+pub struct ParticipantCounts {
+    online: i64,
+    left: i64,
+}
+
+impl ParticipantCounts {
+    pub fn update(&mut self, online: i64, left: i64) {
+        PARTICIPANTS
+            .with_label_values(&["online"])
+            .add(online - self.online);
+        PARTICIPANTS
+            .with_label_values(&["left"])
+            .add(left - self.left);
+        self.online = online;
+        self.left = left;
+    }
+}
+
+impl Drop for ParticipantCounts {
+    fn drop(&mut self) {
+        self.update(0, 0);
+    }
+}
+
+/// Counts an open websocket of the given kind until dropped.
+pub struct WebSocketGuard(&'static str);
+
+impl WebSocketGuard {
+    pub fn open(kind: &'static str) -> WebSocketGuard {
+        WEBSOCKETS.with_label_values(&[kind]).inc();
+        WebSocketGuard(kind)
+    }
+}
+
+impl Drop for WebSocketGuard {
+    fn drop(&mut self) {
+        WEBSOCKETS.with_label_values(&[self.0]).dec();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn participant_counts_are_taken_back_on_drop() {
+        let online = || PARTICIPANTS.with_label_values(&["online"]).get();
+        let before = online();
+        let mut counts = ParticipantCounts::default();
+        counts.update(3, 1);
+        counts.update(2, 2);
+        assert_eq!(online(), before + 2);
+        drop(counts);
+        assert_eq!(online(), before);
+    }
+
+    #[test]
+    fn renders_text_format() {
+        init();
+        ROOM_SPAWNS.inc();
+        let text = render();
+        assert!(text.contains("# TYPE scrum_poker_room_spawns_total counter"));
+        assert!(text.contains("# TYPE scrum_poker_room_request_duration_seconds histogram"));
+    }
+}
@@ -0,0 +1,110 @@
+use crate::{app::use_app_props, export::ExportFormat, history, round::RoundRecord};
+use dioxus::prelude::*;
+use itertools::Itertools;
+
+#[component]
+pub fn PastRounds(rounds: Signal<Vec<RoundRecord>>) -> Element {
+    let app_props = use_app_props();
+    let mut show_history = use_signal(|| false);
+
+    let history = use_resource(move || async move {
+        // Reload whenever the room records or updates a round.
+        _ = rounds.read().len();
+        if !show_history() {
+            return Vec::new();
+        }
+        match history::load_rounds(&app_props().pool, &app_props().room_id).await {
+            Ok(rounds) => rounds,
+            Err(err) => {
+                tracing::error!(
+                    "Failed to load history of room_id {}, error: {}",
+                    app_props().room_id,
+                    err
+                );
+                Vec::new()
+            }
+        }
+    });
+
+    rsx! {
+        div { class: "flex flex-col gap-y-4 text-slate-600",
+            div { class: "flex items-center justify-between",
+                h1 { class: "text-lg font-semibold", "History" }
+                div { class: "flex items-center gap-x-2",
+                    span { class: "text-sm text-gray-500", "Export" }
+                    for format in ExportFormat::ALL {
+                        a {
+                            class: "rounded-full px-4 py-2 text-sm font-bold text-slate-600 bg-white border border-slate-300 hover:bg-slate-100",
+                            href: "/{app_props().room_id}/export/{format.extension()}",
+                            download: true,
+                            "{format.name()}"
+                        }
+                    }
+                }
+                button {
+                    class: "rounded-full px-6 py-2 font-bold text-slate-600 bg-white border border-slate-300 hover:bg-slate-100",
+                    onclick: move |_| show_history.toggle(),
+                    if show_history() {
+                        "Hide"
+                    } else {
+                        "Show"
+                    }
+                }
+            }
+            if show_history() {
+                {match &*history.read() {
+                    Some(rounds) if !rounds.is_empty() => rsx! {
+                        table { class: "w-full text-sm text-left bg-white rounded-lg shadow-md",
+                            thead { class: "text-gray-700 uppercase bg-gray-100",
+                                tr {
+                                    th { scope: "col", class: "py-2 px-4", "Revealed" }
+                                    th { scope: "col", class: "py-2 px-4", "Story" }
+                                    th { scope: "col", class: "py-2 px-4", "Votes" }
+                                    th { scope: "col", class: "py-2 px-4 text-center", "Final" }
+                                }
+                            }
+                            tbody {
+                                for round in rounds.iter().rev() {
+                                    HistoryRow { key: "{round.round_id}", round: round.clone() }
+                                }
+                            }
+                        }
+                    },
+                    Some(_) => rsx! {
+                        p { class: "text-sm text-gray-500", "No rounds recorded yet." }
+                    },
+                    None => rsx! {
+                        p { class: "text-sm text-gray-500", "Loading..." }
+                    },
+                }}
+            }
+        }
+    }
+}
+
+#[component]
+fn HistoryRow(round: RoundRecord) -> Element {
+    let revealed_at = round.revealed_at.format("%Y-%m-%d %H:%M").to_string();
+    let votes = round
+        .votes
+        .iter()
+        .map(|vote| format!("{}: {}", vote.name, vote.estimate))
+        .join(", ");
+
+    rsx! {
+        tr { class: "border-b",
+            td { class: "py-2 px-4 whitespace-nowrap", "{revealed_at}" }
+            td { class: "py-2 px-4",
+                if let Some(story) = &round.story {
+                    "{story.title}"
+                }
+            }
+            td { class: "py-2 px-4", "{votes}" }
+            td { class: "py-2 px-4 text-center font-semibold",
+                if let Some(estimate) = &round.final_estimate {
+                    "{estimate}"
+                }
+            }
+        }
+    }
+}
@@ -1,30 +1,68 @@
 use crate::{
     channel::{
-        EstimateVisibility, RoomBroadcastMessage, RoomChannel, RoomMessage, RoomRequest,
-        RoomResponse,
+        EstimateVisibility, Rejection, RoomBroadcastMessage, RoomChannel, RoomMessage, RoomRequest,
+        RoomResponse, RoomState,
     },
-    estimate::Estimate,
-    room_pool::{CtrlRequest, CtrlResponse, HealthStatus, RoomPoolChannel},
+    chat,
+    connector::Tracker,
+    database,
+    estimate::{CardDeck, Estimate},
+    github::{GitHubClient, GitHubConfig},
+    history,
+    jira::JiraConfig,
+    metrics::{self, ParticipantCounts},
+    room_pool::{CtrlRequest, CtrlResponse, HealthStatus, RoomPoolChannel, RoomSummary},
+    round::{RoundRecord, SyncStatus},
+    snapshot::{self, RoomSnapshot},
+    statistics::RoundStatistics,
+    story::{Story, StoryQueue},
+    webhook::{self, WebhookConfig},
 };
+use chrono::{DateTime, Utc};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, sync::Arc};
-use tokio::sync::{mpsc, oneshot, Mutex};
+use tokio::{
+    sync::{mpsc, oneshot, watch, Mutex},
+    task::JoinHandle,
+    time::Instant,
+};
 use tokio_stream::{wrappers::IntervalStream, StreamExt};
 use uuid::Uuid;
 
 pub type RoomId = Arc<str>;
 
-#[derive(PartialEq, Debug, Clone)]
+pub const MAX_TIMER_SECONDS: u64 = 60 * 60;
+/// Time participants of the REST API stay in the room without sending a request.
+pub const API_IDLE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
 pub enum ParticipantStatus {
     Online,
     Left,
 }
 
-#[derive(Debug, Clone)]
+/// The facilitator controls the round: revealing, hiding and deleting estimates.
+/// There is at most one facilitator per room and the role can be handed over.
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub enum ParticipantRole {
+    Facilitator,
+    Voter,
+}
+
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
 pub struct Participant {
     pub session_id: Uuid,
     pub name: Arc<str>,
     pub estimate: Estimate,
     pub status: ParticipantStatus,
+    pub role: ParticipantRole,
+    /// Observers follow the round without voting and are not counted as missing estimates.
+    pub observer: bool,
+    /// Last request of a participant that joined through the REST API. Such participants send no
+    /// heartbeats, they leave after `API_IDLE_TIMEOUT` without a request.
+    #[serde(default)]
+    pub last_seen: Option<DateTime<Utc>>,
 }
 
 impl Participant {
@@ -34,8 +72,44 @@ impl Participant {
             name,
             estimate: Estimate::None,
             status: ParticipantStatus::Online,
+            role: ParticipantRole::Voter,
+            observer: false,
+            last_seen: None,
         }
     }
+
+    pub fn is_facilitator(&self) -> bool {
+        self.role == ParticipantRole::Facilitator
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RoomSettings {
+    /// Reveal estimates as soon as every online voter has picked a card.
+    pub auto_reveal: bool,
+    /// Seconds participants get to change their card before the automatic reveal.
+    pub auto_reveal_countdown: u64,
+    /// Reveal estimates when the round timer runs out.
+    pub reveal_on_timer_expiry: bool,
+}
+
+/// Countdown started by the facilitator. The deadline lives in the room so it survives reconnects.
+#[derive(Debug)]
+pub struct RoundTimer {
+    pub deadline: Instant,
+    pub handle: JoinHandle<()>,
+}
+
+impl RoundTimer {
+    pub fn remaining(&self) -> u64 {
+        seconds_until(self.deadline)
+    }
+}
+
+/// Whole seconds left until the deadline, rounded up.
+fn seconds_until(deadline: Instant) -> u64 {
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0)
 }
 
 #[derive(Debug)]
@@ -44,18 +118,76 @@ pub struct Room {
     pub channel: RoomChannel,
     pub visibility: Mutex<EstimateVisibility>,
     pub participants: Mutex<HashMap<Uuid, Participant>>,
+    pub deck: Mutex<CardDeck>,
+    pub settings: Mutex<RoomSettings>,
+    pub auto_reveal_countdown: Mutex<Option<JoinHandle<()>>>,
+    pub timer: Mutex<Option<RoundTimer>>,
+    pub stories: Mutex<StoryQueue>,
+    pub rounds: Mutex<Vec<RoundRecord>>,
+    /// Index in `rounds` of the record for the round in progress, once it has one.
+    pub round_record: Mutex<Option<usize>>,
+    pub history: mpsc::UnboundedSender<RoundRecord>,
+    pub snapshot: watch::Sender<Option<RoomSnapshot>>,
+    pub jira: Mutex<Option<JiraConfig>>,
+    pub github: Mutex<Option<GitHubConfig>>,
+    pub webhook: watch::Sender<Option<WebhookConfig>>,
+    /// Incoming webhook of the chat channel revealed rounds are posted to.
+    pub chat: Mutex<Option<Arc<str>>>,
+    pub participant_counts: Mutex<ParticipantCounts>,
+    pub pool: Arc<database::Pool>,
+    pub created_at: DateTime<Utc>,
+    pub last_activity: Mutex<DateTime<Utc>>,
 }
 
 impl Room {
-    pub fn new(room_id: RoomId, channel: RoomChannel) -> Self {
+    pub fn new(room_id: RoomId, channel: RoomChannel, pool: Arc<database::Pool>) -> Self {
+        let (webhook, _) = watch::channel(None);
         Room {
+            snapshot: snapshot::spawn_writer(pool.clone(), room_id.clone()),
             room_id,
             channel,
             visibility: Mutex::new(EstimateVisibility::Hidden),
             participants: Mutex::new(HashMap::new()),
+            deck: Mutex::new(CardDeck::default()),
+            settings: Mutex::new(RoomSettings::default()),
+            auto_reveal_countdown: Mutex::new(None),
+            timer: Mutex::new(None),
+            stories: Mutex::new(StoryQueue::default()),
+            rounds: Mutex::new(Vec::new()),
+            round_record: Mutex::new(None),
+            history: history::spawn_writer(pool.clone()),
+            jira: Mutex::new(JiraConfig::from_env()),
+            github: Mutex::new(None),
+            webhook,
+            chat: Mutex::new(None),
+            participant_counts: Mutex::new(ParticipantCounts::default()),
+            pool,
+            created_at: Utc::now(),
+            last_activity: Mutex::new(Utc::now()),
         }
     }
 
+    /// Rehydrates the room from the snapshot saved before a restart. Participants count as left
+    /// until they reconnect, like after any other dropped connection. Participants of the REST
+    /// API have no connection to lose, they stay until they are idle for too long.
+    pub async fn restore(&self, snapshot: RoomSnapshot) {
+        tracing::info!("Restoring room {} from snapshot", self.room_id);
+        let mut participants = self.participants.lock().await;
+        for mut participant in snapshot.participants {
+            if participant.last_seen.is_none() {
+                participant.status = ParticipantStatus::Left;
+                self.spawn_cleanup_participant(participant.session_id);
+            }
+            participants.insert(participant.session_id, participant);
+        }
+        *self.visibility.lock().await = snapshot.visibility;
+        *self.deck.lock().await = snapshot.deck;
+        *self.settings.lock().await = snapshot.settings;
+        *self.stories.lock().await = snapshot.stories;
+        *self.rounds.lock().await = snapshot.rounds;
+        *self.round_record.lock().await = snapshot.round_record;
+    }
+
     pub async fn run(
         &self,
         mut room_rx: mpsc::Receiver<RoomMessage>,
@@ -63,6 +195,12 @@ impl Room {
         room_pool_channel: RoomPoolChannel,
     ) {
         tracing::info!("Room {} ready.", self.room_id);
+        webhook::spawn_worker(
+            self.room_id.clone(),
+            self.channel.subscribe(),
+            self.webhook.subscribe(),
+            self.participants.lock().await.values().cloned().collect(),
+        );
         let mut interval_stream = self.create_shutdown_interval_stream().await;
         loop {
             tokio::select! {
@@ -70,16 +208,55 @@ impl Room {
                     self.update_room(request, response).await;
                 },
                 Some(_tx) = interval_stream.next() => {
+                    self.expire_idle_participants().await;
                     if self.is_room_empty().await {
                         tracing::trace!("Room is empty. Shutting down room_id: {}", self.room_id);
                         _ = room_pool_channel.shutdown(&self.room_id).await;
+                        self.snapshot.send_replace(None);
                         break;
                     }
                 },
                 Some(ctl) = ctrl.recv() => {
                     match ctl {
                         (CtrlRequest::HealthCheck, rtx) => {
-                            _ = rtx.send(CtrlResponse::Health(HealthStatus::Healthy)).expect("Unable to send health status");
+                            _ = rtx.send(CtrlResponse::Health(self.health()));
+                        },
+                        (CtrlRequest::Summary, rtx) => {
+                            _ = rtx.send(CtrlResponse::Summary(self.summary().await));
+                        },
+                        (CtrlRequest::Participants, rtx) => {
+                            let participants =
+                                self.participants.lock().await.values().cloned().collect();
+                            _ = rtx.send(CtrlResponse::Participants(participants));
+                        },
+                        (CtrlRequest::Flush, rtx) => {
+                            self.flush().await;
+                            _ = rtx.send(CtrlResponse::Done);
+                        },
+                        (CtrlRequest::Notice(notice), rtx) => {
+                            _ = self.channel.broadcast.send(RoomBroadcastMessage::Notice(notice));
+                            _ = rtx.send(CtrlResponse::Done);
+                        },
+                        (CtrlRequest::Close, rtx) => {
+                            tracing::info!(
+                                "Closing room {} on request of an operator",
+                                self.room_id
+                            );
+                            self.close().await;
+                            _ = room_pool_channel.shutdown(&self.room_id).await;
+                            self.snapshot.send_replace(None);
+                            _ = rtx.send(CtrlResponse::Done);
+                            break;
+                        },
+                        (CtrlRequest::Handover, rtx) => {
+                            tracing::info!(
+                                "Room {} is handed over to another instance",
+                                self.room_id
+                            );
+                            self.close().await;
+                            _ = room_pool_channel.shutdown(&self.room_id).await;
+                            _ = rtx.send(CtrlResponse::Done);
+                            break;
                         },
                     }
                 }
@@ -96,9 +273,60 @@ impl Room {
     }
 
     async fn update_room(&self, request: RoomRequest, response: oneshot::Sender<RoomResponse>) {
+        metrics::ROOM_REQUESTS
+            .with_label_values(&[request.name()])
+            .inc();
+        if request.is_activity() {
+            *self.last_activity.lock().await = Utc::now();
+        }
+        if let Some(session_id) = request.session_id() {
+            self.touch_participant(session_id).await;
+        }
+        if let Some(session_id) = request.facilitator_session_id() {
+            if !self.is_facilitator(session_id).await {
+                tracing::warn!(
+                    "Denied {} from non facilitator session_id: {} in room {}",
+                    request.name(),
+                    session_id,
+                    self.room_id
+                );
+                _ = response.send(RoomResponse::PermissionDenied);
+                return;
+            }
+        }
+
+        let refresh_statistics = matches!(
+            request,
+            RoomRequest::Join(_)
+                | RoomRequest::Leave(_)
+                | RoomRequest::Remove(_)
+                | RoomRequest::Heartbeat(_)
+                | RoomRequest::SendEstimate(_, _)
+                | RoomRequest::ChangeVisibility(_)
+                | RoomRequest::SetVisibility(_, _)
+                | RoomRequest::AutoReveal
+                | RoomRequest::TimerExpired
+                | RoomRequest::ChangeObserver(_, _)
+        );
+        let start_auto_reveal = matches!(
+            request,
+            RoomRequest::Join(_)
+                | RoomRequest::Leave(_)
+                | RoomRequest::Remove(_)
+                | RoomRequest::Heartbeat(_)
+                | RoomRequest::SendEstimate(_, _)
+                | RoomRequest::ChangeObserver(_, _)
+                | RoomRequest::ChangeSettings(_, _)
+        );
+
+        let mut reply = RoomResponse::Accepted;
         match request {
             RoomRequest::Join(p) => {
-                self.join_participant(p, response).await;
+                self.join_participant(p).await;
+                reply = RoomResponse::RoomState(Box::new(self.room_state().await));
+            }
+            RoomRequest::State => {
+                reply = RoomResponse::RoomState(Box::new(self.room_state().await));
             }
             RoomRequest::Leave(session_id) => {
                 self.leave_participant(session_id).await;
@@ -112,49 +340,685 @@ impl Room {
                     session_id,
                     estimate_point
                 );
-                let mut participants = self.participants.lock().await;
-
-                if let Some(participant) = participants.get_mut(&session_id) {
-                    participant.estimate = estimate_point;
+                if !self.deck.lock().await.contains(&estimate_point) {
+                    tracing::warn!(
+                        "Estimate {} is not in the deck of room {}",
+                        estimate_point,
+                        self.room_id
+                    );
+                    reply = RoomResponse::Rejected(Rejection::NotInDeck(estimate_point));
+                } else {
+                    let mut participants = self.participants.lock().await;
+                    match participants.get_mut(&session_id) {
+                        Some(participant) if participant.observer => {
+                            tracing::warn!(
+                                "Ignored estimate from observer session_id {} in room {}",
+                                session_id,
+                                self.room_id
+                            );
+                            reply = RoomResponse::Rejected(Rejection::Observer);
+                        }
+                        Some(participant) => {
+                            participant.estimate = estimate_point;
+                            _ = self
+                                .channel
+                                .broadcast
+                                .send(RoomBroadcastMessage::ParticipantUpdate(participant.clone()));
+                        }
+                        None => {
+                            tracing::error!(
+                                "Update estimate: Participant with session_id {} not found in room {}",
+                                session_id,
+                                self.room_id
+                            );
+                            reply = RoomResponse::Rejected(Rejection::NotInRoom);
+                        }
+                    }
+                }
+            }
+            RoomRequest::ChangeVisibility(_) => {
+                let visibility = self.visibility.lock().await.toggle();
+                self.change_visibility(visibility).await;
+            }
+            RoomRequest::SetVisibility(_, visibility) => {
+                self.change_visibility(visibility).await;
+            }
+            RoomRequest::AutoReveal => {
+                if self.auto_reveal_countdown.lock().await.take().is_some() {
+                    tracing::trace!("Auto reveal estimates in room {}", self.room_id);
+                    self.change_visibility(EstimateVisibility::Visible).await;
+                }
+            }
+            RoomRequest::DeleteEstimates(_) => {
+                self.reset_round().await;
+            }
+            RoomRequest::Heartbeat(session_id) => {
+                self.heartbeat_participant(session_id).await;
+            }
+            RoomRequest::NameChange(session_id, new_username) => {
+                self.change_participant_name(session_id, new_username).await;
+            }
+            RoomRequest::ChangeObserver(session_id, observer) => {
+                self.change_participant_observer(session_id, observer).await;
+            }
+            RoomRequest::TransferFacilitator(session_id, new_facilitator_id) => {
+                self.transfer_facilitator(session_id, new_facilitator_id)
+                    .await;
+            }
+            RoomRequest::ChangeDeck(_, new_deck) => {
+                self.change_deck(new_deck).await;
+            }
+            RoomRequest::StartTimer(_, seconds) => {
+                self.start_timer(seconds).await;
+            }
+            RoomRequest::StopTimer(_) => {
+                if let Some(timer) = self.timer.lock().await.take() {
+                    timer.handle.abort();
                     _ = self
                         .channel
                         .broadcast
-                        .send(RoomBroadcastMessage::ParticipantUpdate(participant.clone()));
+                        .send(RoomBroadcastMessage::TimerStopped);
+                }
+            }
+            RoomRequest::TimerExpired => {
+                self.expire_timer().await;
+            }
+            RoomRequest::AddStory(_, story) => {
+                self.update_stories(|stories| stories.add(story)).await;
+            }
+            RoomRequest::ImportStories(_, imported) => {
+                self.update_stories(|stories| {
+                    for story in imported {
+                        stories.add(story);
+                    }
+                })
+                .await;
+            }
+            RoomRequest::RemoveStory(_, story_id) => {
+                self.update_stories(|stories| stories.remove(&story_id))
+                    .await;
+            }
+            RoomRequest::MoveStory(_, story_id, index) => {
+                self.update_stories(|stories| stories.move_to(&story_id, index))
+                    .await;
+            }
+            RoomRequest::SelectStory(_, story_id) => {
+                self.update_stories(|stories| stories.select(&story_id))
+                    .await;
+            }
+            RoomRequest::NextStory(_) => {
+                self.next_story().await;
+            }
+            RoomRequest::FinalizeEstimate(_, estimate) => {
+                if self.visibility.lock().await.is_visible() {
+                    self.record_round(Some(estimate)).await;
+                    if let Some(round_id) = self.current_round_id().await {
+                        self.sync_round(&round_id).await;
+                    }
                 } else {
-                    tracing::error!(
-                        "Update estimate: Participant with session_id {} not found in room {}",
-                        session_id,
+                    tracing::warn!(
+                        "Tried to finalize hidden estimates in room {}",
                         self.room_id
                     );
                 }
             }
-            RoomRequest::ChangeVisibility => {
-                let mut visibility = self.visibility.lock().await;
-                *visibility = visibility.toggle();
+            RoomRequest::AttachIssueKey(_, issue_key) => {
+                self.attach_issue_key(issue_key).await;
+            }
+            RoomRequest::ChangeJiraConfig(_, config) => {
+                let base_url = config.as_ref().map(|config| config.base_url.clone());
+                *self.jira.lock().await = config;
+                _ = self
+                    .channel
+                    .broadcast
+                    .send(RoomBroadcastMessage::JiraChanged(base_url));
+            }
+            RoomRequest::ChangeGitHubConfig(_, config) => {
+                let repository = config.as_ref().map(|config| config.repository());
+                *self.github.lock().await = config.map(|config| *config);
+                _ = self
+                    .channel
+                    .broadcast
+                    .send(RoomBroadcastMessage::GitHubChanged(repository));
+            }
+            RoomRequest::ChangeWebhook(_, config) => {
+                let url = config.as_ref().map(|config| config.url.clone());
+                self.webhook.send_replace(config.map(|config| *config));
                 _ = self
                     .channel
                     .broadcast
-                    .send(RoomBroadcastMessage::ChangedVisibility(visibility.clone()));
+                    .send(RoomBroadcastMessage::WebhookChanged(url));
+            }
+            RoomRequest::ChangeChatWebhook(_, url) => {
+                let enabled = url.is_some();
+                *self.chat.lock().await = url;
+                _ = self
+                    .channel
+                    .broadcast
+                    .send(RoomBroadcastMessage::ChatChanged(enabled));
+            }
+            RoomRequest::PullIssues(_) => match self.github.lock().await.clone() {
+                Some(config) => self.spawn_pull_issues(config),
+                None => tracing::warn!("No GitHub repository connected to room {}", self.room_id),
+            },
+            RoomRequest::IssuesPulled(issues) => {
+                self.add_pulled_issues(issues).await;
+            }
+            RoomRequest::SyncEstimate(_, round_id) => {
+                self.sync_round(&round_id).await;
+            }
+            RoomRequest::SyncFinished(round_id, status) => {
+                self.update_round(&round_id, |record| record.sync = Some(status))
+                    .await;
+            }
+            RoomRequest::ChangeSettings(_, new_settings) => {
+                let mut settings = self.settings.lock().await;
+                *settings = new_settings;
+                _ = self
+                    .channel
+                    .broadcast
+                    .send(RoomBroadcastMessage::SettingsChanged(settings.clone()));
+            }
+        }
+
+        if refresh_statistics {
+            self.refresh_round().await;
+        }
+        self.update_auto_reveal(start_auto_reveal).await;
+        self.save_snapshot().await;
+        self.report_participants().await;
+        _ = response.send(reply);
+    }
+
+    async fn report_participants(&self) {
+        let participants = self.participants.lock().await;
+        let online = participants
+            .values()
+            .filter(|p| p.status == ParticipantStatus::Online)
+            .count();
+        let left = participants.len() - online;
+        self.participant_counts
+            .lock()
+            .await
+            .update(online as i64, left as i64);
+    }
+
+    /// Hands the room state to the snapshot writer when it changed since the last request.
+    async fn snapshot(&self) -> RoomSnapshot {
+        RoomSnapshot {
+            room_id: self.room_id.clone(),
+            participants: self.participants.lock().await.values().cloned().collect(),
+            visibility: self.visibility.lock().await.clone(),
+            deck: self.deck.lock().await.clone(),
+            settings: self.settings.lock().await.clone(),
+            stories: self.stories.lock().await.clone(),
+            rounds: self.rounds.lock().await.clone(),
+            round_record: *self.round_record.lock().await,
+        }
+    }
+
+    async fn save_snapshot(&self) {
+        let snapshot = self.snapshot().await;
+        self.snapshot.send_if_modified(|current| {
+            if current.as_ref() == Some(&snapshot) {
+                return false;
+            }
+            *current = Some(snapshot);
+            true
+        });
+    }
+
+    async fn reset_round(&self) {
+        self.close_round().await;
+        self.delete_estimates().await;
+        let mut visibility = self.visibility.lock().await;
+        *visibility = EstimateVisibility::Hidden;
+
+        _ = self
+            .channel
+            .broadcast
+            .send(RoomBroadcastMessage::EstimatesDeleted);
+    }
+
+    async fn update_stories(&self, update: impl FnOnce(&mut StoryQueue)) {
+        let mut stories = self.stories.lock().await;
+        update(&mut stories);
+        _ = self
+            .channel
+            .broadcast
+            .send(RoomBroadcastMessage::StoriesChanged(stories.clone()));
+    }
+
+    /// Records the result of the revealed round, or brings its existing record up to date.
+    async fn record_round(&self, final_estimate: Option<Estimate>) {
+        let story = self.stories.lock().await.current().cloned();
+        let participants = self.participants.lock().await;
+        let deck = self.deck.lock().await;
+        let mut rounds = self.rounds.lock().await;
+        let mut round_record = self.round_record.lock().await;
+        let record = match *round_record {
+            Some(index) => {
+                let record = &mut rounds[index];
+                record.update(&participants, &deck);
+                record
+            }
+            None => {
+                let record = RoundRecord::new(self.room_id.clone(), story, &participants, &deck);
+                if record.story.is_none() && record.votes.is_empty() && final_estimate.is_none() {
+                    return;
+                }
+                rounds.push(record);
+                *round_record = Some(rounds.len() - 1);
+                rounds.last_mut().unwrap()
+            }
+        };
+        if final_estimate.is_some() {
+            record.final_estimate = final_estimate;
+        }
+        _ = self.history.send(record.clone());
+        _ = self
+            .channel
+            .broadcast
+            .send(RoomBroadcastMessage::RoundsChanged(rounds.clone()));
+    }
+
+    async fn current_round_id(&self) -> Option<Arc<str>> {
+        let index = (*self.round_record.lock().await)?;
+        Some(self.rounds.lock().await[index].round_id.clone())
+    }
+
+    /// Applies `update` to a recorded round and publishes the change.
+    async fn update_round(&self, round_id: &str, update: impl FnOnce(&mut RoundRecord)) {
+        let mut rounds = self.rounds.lock().await;
+        let Some(record) = rounds.iter_mut().find(|r| &*r.round_id == round_id) else {
+            tracing::warn!("Round {} not found in room {}", round_id, self.room_id);
+            return;
+        };
+        update(record);
+        _ = self.history.send(record.clone());
+        _ = self
+            .channel
+            .broadcast
+            .send(RoomBroadcastMessage::RoundsChanged(rounds.clone()));
+    }
+
+    /// Links the revealed round to the issue its final estimate is written to.
+    async fn attach_issue_key(&self, issue_key: Arc<str>) {
+        if !self.visibility.lock().await.is_visible() {
+            tracing::warn!(
+                "Tried to attach an issue to a hidden round in room {}",
+                self.room_id
+            );
+            return;
+        }
+        self.record_round(None).await;
+        match self.current_round_id().await {
+            Some(round_id) => {
+                self.update_round(&round_id, |record| {
+                    record.issue_key = Some(issue_key);
+                    record.sync = None;
+                })
+                .await;
+            }
+            None => tracing::warn!("No round to attach an issue to in room {}", self.room_id),
+        }
+    }
+
+    /// Issue tracker the issue belongs to: `#42` is a GitHub issue, anything else a Jira key.
+    async fn tracker(&self, issue_key: &str) -> Option<Tracker> {
+        if issue_key.starts_with('#') {
+            self.github.lock().await.clone().map(Tracker::GitHub)
+        } else {
+            self.jira
+                .lock()
+                .await
+                .clone()
+                .filter(|config| config.allows(issue_key))
+                .map(Tracker::Jira)
+        }
+    }
+
+    /// Writes the final estimate of the round to its issue in the background.
+    async fn sync_round(&self, round_id: &str) {
+        let rounds = self.rounds.lock().await;
+        let Some(record) = rounds.iter().find(|r| &*r.round_id == round_id) else {
+            return;
+        };
+        let (Some(issue_key), Some(final_estimate)) =
+            (record.issue_key.clone(), record.final_estimate.clone())
+        else {
+            return;
+        };
+        drop(rounds);
+        let Some(tracker) = self.tracker(&issue_key).await else {
+            return;
+        };
+        self.spawn_sync(tracker, Arc::from(round_id), issue_key, final_estimate);
+        self.update_round(round_id, |record| record.sync = Some(SyncStatus::Pending))
+            .await;
+    }
+
+    fn spawn_sync(
+        &self,
+        tracker: Tracker,
+        round_id: Arc<str>,
+        issue_key: Arc<str>,
+        final_estimate: Estimate,
+    ) {
+        let channel = self.channel.clone();
+        tokio::task::spawn(async move {
+            let status = match tracker.write_estimate(&issue_key, &final_estimate).await {
+                Ok(()) => SyncStatus::Synced,
+                Err(err) => {
+                    tracing::warn!(
+                        "Failed to write estimate to issue {}, error: {}",
+                        issue_key,
+                        err
+                    );
+                    SyncStatus::Failed(Arc::from(err.to_string()))
+                }
+            };
+            _ = channel
+                .send(RoomRequest::SyncFinished(round_id, status))
+                .await;
+        });
+    }
+
+    /// Adds the open issues of the connected repository that are not queued yet.
+    fn spawn_pull_issues(&self, config: GitHubConfig) {
+        let channel = self.channel.clone();
+        tokio::task::spawn(async move {
+            let issues = GitHubClient::new(config)
+                .open_issues()
+                .await
+                .map_err(|err| {
+                    tracing::warn!("Failed to pull GitHub issues, error: {}", err);
+                    Arc::from(err.to_string())
+                });
+            _ = channel.send(RoomRequest::IssuesPulled(issues)).await;
+        });
+    }
+
+    async fn add_pulled_issues(&self, issues: Result<Vec<Story>, Arc<str>>) {
+        let result = match issues {
+            Ok(issues) => {
+                let mut added = 0;
+                self.update_stories(|stories| {
+                    for issue in issues {
+                        if !stories.stories.iter().any(|story| story.key == issue.key) {
+                            stories.add(issue);
+                            added += 1;
+                        }
+                    }
+                })
+                .await;
+                Ok(added)
+            }
+            Err(err) => Err(err),
+        };
+        _ = self
+            .channel
+            .broadcast
+            .send(RoomBroadcastMessage::IssuesPulled(result));
+    }
+
+    /// Stamps the reset time on the record of the round that is being reset.
+    async fn close_round(&self) {
+        let Some(index) = self.round_record.lock().await.take() else {
+            return;
+        };
+        let mut rounds = self.rounds.lock().await;
+        rounds[index].reset_at = Some(Utc::now());
+        _ = self.history.send(rounds[index].clone());
+        _ = self
+            .channel
+            .broadcast
+            .send(RoomBroadcastMessage::RoundsChanged(rounds.clone()));
+    }
+
+    /// Starts a fresh round for the next story.
+    async fn next_story(&self) {
+        self.update_stories(|stories| {
+            stories.advance();
+        })
+        .await;
+        self.reset_round().await;
+    }
+
+    async fn start_timer(&self, seconds: u64) {
+        if seconds == 0 || seconds > MAX_TIMER_SECONDS {
+            tracing::warn!("Invalid timer of {}s in room {}", seconds, self.room_id);
+            return;
+        }
+        let mut timer = self.timer.lock().await;
+        if let Some(running_timer) = timer.take() {
+            running_timer.handle.abort();
+        }
+        let deadline = Instant::now() + tokio::time::Duration::from_secs(seconds);
+        *timer = Some(RoundTimer {
+            deadline,
+            handle: self.spawn_timer_ticks(deadline),
+        });
+        _ = self
+            .channel
+            .broadcast
+            .send(RoomBroadcastMessage::TimerStarted(seconds));
+    }
+
+    fn spawn_timer_ticks(&self, deadline: Instant) -> JoinHandle<()> {
+        let channel = self.channel.clone();
+        tokio::task::spawn(async move {
+            let mut tick = Instant::now() + tokio::time::Duration::from_secs(1);
+            while tick < deadline {
+                tokio::time::sleep_until(tick).await;
+                _ = channel
+                    .broadcast
+                    .send(RoomBroadcastMessage::TimerTick(seconds_until(deadline)));
+                tick += tokio::time::Duration::from_secs(1);
             }
-            RoomRequest::DeleteEstimates => {
-                self.delete_estimates().await;
-                let mut visibility = self.visibility.lock().await;
-                *visibility = EstimateVisibility::Hidden;
+            tokio::time::sleep_until(deadline).await;
+            _ = channel.send(RoomRequest::TimerExpired).await;
+        })
+    }
+
+    async fn expire_timer(&self) {
+        if self.timer.lock().await.take().is_none() {
+            return;
+        }
+        _ = self
+            .channel
+            .broadcast
+            .send(RoomBroadcastMessage::TimerExpired);
+        let reveal = self.settings.lock().await.reveal_on_timer_expiry;
+        if reveal && !self.visibility.lock().await.is_visible() {
+            tracing::trace!("Timer expired, reveal estimates in room {}", self.room_id);
+            self.change_visibility(EstimateVisibility::Visible).await;
+        }
+    }
 
+    async fn change_visibility(&self, new_visibility: EstimateVisibility) {
+        let mut visibility = self.visibility.lock().await;
+        if new_visibility.is_visible() && !visibility.is_visible() {
+            self.notify_chat().await;
+        }
+        *visibility = new_visibility;
+        _ = self
+            .channel
+            .broadcast
+            .send(RoomBroadcastMessage::ChangedVisibility(visibility.clone()));
+    }
+
+    /// Posts a summary of the revealed round to the chat channel of the room in the background.
+    async fn notify_chat(&self) {
+        let Some(webhook_url) = self.chat.lock().await.clone() else {
+            return;
+        };
+        let story = self.stories.lock().await.current().cloned();
+        let text = chat::summary(
+            &self.room_id,
+            story.as_ref(),
+            &*self.participants.lock().await,
+            &*self.deck.lock().await,
+        );
+        let room_id = self.room_id.clone();
+        tokio::task::spawn(async move {
+            if let Err(err) = chat::post(&webhook_url, &text).await {
+                tracing::warn!(
+                    "Failed to post summary of room {} to chat, error: {}",
+                    room_id,
+                    err
+                );
+            }
+        });
+    }
+
+    /// Every online voter has picked a card while estimates are still hidden.
+    async fn is_ready_to_reveal(&self) -> bool {
+        if self.visibility.lock().await.is_visible() {
+            return false;
+        }
+        let map = self.participants.lock().await;
+        let mut voters = map
+            .values()
+            .filter(|p| !p.observer && p.status == ParticipantStatus::Online)
+            .peekable();
+        voters.peek().is_some() && voters.all(|p| p.estimate != Estimate::None)
+    }
+
+    /// Starts the auto reveal countdown once everyone voted and cancels it when that no longer holds.
+    async fn update_auto_reveal(&self, may_start: bool) {
+        let settings = self.settings.lock().await.clone();
+        let ready = settings.auto_reveal && self.is_ready_to_reveal().await;
+        let mut countdown = self.auto_reveal_countdown.lock().await;
+        match countdown.as_ref() {
+            None if ready && may_start => {
+                if settings.auto_reveal_countdown == 0 {
+                    drop(countdown);
+                    self.change_visibility(EstimateVisibility::Visible).await;
+                    self.refresh_round().await;
+                } else {
+                    *countdown =
+                        Some(self.spawn_auto_reveal_countdown(settings.auto_reveal_countdown));
+                }
+            }
+            Some(handle) if !ready => {
+                handle.abort();
+                *countdown = None;
                 _ = self
                     .channel
                     .broadcast
-                    .send(RoomBroadcastMessage::EstimatesDeleted);
+                    .send(RoomBroadcastMessage::AutoRevealCancelled);
             }
-            RoomRequest::Heartbeat(session_id) => {
-                self.heartbeat_participant(session_id).await;
+            _ => {}
+        }
+    }
+
+    fn spawn_auto_reveal_countdown(&self, seconds: u64) -> JoinHandle<()> {
+        let channel = self.channel.clone();
+        tokio::task::spawn(async move {
+            for remaining in (1..=seconds).rev() {
+                _ = channel
+                    .broadcast
+                    .send(RoomBroadcastMessage::AutoRevealCountdown(remaining));
+                tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
             }
-            RoomRequest::NameChange(session_id, new_username) => {
-                self.change_participant_name(session_id, new_username).await;
+            _ = channel.send(RoomRequest::AutoReveal).await;
+        })
+    }
+
+    /// Statistics of the current round, only available once estimates are visible.
+    async fn statistics(&self) -> Option<RoundStatistics> {
+        if !self.visibility.lock().await.is_visible() {
+            return None;
+        }
+        let participants = self.participants.lock().await;
+        let deck = self.deck.lock().await;
+        Some(RoundStatistics::new(&participants, &deck))
+    }
+
+    /// Publishes statistics and records the round while estimates are visible.
+    async fn refresh_round(&self) {
+        if let Some(statistics) = self.statistics().await {
+            _ = self
+                .channel
+                .broadcast
+                .send(RoomBroadcastMessage::StatisticsUpdate(statistics));
+            self.record_round(None).await;
+        }
+    }
+
+    async fn change_deck(&self, new_deck: CardDeck) {
+        if new_deck.cards().is_empty() {
+            tracing::warn!("Tried to change to an empty deck in room {}", self.room_id);
+            return;
+        }
+        self.close_round().await;
+        let mut deck = self.deck.lock().await;
+        *deck = new_deck;
+        // Estimates from the previous deck are meaningless with the new cards.
+        self.delete_estimates().await;
+        *self.visibility.lock().await = EstimateVisibility::Hidden;
+        _ = self
+            .channel
+            .broadcast
+            .send(RoomBroadcastMessage::DeckChanged(deck.clone()));
+    }
+
+    async fn is_facilitator(&self, session_id: Uuid) -> bool {
+        let map = self.participants.lock().await;
+        map.get(&session_id)
+            .is_some_and(|participant| participant.is_facilitator())
+    }
+
+    async fn transfer_facilitator(&self, session_id: Uuid, new_facilitator_id: Uuid) {
+        let mut map = self.participants.lock().await;
+        if session_id == new_facilitator_id || !map.contains_key(&new_facilitator_id) {
+            tracing::warn!(
+                "Tried to transfer facilitator role to unknown session_id {} in room {}",
+                new_facilitator_id,
+                self.room_id
+            );
+            return;
+        }
+        for (id, role) in [
+            (session_id, ParticipantRole::Voter),
+            (new_facilitator_id, ParticipantRole::Facilitator),
+        ] {
+            if let Some(participant) = map.get_mut(&id) {
+                participant.role = role;
+                _ = self
+                    .channel
+                    .broadcast
+                    .send(RoomBroadcastMessage::ParticipantUpdate(participant.clone()));
             }
         }
     }
 
+    /// Hands the facilitator role to the next participant, online ones first, once the room lost
+    /// its facilitator. A facilitator that left keeps the role through the cleanup grace period,
+    /// so reloading the page does not give it away.
+    fn promote_facilitator(&self, map: &mut HashMap<Uuid, Participant>) {
+        if map.values().any(|p| p.is_facilitator()) {
+            return;
+        }
+        let next_facilitator = map
+            .values_mut()
+            .sorted_by_key(|p| (p.status != ParticipantStatus::Online, p.name.clone()))
+            .next();
+        if let Some(participant) = next_facilitator {
+            participant.role = ParticipantRole::Facilitator;
+            tracing::trace!(
+                "Promoted session_id {} to facilitator in room {}",
+                participant.session_id,
+                self.room_id
+            );
+            _ = self
+                .channel
+                .broadcast
+                .send(RoomBroadcastMessage::ParticipantUpdate(participant.clone()));
+        }
+    }
+
     async fn change_participant_name(&self, session_id: Uuid, new_username: Arc<str>) {
         let mut map = self.participants.lock().await;
         match map.get_mut(&session_id) {
@@ -171,14 +1035,50 @@ impl Room {
         }
     }
 
-    async fn join_participant(&self, p: Participant, response: oneshot::Sender<RoomResponse>) {
+    async fn change_participant_observer(&self, session_id: Uuid, observer: bool) {
+        let mut map = self.participants.lock().await;
+        match map.get_mut(&session_id) {
+            Some(participant) => {
+                participant.observer = observer;
+                if observer {
+                    participant.estimate = Estimate::None;
+                }
+                _ = self
+                    .channel
+                    .broadcast
+                    .send(RoomBroadcastMessage::ParticipantUpdate(participant.clone()));
+            }
+            None => {
+                tracing::warn!("Tried to change participant observer mode but not found in room");
+            }
+        }
+    }
+
+    async fn join_participant(&self, p: Participant) {
         let mut map = self.participants.lock().await;
         match map.get_mut(&p.session_id) {
             Some(existing_participant) => {
                 existing_participant.status = ParticipantStatus::Online;
                 existing_participant.name = p.name;
+                existing_participant.observer = p.observer;
+                existing_participant.last_seen = p.last_seen;
+                if p.observer {
+                    existing_participant.estimate = Estimate::None;
+                }
+                _ = self
+                    .channel
+                    .broadcast
+                    .send(RoomBroadcastMessage::ParticipantUpdate(
+                        existing_participant.clone(),
+                    ));
             }
             None => {
+                // The room creator arriving from the root redirect is the first to join
+                // and becomes facilitator, as does anyone joining a room left without one.
+                let mut p = p;
+                if !map.values().any(|p| p.is_facilitator()) {
+                    p.role = ParticipantRole::Facilitator;
+                }
                 map.insert(p.session_id, p.to_owned());
                 let new_participant = map.get(&p.session_id).unwrap().to_owned();
                 _ = self
@@ -187,10 +1087,45 @@ impl Room {
                     .send(RoomBroadcastMessage::Joined(new_participant));
             }
         };
-        let room_visibility = self.visibility.lock().await.clone();
-        response
-            .send(RoomResponse::RoomState(map.clone(), room_visibility))
-            .unwrap();
+    }
+
+    async fn room_state(&self) -> RoomState {
+        let participants = self.participants.lock().await.clone();
+        let visibility = self.visibility.lock().await.clone();
+        let deck = self.deck.lock().await.clone();
+        let settings = self.settings.lock().await.clone();
+        let timer = self.timer.lock().await.as_ref().map(|t| t.remaining());
+        let statistics = visibility
+            .is_visible()
+            .then(|| RoundStatistics::new(&participants, &deck));
+        RoomState {
+            participants,
+            visibility,
+            deck,
+            statistics,
+            settings,
+            timer,
+            stories: self.stories.lock().await.clone(),
+            rounds: self.rounds.lock().await.clone(),
+            jira: self
+                .jira
+                .lock()
+                .await
+                .as_ref()
+                .map(|config| config.base_url.clone()),
+            github: self
+                .github
+                .lock()
+                .await
+                .as_ref()
+                .map(|config| config.repository()),
+            chat: self.chat.lock().await.is_some(),
+            webhook: self
+                .webhook
+                .borrow()
+                .as_ref()
+                .map(|config| config.url.clone()),
+        }
     }
 
     async fn leave_participant(&self, session_id: Uuid) {
@@ -228,6 +1163,7 @@ impl Room {
                         .channel
                         .broadcast
                         .send(RoomBroadcastMessage::Left(session_id));
+                    self.promote_facilitator(&mut map);
                 }
             }
             None => {
@@ -249,6 +1185,50 @@ impl Room {
         }
     }
 
+    /// Keeps a participant of the REST API in the room, any of its requests counts.
+    async fn touch_participant(&self, session_id: Uuid) {
+        let mut map = self.participants.lock().await;
+        let Some(participant) = map.get_mut(&session_id) else {
+            return;
+        };
+        if participant.last_seen.is_none() {
+            return;
+        }
+        participant.last_seen = Some(Utc::now());
+        if participant.status == ParticipantStatus::Left {
+            participant.status = ParticipantStatus::Online;
+        }
+    }
+
+    /// Participants of the REST API that went quiet leave, like a closed browser tab.
+    async fn expire_idle_participants(&self) {
+        let mut map = self.participants.lock().await;
+        let now = Utc::now();
+        let idle: Vec<Uuid> = map
+            .values()
+            .filter(|p| p.status == ParticipantStatus::Online)
+            .filter(|p| {
+                p.last_seen.is_some_and(|last_seen| {
+                    (now - last_seen)
+                        .to_std()
+                        .is_ok_and(|idle| idle > API_IDLE_TIMEOUT)
+                })
+            })
+            .map(|p| p.session_id)
+            .collect();
+        for session_id in idle {
+            tracing::trace!(
+                "API participant {} in room {} is idle",
+                session_id,
+                self.room_id
+            );
+            if let Some(participant) = map.get_mut(&session_id) {
+                participant.status = ParticipantStatus::Left;
+            }
+            self.spawn_cleanup_participant(session_id);
+        }
+    }
+
     async fn delete_estimates(&self) {
         let mut map = self.participants.lock().await;
         for (_, p) in map.iter_mut() {
@@ -256,6 +1236,55 @@ impl Room {
         }
     }
 
+    fn health(&self) -> HealthStatus {
+        if self.snapshot.is_closed() || self.history.is_closed() {
+            HealthStatus::Degraded
+        } else {
+            HealthStatus::Healthy
+        }
+    }
+
+    async fn summary(&self) -> RoomSummary {
+        let participants = self.participants.lock().await;
+        let online = participants
+            .values()
+            .filter(|p| p.status == ParticipantStatus::Online)
+            .count();
+        RoomSummary {
+            room_id: self.room_id.clone(),
+            online,
+            left: participants.len() - online,
+            visibility: self.visibility.lock().await.clone(),
+            created_at: self.created_at,
+            last_activity: *self.last_activity.lock().await,
+        }
+    }
+
+    /// Saves the snapshot itself rather than waiting for the background writer.
+    async fn flush(&self) {
+        let snapshot = self.snapshot().await;
+        if let Err(err) = snapshot::save_snapshot(&self.pool, &snapshot).await {
+            tracing::error!(
+                "Failed to flush snapshot of room {}, error: {}",
+                self.room_id,
+                err
+            );
+        }
+        // Saved again by the writer, so a save it has in flight cannot leave an older snapshot.
+        self.snapshot.send_replace(Some(snapshot));
+    }
+
+    /// Stops the background tasks of the room and tells its clients it is gone.
+    async fn close(&self) {
+        if let Some(timer) = self.timer.lock().await.take() {
+            timer.handle.abort();
+        }
+        if let Some(handle) = self.auto_reveal_countdown.lock().await.take() {
+            handle.abort();
+        }
+        _ = self.channel.broadcast.send(RoomBroadcastMessage::Closed);
+    }
+
     async fn is_room_empty(&self) -> bool {
         let participants = self.participants.lock().await;
         if participants.len() == 0 {
@@ -265,3 +1294,140 @@ impl Room {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn room() -> Room {
+        let (tx, _rx) = mpsc::channel(8);
+        let (broadcast, _) = tokio::sync::broadcast::channel(64);
+        let pool = database::Pool::builder(database::Manager {})
+            .build()
+            .unwrap();
+        Room::new(
+            Arc::from("room"),
+            RoomChannel { tx, broadcast },
+            Arc::new(pool),
+        )
+    }
+
+    async fn role(room: &Room, session_id: Uuid) -> ParticipantRole {
+        room.participants.lock().await[&session_id].role.clone()
+    }
+
+    async fn status(room: &Room, session_id: Uuid) -> ParticipantStatus {
+        room.participants.lock().await[&session_id].status.clone()
+    }
+
+    #[tokio::test]
+    async fn facilitator_that_does_not_come_back_hands_the_role_over() {
+        let room = room();
+        let (alice, bob) = (Uuid::new_v4(), Uuid::new_v4());
+        room.join_participant(Participant::new(alice, Arc::from("alice")))
+            .await;
+        room.join_participant(Participant::new(bob, Arc::from("bob")))
+            .await;
+        assert_eq!(role(&room, alice).await, ParticipantRole::Facilitator);
+
+        room.leave_participant(alice).await;
+        assert_eq!(role(&room, alice).await, ParticipantRole::Facilitator);
+        assert_eq!(role(&room, bob).await, ParticipantRole::Voter);
+
+        room.remove_participant(alice).await;
+        assert_eq!(role(&room, bob).await, ParticipantRole::Facilitator);
+    }
+
+    #[tokio::test]
+    async fn facilitator_reloading_keeps_the_role() {
+        let room = room();
+        let (alice, bob) = (Uuid::new_v4(), Uuid::new_v4());
+        room.join_participant(Participant::new(alice, Arc::from("alice")))
+            .await;
+        room.join_participant(Participant::new(bob, Arc::from("bob")))
+            .await;
+
+        room.leave_participant(alice).await;
+        room.join_participant(Participant::new(alice, Arc::from("alice")))
+            .await;
+        room.remove_participant(alice).await;
+        assert_eq!(status(&room, alice).await, ParticipantStatus::Online);
+        assert_eq!(role(&room, alice).await, ParticipantRole::Facilitator);
+        assert_eq!(role(&room, bob).await, ParticipantRole::Voter);
+    }
+
+    #[tokio::test]
+    async fn estimates_the_room_cannot_take_are_rejected() {
+        let room = room();
+        let (voter, observer) = (Uuid::new_v4(), Uuid::new_v4());
+        room.join_participant(Participant::new(voter, Arc::from("voter")))
+            .await;
+        let mut participant = Participant::new(observer, Arc::from("observer"));
+        participant.observer = true;
+        room.join_participant(participant).await;
+
+        let cases = [
+            (voter, "7", Some(Rejection::NotInDeck(Estimate::from("7")))),
+            (observer, "5", Some(Rejection::Observer)),
+            (Uuid::new_v4(), "5", Some(Rejection::NotInRoom)),
+            (voter, "5", None),
+        ];
+        for (session_id, card, rejection) in cases {
+            let (tx, rx) = oneshot::channel();
+            let request = RoomRequest::SendEstimate(session_id, Estimate::from(card));
+            room.update_room(request, tx).await;
+            match (rx.await.unwrap(), rejection) {
+                (RoomResponse::Rejected(rejected), Some(rejection)) => {
+                    assert_eq!(rejected, rejection)
+                }
+                (RoomResponse::Accepted, None) => {}
+                (response, _) => panic!("unexpected {response:?} for {card}"),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn idle_api_participants_leave() {
+        let room = room();
+        let (bot, active) = (Uuid::new_v4(), Uuid::new_v4());
+        for session_id in [bot, active] {
+            let mut participant = Participant::new(session_id, Arc::from("bot"));
+            participant.last_seen = Some(Utc::now() - chrono::Duration::minutes(10));
+            room.join_participant(participant).await;
+        }
+        room.touch_participant(active).await;
+
+        room.expire_idle_participants().await;
+        assert_eq!(status(&room, bot).await, ParticipantStatus::Left);
+        assert_eq!(status(&room, active).await, ParticipantStatus::Online);
+
+        room.touch_participant(bot).await;
+        assert_eq!(status(&room, bot).await, ParticipantStatus::Online);
+    }
+
+    #[tokio::test]
+    async fn online_participants_take_over_first() {
+        let room = room();
+        let (alice, bob, carol) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        for (session_id, name) in [(alice, "alice"), (bob, "bob"), (carol, "carol")] {
+            room.join_participant(Participant::new(session_id, Arc::from(name)))
+                .await;
+        }
+        room.leave_participant(bob).await;
+        room.leave_participant(alice).await;
+        room.remove_participant(alice).await;
+        assert_eq!(role(&room, bob).await, ParticipantRole::Voter);
+        assert_eq!(role(&room, carol).await, ParticipantRole::Facilitator);
+    }
+
+    #[tokio::test]
+    async fn rooms_without_a_history_writer_are_degraded() {
+        let mut room = room();
+        assert_eq!(room.health(), HealthStatus::Healthy);
+
+        let (history, history_rx) = mpsc::unbounded_channel();
+        drop(history_rx);
+        room.history = history;
+        assert_eq!(room.health(), HealthStatus::Degraded);
+    }
+}
@@ -1,42 +1,104 @@
 use std::{collections::HashMap, sync::Arc};
 
+use chrono::{DateTime, Utc};
+use serde::Serialize;
 use tokio::sync::{broadcast, mpsc, oneshot, RwLock};
 
 const BUFFER_SIZE: usize = 256;
 
 use crate::{
-    channel::{RoomBroadcastMessage, RoomChannel, RoomMessage, RoomRequest, RoomResponse},
+    channel::{
+        EstimateVisibility, RoomBroadcastMessage, RoomChannel, RoomMessage, RoomRequest,
+        RoomResponse,
+    },
+    cluster::{Cluster, InstanceId},
+    database,
     error::ScError,
-    room::{Room, RoomId},
+    metrics,
+    room::{Participant, Room, RoomId},
+    snapshot, validate,
 };
 
+/// Requests of the operators to a room, answered by the room actor next to the requests of its
+/// participants.
 #[derive(Debug)]
 pub enum CtrlRequest {
     HealthCheck,
+    Summary,
+    Participants,
+    /// Saves the state of the room right away, before the server shuts down.
+    Flush,
+    /// Closes the room and deletes its snapshot, e.g. when it was abandoned.
+    Close,
+    /// Closes the room for another instance to take over, its snapshot stays for the new owner.
+    Handover,
+    /// Shows a notice, e.g. about maintenance, to everyone in the room.
+    Notice(Arc<str>),
 }
 
 #[derive(Debug)]
 pub enum CtrlResponse {
     Health(HealthStatus),
+    Summary(RoomSummary),
+    Participants(Vec<Participant>),
+    Done,
 }
 
-#[derive(Debug)]
+/// Overview of a room for the admin dashboard.
+#[derive(Debug, Clone, Serialize)]
+pub struct RoomSummary {
+    pub room_id: RoomId,
+    pub online: usize,
+    pub left: usize,
+    pub visibility: EstimateVisibility,
+    /// When the room was spawned by this process.
+    pub created_at: DateTime<Utc>,
+    /// Last request of a participant, heartbeats aside.
+    pub last_activity: DateTime<Utc>,
+}
+
+#[derive(PartialEq, Debug)]
 pub enum HealthStatus {
     Healthy,
+    /// The room answers, but a task saving its snapshots or history has stopped.
+    Degraded,
 }
 
 pub type CtrlMessage = (CtrlRequest, oneshot::Sender<CtrlResponse>);
 
+/// Sends a request to the control channel of a room, `None` once the room is gone.
+pub async fn control(
+    ctrl: &mpsc::Sender<CtrlMessage>,
+    request: CtrlRequest,
+) -> Option<CtrlResponse> {
+    let (tx, rx) = oneshot::channel::<CtrlResponse>();
+    ctrl.send((request, tx)).await.ok()?;
+    rx.await.ok()
+}
+
 #[derive(Debug)]
 pub enum RoomPoolRequest {
     Spawn(RoomId),
     Shutdown(RoomId),
+    Controls,
+    /// Stops spawning rooms and returns the control channels of the running ones.
+    Drain,
+    /// Sent by the task that started the room, or the proxy of a room another instance owns,
+    /// which has no control channel.
+    Started(RoomId, RoomChannel, Option<mpsc::Sender<CtrlMessage>>),
+    /// Sent by the task that started the room when the cluster could not tell who owns it.
+    Failed(RoomId),
 }
 
 #[derive(Debug)]
 pub enum RoomPoolResponse {
     Channel(RoomChannel),
     GrantShutdown,
+    Controls(Vec<(RoomId, mpsc::Sender<CtrlMessage>)>),
+    /// The server shuts down, the room is not running and will not be spawned.
+    Draining,
+    /// The room could not be started, the next request tries again.
+    Unavailable,
 }
 
 pub type RoomPoolMessage = (RoomPoolRequest, oneshot::Sender<RoomPoolResponse>);
@@ -47,10 +109,17 @@ pub struct RoomPoolChannel {
 }
 
 impl RoomPoolChannel {
+    /// Channel of the room, spawning it if needed. Only ids [`validate::room_id`] keeps as they
+    /// are get a room, they end up in URLs and pages.
     pub async fn spawn(&self, room_id: &RoomId) -> Result<RoomChannel, ScError> {
+        if room_id.is_empty() || validate::room_id(room_id.clone()) != *room_id {
+            return Err(ScError::InvalidRoomId);
+        }
         let response = self.send(RoomPoolRequest::Spawn(room_id.clone())).await?;
         match response {
             RoomPoolResponse::Channel(ch) => Ok(ch),
+            RoomPoolResponse::Draining => Err(ScError::ShuttingDown),
+            RoomPoolResponse::Unavailable => Err(ScError::RoomUnavailable),
             _ => Err(ScError::UnexpectedResponse),
         }
     }
@@ -61,6 +130,21 @@ impl RoomPoolChannel {
         Ok(())
     }
 
+    /// Control channels of all running rooms.
+    pub async fn controls(&self) -> Result<Vec<(RoomId, mpsc::Sender<CtrlMessage>)>, ScError> {
+        match self.send(RoomPoolRequest::Controls).await? {
+            RoomPoolResponse::Controls(controls) => Ok(controls),
+            _ => Err(ScError::UnexpectedResponse),
+        }
+    }
+
+    pub async fn drain(&self) -> Result<Vec<(RoomId, mpsc::Sender<CtrlMessage>)>, ScError> {
+        match self.send(RoomPoolRequest::Drain).await? {
+            RoomPoolResponse::Controls(controls) => Ok(controls),
+            _ => Err(ScError::UnexpectedResponse),
+        }
+    }
+
     async fn send(&self, msg: RoomPoolRequest) -> Result<RoomPoolResponse, ScError> {
         let (rp_tx, rpr_rx) = oneshot::channel::<RoomPoolResponse>();
         let rp_message: RoomPoolMessage = (msg, rp_tx);
@@ -79,12 +163,20 @@ impl RoomPoolChannel {
 
 pub struct RoomPool {
     room_channels: Arc<RwLock<HashMap<RoomId, RoomChannel>>>,
+    room_controls: RwLock<HashMap<RoomId, mpsc::Sender<CtrlMessage>>>,
     room_pool_channel: RoomPoolChannel,
     room_pool_rx: mpsc::Receiver<RoomPoolMessage>,
+    pool: Arc<database::Pool>,
+    /// Shares the rooms with the other instances, `None` when this instance runs alone.
+    cluster: Option<Cluster>,
+    /// Requests waiting for rooms that are starting. Claiming a room and loading its snapshot
+    /// take a round trip to the database, the pool answers other requests meanwhile.
+    starting: HashMap<RoomId, Vec<oneshot::Sender<RoomPoolResponse>>>,
+    draining: bool,
 }
 
 impl RoomPool {
-    pub fn spawn() -> RoomPoolChannel {
+    pub fn spawn(pool: Arc<database::Pool>, cluster: Option<Cluster>) -> RoomPoolChannel {
         let (retx, rerx) = mpsc::channel::<RoomPoolMessage>(BUFFER_SIZE);
 
         let room_pool_chanel = RoomPoolChannel { request_tx: retx };
@@ -93,8 +185,13 @@ impl RoomPool {
         tokio::spawn(async move {
             let mut room_pool = RoomPool {
                 room_channels: Arc::new(RwLock::new(HashMap::new())),
+                room_controls: RwLock::new(HashMap::new()),
                 room_pool_channel: rp_ch,
                 room_pool_rx: rerx,
+                pool,
+                cluster,
+                starting: HashMap::new(),
+                draining: false,
             };
             room_pool.spawn_room_pool().await;
         });
@@ -107,26 +204,66 @@ impl RoomPool {
             while let Some((request, response)) = self.room_pool_rx.recv().await {
                 match request {
                     RoomPoolRequest::Spawn(room_id) => {
-                        let room_ch = self.spawn_or_find_room(room_id).await;
-                        response.send(RoomPoolResponse::Channel(room_ch)).unwrap();
+                        if let Some(channel) = self.find_channel(room_id.clone()).await {
+                            _ = response.send(RoomPoolResponse::Channel(channel));
+                        } else if self.draining {
+                            _ = response.send(RoomPoolResponse::Draining);
+                        } else if let Some(waiting) = self.starting.get_mut(&room_id) {
+                            waiting.push(response);
+                        } else {
+                            self.starting.insert(room_id.clone(), vec![response]);
+                            self.spawn_room(room_id);
+                        }
+                    }
+                    RoomPoolRequest::Started(room_id, channel, ctrl) => {
+                        let mut w_rooms = self.room_channels.write().await;
+                        let mut controls = self.room_controls.write().await;
+                        if let Some(ctrl) = ctrl {
+                            controls.insert(room_id.clone(), ctrl);
+                        }
+                        w_rooms.insert(room_id.clone(), channel.clone());
+                        metrics::ROOM_SPAWNS.inc();
+                        set_rooms(w_rooms.len(), controls.len());
+                        for waiting in self.starting.remove(&room_id).unwrap_or_default() {
+                            _ = waiting.send(RoomPoolResponse::Channel(channel.clone()));
+                        }
+                        _ = response.send(RoomPoolResponse::Channel(channel));
+                    }
+                    RoomPoolRequest::Failed(room_id) => {
+                        for waiting in self.starting.remove(&room_id).unwrap_or_default() {
+                            _ = waiting.send(RoomPoolResponse::Unavailable);
+                        }
+                        _ = response.send(RoomPoolResponse::Unavailable);
                     }
                     RoomPoolRequest::Shutdown(room_id) => {
                         let mut map = self.room_channels.write().await;
                         map.remove(&room_id);
-                        response.send(RoomPoolResponse::GrantShutdown).unwrap();
+                        let mut controls = self.room_controls.write().await;
+                        controls.remove(&room_id);
+                        metrics::ROOM_SHUTDOWNS.inc();
+                        set_rooms(map.len(), controls.len());
+                        _ = response.send(RoomPoolResponse::GrantShutdown);
                         tracing::trace!("Granted shutdown for room_id: {}", room_id);
                     }
+                    RoomPoolRequest::Controls => {
+                        _ = response.send(RoomPoolResponse::Controls(self.controls().await));
+                    }
+                    RoomPoolRequest::Drain => {
+                        self.draining = true;
+                        _ = response.send(RoomPoolResponse::Controls(self.controls().await));
+                    }
                 }
             }
         }
     }
 
-    pub async fn spawn_or_find_room(&self, room_id: RoomId) -> RoomChannel {
-        if let Some(channel) = self.find_channel(room_id.clone()).await {
-            channel
-        } else {
-            self.spawn_room(room_id.clone()).await
-        }
+    async fn controls(&self) -> Vec<(RoomId, mpsc::Sender<CtrlMessage>)> {
+        self.room_controls
+            .read()
+            .await
+            .iter()
+            .map(|(room_id, ctrl)| (room_id.clone(), ctrl.clone()))
+            .collect()
     }
 
     async fn find_channel(&self, room_id: RoomId) -> Option<RoomChannel> {
@@ -134,47 +271,138 @@ impl RoomPool {
         return r_rooms.get(&room_id).cloned();
     }
 
-    async fn spawn_room(&self, room_id: RoomId) -> RoomChannel {
-        let (room_tx, room_rx) = self.create_room_request_sender_channel();
-        let (room_bc_tx, _room_bc_rx) = self.create_room_broadcast_channel();
-        let rid = room_id.clone();
-        let new_room_ch = RoomChannel {
-            tx: room_tx,
-            broadcast: room_bc_tx,
-        };
-        let channel = new_room_ch.clone();
-
-        let (ctrl_tx, ctrl_rx) = mpsc::channel::<CtrlMessage>(BUFFER_SIZE);
-
+    /// Starts the room, or a proxy if another instance owns it, in a task of its own that
+    /// reports back with [`RoomPoolRequest::Started`] or [`RoomPoolRequest::Failed`].
+    fn spawn_room(&self, room_id: RoomId) {
         let room_pool_ch = self.room_pool_channel.clone();
+        let pool = self.pool.clone();
+        let cluster = self.cluster.clone();
         tokio::spawn(async move {
-            let room = Room::new(rid, channel);
-            room.run(room_rx, ctrl_rx, room_pool_ch).await;
+            let started = match start_room(room_id.clone(), pool, cluster, &room_pool_ch).await {
+                Ok((channel, ctrl)) => RoomPoolRequest::Started(room_id, channel, ctrl),
+                Err(err) => {
+                    tracing::error!("Failed to start room {}, error: {}", room_id, err);
+                    RoomPoolRequest::Failed(room_id)
+                }
+            };
+            let (tx, _rx) = oneshot::channel::<RoomPoolResponse>();
+            _ = room_pool_ch.request_tx.send((started, tx)).await;
         });
+    }
+}
 
-        let (htx, hrx) = oneshot::channel::<CtrlResponse>();
-        _ = ctrl_tx.send((CtrlRequest::HealthCheck, htx)).await;
-        hrx.await.ok();
+/// Runs the room here, unless another instance of the cluster owns it and a proxy stands in.
+/// Fails when the claim does, running the room anyway could run it on two instances.
+async fn start_room(
+    room_id: RoomId,
+    pool: Arc<database::Pool>,
+    cluster: Option<Cluster>,
+    room_pool_ch: &RoomPoolChannel,
+) -> Result<(RoomChannel, Option<mpsc::Sender<CtrlMessage>>), ScError> {
+    if let Some(cluster) = &cluster {
+        let owner = cluster.claim(&room_id).await?;
+        if owner != *cluster.instance() {
+            let channel = start_proxy(cluster, room_id.clone(), owner, room_pool_ch).await?;
+            return Ok((channel, None));
+        }
+    }
 
-        let mut w_rooms = self.room_channels.write().await;
+    let (room_tx, room_rx) = create_room_request_sender_channel();
+    let (room_bc_tx, _room_bc_rx) = create_room_broadcast_channel();
+    let rid = room_id.clone();
+    let new_room_ch = RoomChannel {
+        tx: room_tx,
+        broadcast: room_bc_tx,
+    };
+    let channel = new_room_ch.clone();
 
-        w_rooms.insert(room_id, new_room_ch.clone());
+    let (ctrl_tx, ctrl_rx) = mpsc::channel::<CtrlMessage>(BUFFER_SIZE);
 
-        new_room_ch
-    }
+    let snapshot = match snapshot::load_snapshot(&pool, &room_id).await {
+        Ok(snapshot) => snapshot,
+        Err(err) => {
+            tracing::error!(
+                "Failed to load snapshot of room {}, error: {}",
+                room_id,
+                err
+            );
+            None
+        }
+    };
 
-    fn create_room_request_sender_channel(
-        &self,
-    ) -> (mpsc::Sender<RoomMessage>, mpsc::Receiver<RoomMessage>) {
-        mpsc::channel::<(RoomRequest, oneshot::Sender<RoomResponse>)>(BUFFER_SIZE)
-    }
+    let room_pool_ch = room_pool_ch.clone();
+    tokio::spawn(async move {
+        let room = Room::new(rid, channel, pool);
+        if let Some(snapshot) = snapshot {
+            room.restore(snapshot).await;
+        }
+        room.run(room_rx, ctrl_rx, room_pool_ch).await;
+    });
 
-    fn create_room_broadcast_channel(
-        &self,
-    ) -> (
-        broadcast::Sender<RoomBroadcastMessage>,
-        broadcast::Receiver<RoomBroadcastMessage>,
-    ) {
-        broadcast::channel::<RoomBroadcastMessage>(BUFFER_SIZE)
+    let (htx, hrx) = oneshot::channel::<CtrlResponse>();
+    _ = ctrl_tx.send((CtrlRequest::HealthCheck, htx)).await;
+    hrx.await.ok();
+
+    if let Some(cluster) = &cluster {
+        if let Err(err) = cluster
+            .serve(room_id.clone(), new_room_ch.clone(), ctrl_tx.clone())
+            .await
+        {
+            tracing::error!(
+                "Failed to share room {} with the cluster, error: {}",
+                room_id,
+                err
+            );
+        }
     }
+
+    Ok((new_room_ch, Some(ctrl_tx)))
+}
+
+/// Channel to a room another instance owns, participants use it like a room running here.
+async fn start_proxy(
+    cluster: &Cluster,
+    room_id: RoomId,
+    owner: InstanceId,
+    room_pool_ch: &RoomPoolChannel,
+) -> Result<RoomChannel, ScError> {
+    let (room_tx, room_rx) = create_room_request_sender_channel();
+    let (room_bc_tx, _room_bc_rx) = create_room_broadcast_channel();
+    cluster
+        .proxy(
+            room_id.clone(),
+            owner.clone(),
+            room_rx,
+            room_bc_tx.clone(),
+            room_pool_ch.clone(),
+        )
+        .await?;
+    tracing::trace!("Standing in for room {} of instance {}", room_id, owner);
+
+    Ok(RoomChannel {
+        tx: room_tx,
+        broadcast: room_bc_tx,
+    })
+}
+
+fn create_room_request_sender_channel() -> (mpsc::Sender<RoomMessage>, mpsc::Receiver<RoomMessage>)
+{
+    mpsc::channel::<(RoomRequest, oneshot::Sender<RoomResponse>)>(BUFFER_SIZE)
+}
+
+fn create_room_broadcast_channel() -> (
+    broadcast::Sender<RoomBroadcastMessage>,
+    broadcast::Receiver<RoomBroadcastMessage>,
+) {
+    broadcast::channel::<RoomBroadcastMessage>(BUFFER_SIZE)
+}
+
+/// Only rooms running here have a control channel, the others are proxies.
+fn set_rooms(rooms: usize, owned: usize) {
+    metrics::ROOMS
+        .with_label_values(&["owner"])
+        .set(owned as i64);
+    metrics::ROOMS
+        .with_label_values(&["proxy"])
+        .set(rooms.saturating_sub(owned) as i64);
 }
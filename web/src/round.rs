@@ -0,0 +1,97 @@
+use crate::{
+    estimate::{CardDeck, Estimate},
+    room::{Participant, RoomId},
+    statistics::RoundStatistics,
+    story::Story,
+    validate::{self, ALPHABET_AND_NUMBERS},
+};
+use chrono::{DateTime, Utc};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Arc};
+use uuid::Uuid;
+
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct Vote {
+    pub name: Arc<str>,
+    pub estimate: Estimate,
+}
+
+/// Progress of writing the final estimate back to the issue tracker.
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub enum SyncStatus {
+    Pending,
+    Synced,
+    Failed(Arc<str>),
+}
+
+/// Result of a revealed round. Recorded on reveal, kept up to date while estimates are visible
+/// and closed when the estimates are reset.
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct RoundRecord {
+    pub round_id: Arc<str>,
+    pub room_id: RoomId,
+    pub story: Option<Story>,
+    /// Issue the final estimate is written to, taken from the story or attached by the facilitator.
+    #[serde(default)]
+    pub issue_key: Option<Arc<str>>,
+    pub votes: Vec<Vote>,
+    pub statistics: RoundStatistics,
+    pub final_estimate: Option<Estimate>,
+    pub revealed_at: DateTime<Utc>,
+    pub reset_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub sync: Option<SyncStatus>,
+}
+
+impl RoundRecord {
+    pub fn new(
+        room_id: RoomId,
+        story: Option<Story>,
+        participants: &HashMap<Uuid, Participant>,
+        deck: &CardDeck,
+    ) -> RoundRecord {
+        RoundRecord {
+            round_id: Arc::from(nanoid::nanoid!(10, &ALPHABET_AND_NUMBERS)),
+            room_id,
+            issue_key: story
+                .as_ref()
+                .and_then(|story| story.key.as_deref())
+                .and_then(validate::issue_key),
+            story,
+            votes: votes(participants, deck),
+            statistics: RoundStatistics::new(participants, deck),
+            final_estimate: None,
+            revealed_at: Utc::now(),
+            reset_at: None,
+            sync: None,
+        }
+    }
+
+    /// Refreshes the votes after someone changed their card with the estimates visible.
+    pub fn update(&mut self, participants: &HashMap<Uuid, Participant>, deck: &CardDeck) {
+        self.votes = votes(participants, deck);
+        self.statistics = RoundStatistics::new(participants, deck);
+    }
+}
+
+/// Votes of the round ordered by card, then by name.
+pub fn votes(participants: &HashMap<Uuid, Participant>, deck: &CardDeck) -> Vec<Vote> {
+    participants
+        .values()
+        .filter(|p| !p.observer && p.estimate != Estimate::None)
+        .sorted_by_key(|p| (deck.position(&p.estimate), p.name.clone()))
+        .map(|p| Vote {
+            name: p.name.clone(),
+            estimate: p.estimate.clone(),
+        })
+        .collect()
+}
+
+/// Sum of the numeric final estimates.
+pub fn total_points(rounds: &[RoundRecord]) -> f64 {
+    rounds
+        .iter()
+        .filter_map(|round| round.final_estimate.as_ref()?.value())
+        .sum()
+}
@@ -0,0 +1,74 @@
+use crate::{app::use_app_props, channel::RoomRequest, room::RoomSettings};
+use dioxus::prelude::*;
+
+const AUTO_REVEAL_COUNTDOWNS: [u64; 4] = [0, 3, 5, 10];
+
+#[component]
+pub fn AutoRevealSettings(settings: Signal<RoomSettings>) -> Element {
+    let app_props = use_app_props();
+
+    let send_settings = move |new_settings: RoomSettings| async move {
+        _ = app_props()
+            .channel
+            .send(RoomRequest::ChangeSettings(
+                app_props().session_id,
+                new_settings,
+            ))
+            .await;
+    };
+
+    rsx! {
+        div { class: "flex flex-wrap items-center gap-x-4 gap-y-2 text-slate-600",
+            label { class: "inline-flex items-center gap-x-2 cursor-pointer select-none",
+                input {
+                    r#type: "checkbox",
+                    class: "w-4 h-4 accent-slate-600",
+                    checked: settings().auto_reveal,
+                    onchange: move |evt| {
+                        let new_settings = RoomSettings {
+                            auto_reveal: evt.checked(),
+                            ..settings()
+                        };
+                        send_settings(new_settings)
+                    },
+                }
+                "Auto reveal"
+            }
+            if settings().auto_reveal {
+                select {
+                    class: "bg-white border border-slate-300 rounded-full px-4 py-2 focus:outline-none focus:ring-2 focus:ring-slate-600",
+                    onchange: move |evt| {
+                        let new_settings = RoomSettings {
+                            auto_reveal_countdown: evt.value().parse().unwrap_or_default(),
+                            ..settings()
+                        };
+                        send_settings(new_settings)
+                    },
+                    for seconds in AUTO_REVEAL_COUNTDOWNS {
+                        option {
+                            value: "{seconds}",
+                            selected: settings().auto_reveal_countdown == seconds,
+                            if seconds == 0 {
+                                "Immediately"
+                            } else {
+                                "After {seconds}s"
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+pub fn AutoRevealCountdown(auto_reveal_countdown: Signal<Option<u64>>) -> Element {
+    match auto_reveal_countdown() {
+        Some(remaining) => rsx! {
+            span { class: "px-4 py-1 rounded-full text-sm font-bold text-white bg-slate-600 animate-pulse",
+                "Everyone voted, revealing in {remaining}s"
+            }
+        },
+        None => rsx! {},
+    }
+}
@@ -0,0 +1,129 @@
+use crate::room_pool::{self, CtrlRequest, RoomPoolChannel};
+use axum::extract::ws::CloseFrame;
+use std::{future::Future, sync::Arc, time::Duration};
+use tokio::{sync::watch, time::timeout};
+
+/// Time rooms get to show the notice and save their state.
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+/// Time open sockets get to send their close frame.
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Close code for a server that restarts, see RFC 6455.
+const SERVICE_RESTART: u16 = 1012;
+
+const RESTART_NOTICE: &str = "The server is restarting, you will be reconnected in a moment.";
+
+#[derive(PartialEq, PartialOrd, Debug, Clone, Copy)]
+enum Phase {
+    Running,
+    /// Rooms are no longer spawned and save their state.
+    Draining,
+    /// Connections close.
+    Closing,
+}
+
+/// Tells long lived connections to close once the rooms are drained.
+#[derive(Clone)]
+pub struct Shutdown(Arc<watch::Sender<Phase>>);
+
+impl Shutdown {
+    pub fn new() -> Shutdown {
+        let (tx, _) = watch::channel(Phase::Running);
+        Shutdown(Arc::new(tx))
+    }
+
+    /// Whether the server shuts down, it should get no new traffic.
+    pub fn draining(&self) -> bool {
+        *self.0.borrow() >= Phase::Draining
+    }
+
+    /// Completes when connections should close. The server waits for it to be dropped, so it
+    /// should live as long as the connection.
+    pub fn closing(&self) -> impl Future<Output = ()> + Send + 'static {
+        let mut rx = self.0.subscribe();
+        async move {
+            _ = rx.wait_for(|phase| *phase == Phase::Closing).await;
+        }
+    }
+}
+
+/// Close frame of sockets closed by the shutdown, clients wait for the server and reconnect.
+pub fn restart_frame() -> CloseFrame<'static> {
+    CloseFrame {
+        code: SERVICE_RESTART,
+        reason: "server restarting".into(),
+    }
+}
+
+async fn signal() {
+    let interrupt = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Unable to listen for SIGINT");
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Unable to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        _ = interrupt => {},
+        _ = terminate => {},
+    }
+}
+
+/// Waits for SIGINT or SIGTERM, then stops spawning rooms, tells every room the server restarts,
+/// lets the rooms save their state and closes the open sockets.
+pub async fn graceful(room_pool: RoomPoolChannel, shutdown: Shutdown) {
+    signal().await;
+    tracing::info!("Shutting down, draining rooms");
+    shutdown.0.send_replace(Phase::Draining);
+
+    let controls = match room_pool.drain().await {
+        Ok(controls) => controls,
+        Err(err) => {
+            tracing::error!("Failed to drain room pool, error: {}", err);
+            Vec::new()
+        }
+    };
+    let rooms = controls.iter().map(|(room_id, ctrl)| async move {
+        let notice = CtrlRequest::Notice(Arc::from(RESTART_NOTICE));
+        room_pool::control(ctrl, notice).await?;
+        room_pool::control(ctrl, CtrlRequest::Flush).await?;
+        tracing::trace!("Drained room {}", room_id);
+        Some(())
+    });
+    match timeout(DRAIN_TIMEOUT, futures::future::join_all(rooms)).await {
+        Ok(_) => tracing::info!("Drained {} rooms", controls.len()),
+        Err(_) => tracing::warn!("Rooms did not drain within {:?}", DRAIN_TIMEOUT),
+    }
+
+    shutdown.0.send_replace(Phase::Closing);
+    if timeout(CLOSE_TIMEOUT, shutdown.0.closed()).await.is_err() {
+        tracing::warn!("Sockets did not close within {:?}", CLOSE_TIMEOUT);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn connections_follow_the_shutdown() {
+        let shutdown = Shutdown::new();
+        let closing = shutdown.closing();
+        assert!(!shutdown.draining());
+        shutdown.0.send_replace(Phase::Draining);
+        assert!(shutdown.draining());
+        shutdown.0.send_replace(Phase::Closing);
+        assert!(shutdown.draining());
+        closing.await;
+        timeout(Duration::from_millis(100), shutdown.0.closed())
+            .await
+            .expect("no connection is left");
+    }
+}
@@ -0,0 +1,79 @@
+use crate::{
+    channel::EstimateVisibility,
+    database::{self, Pool},
+    error::ScError,
+    estimate::CardDeck,
+    room::{Participant, RoomId, RoomSettings},
+    round::RoundRecord,
+    story::StoryQueue,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::watch;
+
+const SNAPSHOT_TABLE: &str = "room_snapshot";
+
+/// State of a room saved on every change, so a restart of the server does not wipe the round.
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct RoomSnapshot {
+    pub room_id: RoomId,
+    pub participants: Vec<Participant>,
+    pub visibility: EstimateVisibility,
+    pub deck: CardDeck,
+    pub settings: RoomSettings,
+    pub stories: StoryQueue,
+    pub rounds: Vec<RoundRecord>,
+    pub round_record: Option<usize>,
+}
+
+pub async fn save_snapshot(pool: &Pool, snapshot: &RoomSnapshot) -> Result<(), ScError> {
+    let db = database::connection(pool).await?;
+    db.query("UPSERT type::thing($table, $room_id) CONTENT $snapshot")
+        .bind(("table", SNAPSHOT_TABLE))
+        .bind(("room_id", snapshot.room_id.clone()))
+        .bind(("snapshot", snapshot.clone()))
+        .await?;
+    Ok(())
+}
+
+pub async fn load_snapshot(pool: &Pool, room_id: &RoomId) -> Result<Option<RoomSnapshot>, ScError> {
+    let db = database::connection(pool).await?;
+    let mut response = db
+        .query("SELECT * FROM type::thing($table, $room_id)")
+        .bind(("table", SNAPSHOT_TABLE))
+        .bind(("room_id", room_id.clone()))
+        .await?;
+    Ok(response.take(0)?)
+}
+
+pub async fn delete_snapshot(pool: &Pool, room_id: &RoomId) -> Result<(), ScError> {
+    let db = database::connection(pool).await?;
+    db.query("DELETE type::thing($table, $room_id)")
+        .bind(("table", SNAPSHOT_TABLE))
+        .bind(("room_id", room_id.clone()))
+        .await?;
+    Ok(())
+}
+
+/// Saves the latest snapshot in the background. Snapshots sent while a save is in flight are
+/// coalesced and sending `None` deletes the saved snapshot.
+pub fn spawn_writer(pool: Arc<Pool>, room_id: RoomId) -> watch::Sender<Option<RoomSnapshot>> {
+    let (tx, mut rx) = watch::channel::<Option<RoomSnapshot>>(None);
+    tokio::spawn(async move {
+        while rx.changed().await.is_ok() {
+            let snapshot = rx.borrow_and_update().clone();
+            let result = match snapshot {
+                Some(snapshot) => save_snapshot(&pool, &snapshot).await,
+                None => delete_snapshot(&pool, &room_id).await,
+            };
+            if let Err(err) = result {
+                tracing::error!(
+                    "Failed to save snapshot of room {}, error: {}",
+                    room_id,
+                    err
+                );
+            }
+        }
+    });
+    tx
+}
@@ -0,0 +1,562 @@
+use crate::{
+    api::{self, ApiError},
+    channel::{EstimateVisibility, RoomRequest},
+    estimate::{CardDeck, Estimate},
+    events::{RoomEvent, RoomEvents},
+    metrics,
+    room::{Participant, RoomId, RoomSettings, MAX_TIMER_SECONDS},
+    shutdown,
+    state::AppState,
+    story::Story,
+    validate,
+};
+use axum::extract::ws::{Message, WebSocket};
+use serde::{Deserialize, Serialize};
+use std::{sync::Arc, time::Duration};
+use uuid::Uuid;
+
+/// Version of the socket protocol. Clients announce the version they speak in their `hello` and
+/// servers refuse versions they do not know, so breaking changes get a new version.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Time a client gets to say hello after connecting.
+const HELLO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Messages clients send, JSON objects with a `type` field. Every message but the `hello` may
+/// carry a numeric `id`, which the server echoes in the `ack` or `error` it answers with.
+///
+/// The protocol covers voting, the round, the timer, the deck, the settings, adding stories and
+/// handing the facilitator role over. The story queue is not part of the [`RoomEvent`]s, so
+/// picking, moving or removing stories, importing them and the issue tracker, webhook and chat
+/// integrations are only available in the web UI. Messages besides the `estimate`, name and
+/// observer changes are for the facilitator.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    Hello(Hello),
+    /// Card from the deck of the room, empty to take the estimate back.
+    Estimate {
+        estimate: Estimate,
+    },
+    Reveal,
+    Hide,
+    /// Deletes the estimates of the round.
+    Reset,
+    ChangeName {
+        name: String,
+    },
+    ChangeObserver {
+        observer: bool,
+    },
+    /// Deals the given cards, like a custom deck in the web UI.
+    ChangeDeck {
+        cards: Vec<String>,
+    },
+    ChangeSettings {
+        settings: RoomSettings,
+    },
+    /// Round timer of up to an hour.
+    StartTimer {
+        seconds: u64,
+    },
+    StopTimer,
+    /// Adds a story to the end of the queue.
+    AddStory {
+        title: String,
+        #[serde(default)]
+        link: String,
+        #[serde(default)]
+        description: String,
+    },
+    /// Moves on to the next story of the queue and starts a fresh round.
+    NextStory,
+    /// Hands the facilitator role to the participant with the public id.
+    TransferFacilitator {
+        participant: Uuid,
+    },
+}
+
+/// Opens the session. With a `token` the client takes its seat in the room back, with a `name` it
+/// joins as a new participant and without either it only watches.
+#[derive(Debug, Deserialize)]
+pub struct Hello {
+    pub version: u32,
+    #[serde(default)]
+    pub token: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub observer: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClientEnvelope {
+    #[serde(default)]
+    pub id: Option<u64>,
+    #[serde(flatten)]
+    pub message: ClientMessage,
+}
+
+/// Seat of the client in the room. The token authenticates the participant on the REST API and
+/// in the `hello` of later connections.
+#[derive(Debug, Serialize)]
+pub struct Seat {
+    pub id: Uuid,
+    pub token: String,
+}
+
+/// Messages the server sends besides the [`RoomEvent`]s, which share the `type` field.
+///
+/// The server answers the `hello` with a `welcome`, followed by a `snapshot` event of the room
+/// and the events of the room from then on.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    Welcome {
+        version: u32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        seat: Option<Seat>,
+    },
+    Ack {
+        id: u64,
+    },
+    /// Failed request, or why the server closes the socket.
+    Error {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        id: Option<u64>,
+        code: &'static str,
+        message: String,
+    },
+    #[serde(untagged)]
+    Event(Box<RoomEvent>),
+}
+
+impl ServerMessage {
+    fn error(id: Option<u64>, err: &ApiError) -> ServerMessage {
+        ServerMessage::Error {
+            id,
+            code: err.code(),
+            message: err.to_string(),
+        }
+    }
+}
+
+async fn send(socket: &mut WebSocket, message: &ServerMessage) -> bool {
+    match serde_json::to_string(message) {
+        Ok(text) => socket.send(Message::Text(text)).await.is_ok(),
+        Err(err) => {
+            tracing::error!("Failed to serialize socket message, error: {}", err);
+            true
+        }
+    }
+}
+
+fn parse(message: Message) -> Option<Result<ClientEnvelope, ApiError>> {
+    match message {
+        Message::Text(text) => Some(
+            serde_json::from_str(&text).map_err(|err| ApiError::InvalidMessage(err.to_string())),
+        ),
+        Message::Binary(_) => Some(Err(ApiError::InvalidMessage(
+            "messages are JSON text".to_string(),
+        ))),
+        Message::Ping(_) | Message::Pong(_) | Message::Close(_) => None,
+    }
+}
+
+/// Joins or rejoins the room as asked in the `hello`. `None` for clients that only watch.
+async fn take_seat(
+    state: &AppState,
+    room_id: &RoomId,
+    hello: Hello,
+) -> Result<Option<Uuid>, ApiError> {
+    let channel = api::channel(state, room_id).await?;
+    let (session_id, existing) = match (hello.token, &hello.name) {
+        (Some(token), _) => {
+            let session_id = api::verify_token(&state.api_secret, room_id, &token)
+                .ok_or(ApiError::Unauthorized)?;
+            let room_state = api::room_state(&channel).await?;
+            (
+                session_id,
+                room_state.participants.get(&session_id).cloned(),
+            )
+        }
+        (None, Some(_)) => (Uuid::new_v4(), None),
+        (None, None) => return Ok(None),
+    };
+    let name = match (hello.name, &existing) {
+        (Some(name), _) => Arc::from(validate::username(&name)),
+        (None, Some(existing)) => existing.name.clone(),
+        (None, None) => Arc::from(validate::username(&String::new())),
+    };
+    let mut participant = Participant::new(session_id, name);
+    participant.observer = hello
+        .observer
+        .or(existing.map(|existing| existing.observer))
+        .unwrap_or(false);
+    api::join_as(&channel, participant).await?;
+    tracing::trace!("Socket participant {} joined room {}", session_id, room_id);
+    Ok(Some(session_id))
+}
+
+async fn handle(
+    state: &AppState,
+    room_id: &RoomId,
+    session_id: Option<Uuid>,
+    message: ClientMessage,
+) -> Result<(), ApiError> {
+    let session_id = session_id.ok_or(ApiError::Unauthorized)?;
+    let channel = api::channel(state, room_id).await?;
+    let request = match message {
+        ClientMessage::Hello(_) => {
+            return Err(ApiError::InvalidMessage("already said hello".to_string()));
+        }
+        ClientMessage::Estimate { estimate } => {
+            return api::send_estimate_of(&channel, session_id, estimate).await;
+        }
+        ClientMessage::Reveal => {
+            RoomRequest::SetVisibility(session_id, EstimateVisibility::Visible)
+        }
+        ClientMessage::Hide => RoomRequest::SetVisibility(session_id, EstimateVisibility::Hidden),
+        ClientMessage::Reset => RoomRequest::DeleteEstimates(session_id),
+        ClientMessage::ChangeName { name } => {
+            RoomRequest::NameChange(session_id, Arc::from(validate::username(&name)))
+        }
+        ClientMessage::ChangeObserver { observer } => {
+            RoomRequest::ChangeObserver(session_id, observer)
+        }
+        ClientMessage::ChangeDeck { cards } => {
+            let cards = validate::custom_deck(&cards.join(","));
+            if cards.is_empty() {
+                return Err(ApiError::InvalidMessage(
+                    "the deck has no cards".to_string(),
+                ));
+            }
+            RoomRequest::ChangeDeck(session_id, CardDeck::Custom(cards))
+        }
+        ClientMessage::ChangeSettings { settings } => {
+            RoomRequest::ChangeSettings(session_id, settings)
+        }
+        ClientMessage::StartTimer { seconds } => {
+            if seconds == 0 || seconds > MAX_TIMER_SECONDS {
+                return Err(ApiError::InvalidMessage(format!(
+                    "timers run for 1 to {MAX_TIMER_SECONDS} seconds"
+                )));
+            }
+            RoomRequest::StartTimer(session_id, seconds)
+        }
+        ClientMessage::StopTimer => RoomRequest::StopTimer(session_id),
+        ClientMessage::AddStory {
+            title,
+            link,
+            description,
+        } => {
+            let story = Story::new(&title, &link, &description)
+                .ok_or_else(|| ApiError::InvalidMessage("stories need a title".to_string()))?;
+            RoomRequest::AddStory(session_id, story)
+        }
+        ClientMessage::NextStory => RoomRequest::NextStory(session_id),
+        ClientMessage::TransferFacilitator { participant } => {
+            let room_state = api::room_state(&channel).await?;
+            let new_facilitator = room_state
+                .participants
+                .keys()
+                .find(|session_id| api::participant_id(**session_id) == participant)
+                .ok_or(ApiError::NotInRoom)?;
+            RoomRequest::TransferFacilitator(session_id, *new_facilitator)
+        }
+    };
+    api::send(&channel, request).await
+}
+
+/// Waits for the `hello` of the client. Anything else ends the session.
+async fn hello(socket: &mut WebSocket) -> Result<Hello, ApiError> {
+    loop {
+        let Some(Ok(message)) = socket.recv().await else {
+            return Err(ApiError::InvalidMessage("closed before hello".to_string()));
+        };
+        return match parse(message) {
+            None => continue,
+            Some(Ok(ClientEnvelope {
+                message: ClientMessage::Hello(hello),
+                ..
+            })) if hello.version != PROTOCOL_VERSION => {
+                Err(ApiError::UnsupportedVersion(hello.version))
+            }
+            Some(Ok(ClientEnvelope {
+                message: ClientMessage::Hello(hello),
+                ..
+            })) => Ok(hello),
+            Some(Ok(_)) => Err(ApiError::InvalidMessage(
+                "the first message must be a hello".to_string(),
+            )),
+            Some(Err(err)) => Err(err),
+        };
+    }
+}
+
+/// Serves a client of the JSON socket protocol until either side goes away. Participants that
+/// joined over the socket leave the room when it closes, like closing the browser tab.
+pub async fn serve(mut socket: WebSocket, state: AppState, room_id: RoomId) {
+    let _socket = metrics::WebSocketGuard::open("api");
+    let closing = state.shutdown.closing();
+    tokio::pin!(closing);
+    let hello = match tokio::time::timeout(HELLO_TIMEOUT, hello(&mut socket)).await {
+        Ok(Ok(hello)) => hello,
+        Ok(Err(err)) => {
+            send(&mut socket, &ServerMessage::error(None, &err)).await;
+            return;
+        }
+        Err(_) => {
+            let err = ApiError::InvalidMessage("no hello received".to_string());
+            send(&mut socket, &ServerMessage::error(None, &err)).await;
+            return;
+        }
+    };
+
+    let mut events = match RoomEvents::subscribe(state.room_pool.clone(), room_id.clone()).await {
+        Ok(events) => events,
+        Err(err) => {
+            send(&mut socket, &ServerMessage::error(None, &err.into())).await;
+            return;
+        }
+    };
+    let session_id = match take_seat(&state, &room_id, hello).await {
+        Ok(session_id) => session_id,
+        Err(err) => {
+            send(&mut socket, &ServerMessage::error(None, &err)).await;
+            return;
+        }
+    };
+    let welcome = ServerMessage::Welcome {
+        version: PROTOCOL_VERSION,
+        seat: session_id.map(|session_id| Seat {
+            id: api::participant_id(session_id),
+            token: api::participant_token(&state.api_secret, &room_id, session_id),
+        }),
+    };
+
+    let mut open = send(&mut socket, &welcome).await;
+    while open {
+        tokio::select! {
+            _ = &mut closing => {
+                _ = socket.send(Message::Close(Some(shutdown::restart_frame()))).await;
+                break;
+            }
+            event = events.next() => {
+                open = match event {
+                    Some(event) => send(&mut socket, &ServerMessage::Event(Box::new(event))).await,
+                    None => false,
+                };
+            }
+            message = socket.recv() => {
+                let Some(Ok(message)) = message else {
+                    break;
+                };
+                let reply = match parse(message) {
+                    None => continue,
+                    Some(Err(err)) => ServerMessage::error(None, &err),
+                    Some(Ok(ClientEnvelope { id, message })) => {
+                        match handle(&state, &room_id, session_id, message).await {
+                            Ok(()) => match id {
+                                Some(id) => ServerMessage::Ack { id },
+                                None => continue,
+                            },
+                            Err(err) => ServerMessage::error(id, &err),
+                        }
+                    }
+                };
+                open = send(&mut socket, &reply).await;
+            }
+        }
+    }
+
+    // Leaving a closed room would spawn it again.
+    if let Some(session_id) = session_id.filter(|_| !events.is_closed()) {
+        if let Ok(channel) = api::channel(&state, &room_id).await {
+            _ = channel.send(RoomRequest::Leave(session_id)).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        api::{ParticipantView, RoomView},
+        statistics::RoundStatistics,
+    };
+    use std::collections::HashMap;
+
+    #[test]
+    fn parses_client_messages() {
+        let hello: ClientEnvelope =
+            serde_json::from_str(r#"{"type": "hello", "version": 1, "name": "Bot"}"#).unwrap();
+        let ClientMessage::Hello(hello) = hello.message else {
+            panic!("expected a hello");
+        };
+        assert_eq!(hello.version, 1);
+        assert_eq!(hello.name.as_deref(), Some("Bot"));
+        assert_eq!(hello.token, None);
+
+        let estimate: ClientEnvelope =
+            serde_json::from_str(r#"{"id": 3, "type": "estimate", "estimate": "8"}"#).unwrap();
+        assert_eq!(estimate.id, Some(3));
+        assert!(matches!(
+            estimate.message,
+            ClientMessage::Estimate { estimate } if estimate == Estimate::from("8")
+        ));
+
+        let reveal: ClientEnvelope = serde_json::from_str(r#"{"type": "reveal"}"#).unwrap();
+        assert!(matches!(reveal.message, ClientMessage::Reveal));
+
+        let story: ClientEnvelope =
+            serde_json::from_str(r#"{"type": "add_story", "title": "Login"}"#).unwrap();
+        assert!(matches!(
+            story.message,
+            ClientMessage::AddStory { title, link, .. } if title == "Login" && link.is_empty()
+        ));
+
+        let deck: ClientEnvelope =
+            serde_json::from_str(r#"{"type": "change_deck", "cards": ["S", "M", "L"]}"#).unwrap();
+        assert!(matches!(deck.message, ClientMessage::ChangeDeck { cards } if cards.len() == 3));
+
+        let timer: ClientEnvelope =
+            serde_json::from_str(r#"{"type": "start_timer", "seconds": 60}"#).unwrap();
+        assert!(matches!(
+            timer.message,
+            ClientMessage::StartTimer { seconds: 60 }
+        ));
+
+        let id = Uuid::new_v4();
+        let transfer: ClientEnvelope = serde_json::from_str(&format!(
+            r#"{{"type": "transfer_facilitator", "participant": "{id}"}}"#
+        ))
+        .unwrap();
+        assert!(matches!(
+            transfer.message,
+            ClientMessage::TransferFacilitator { participant } if participant == id
+        ));
+
+        assert!(serde_json::from_str::<ClientEnvelope>(r#"{"type": "shout"}"#).is_err());
+    }
+
+    /// Type of the event, listed here so that new events have to be added to the test below.
+    fn event_type(event: &RoomEvent) -> &'static str {
+        match event {
+            RoomEvent::Snapshot { .. } => "snapshot",
+            RoomEvent::ParticipantJoined { .. } => "participant_joined",
+            RoomEvent::ParticipantUpdated { .. } => "participant_updated",
+            RoomEvent::ParticipantLeft { .. } => "participant_left",
+            RoomEvent::EstimatesDeleted => "estimates_deleted",
+            RoomEvent::Statistics { .. } => "statistics",
+            RoomEvent::DeckChanged { .. } => "deck_changed",
+            RoomEvent::StoryChanged { .. } => "story_changed",
+            RoomEvent::AutoRevealCountdown { .. } => "auto_reveal_countdown",
+            RoomEvent::AutoRevealCancelled => "auto_reveal_cancelled",
+            RoomEvent::TimerStarted { .. } => "timer_started",
+            RoomEvent::TimerTick { .. } => "timer_tick",
+            RoomEvent::TimerExpired => "timer_expired",
+            RoomEvent::TimerStopped => "timer_stopped",
+            RoomEvent::Notice { .. } => "notice",
+            RoomEvent::RoomClosed => "room_closed",
+        }
+    }
+
+    #[test]
+    fn event_types_differ_from_server_messages() {
+        let server_types: Vec<serde_json::Value> = [
+            ServerMessage::Welcome {
+                version: PROTOCOL_VERSION,
+                seat: None,
+            },
+            ServerMessage::Ack { id: 1 },
+            ServerMessage::error(None, &ApiError::Forbidden),
+        ]
+        .iter()
+        .map(|message| serde_json::to_value(message).unwrap()["type"].clone())
+        .collect();
+        assert_eq!(server_types, ["welcome", "ack", "error"]);
+
+        let participant = ParticipantView {
+            id: Uuid::new_v4(),
+            name: Arc::from("Bot"),
+            facilitator: false,
+            observer: false,
+            online: true,
+            voted: false,
+            estimate: None,
+        };
+        let events = [
+            RoomEvent::Snapshot {
+                room: RoomView {
+                    room_id: Arc::from("room1"),
+                    revealed: false,
+                    deck: Vec::new(),
+                    participants: Vec::new(),
+                    story: None,
+                    statistics: None,
+                },
+            },
+            RoomEvent::ParticipantJoined {
+                participant: participant.clone(),
+            },
+            RoomEvent::ParticipantUpdated { participant },
+            RoomEvent::ParticipantLeft { id: Uuid::new_v4() },
+            RoomEvent::EstimatesDeleted,
+            RoomEvent::Statistics {
+                statistics: RoundStatistics::new(&HashMap::new(), &CardDeck::Fibonacci),
+            },
+            RoomEvent::DeckChanged { deck: Vec::new() },
+            RoomEvent::StoryChanged { story: None },
+            RoomEvent::AutoRevealCountdown { seconds: 3 },
+            RoomEvent::AutoRevealCancelled,
+            RoomEvent::TimerStarted { seconds: 60 },
+            RoomEvent::TimerTick { seconds: 59 },
+            RoomEvent::TimerExpired,
+            RoomEvent::TimerStopped,
+            RoomEvent::Notice {
+                message: Arc::from("maintenance"),
+            },
+            RoomEvent::RoomClosed,
+        ];
+        for event in &events {
+            let name = serde_json::to_value(event).unwrap()["type"].clone();
+            assert_eq!(name, event_type(event), "{event:?}");
+            assert!(!server_types.contains(&name), "{name} is taken");
+        }
+    }
+
+    #[test]
+    fn server_messages_share_the_type_field() {
+        let error = ServerMessage::error(Some(3), &ApiError::Forbidden);
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({
+                "type": "error",
+                "id": 3,
+                "code": "forbidden",
+                "message": "only the facilitator may do this"
+            })
+        );
+
+        let event = ServerMessage::Event(Box::new(RoomEvent::TimerTick { seconds: 5 }));
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({ "type": "timer_tick", "seconds": 5 })
+        );
+
+        let room = RoomView {
+            room_id: Arc::from("room1"),
+            revealed: false,
+            deck: Vec::new(),
+            participants: Vec::new(),
+            story: None,
+            statistics: None,
+        };
+        let snapshot = ServerMessage::Event(Box::new(RoomEvent::Snapshot { room }));
+        assert_eq!(
+            serde_json::to_value(&snapshot).unwrap()["type"],
+            serde_json::json!("snapshot")
+        );
+    }
+}
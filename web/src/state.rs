@@ -1,6 +1,8 @@
 use crate::{
+    cluster::Cluster,
     database,
     room_pool::{RoomPool, RoomPoolChannel},
+    shutdown::Shutdown,
 };
 use std::{env, io, net::ToSocketAddrs, sync::Arc};
 
@@ -11,20 +13,48 @@ pub struct AppState {
     pub pool: Arc<database::Pool>,
     pub view: dioxus_liveview::LiveViewPool,
     pub room_pool: RoomPoolChannel,
+    /// Key participant tokens of the JSON API are signed with.
+    pub api_secret: Arc<str>,
+    /// Token operators sign in to the admin dashboard with, the dashboard is off without it.
+    pub admin_token: Option<Arc<str>>,
+    pub shutdown: Shutdown,
 }
 
 impl AppState {
     pub fn new() -> AppState {
         let mgr = database::Manager {};
-        let pool = database::Pool::builder(mgr).max_size(50).build().unwrap();
+        let pool = Arc::new(database::Pool::builder(mgr).max_size(50).build().unwrap());
         let hostname = env::var("HOST_ADDRESS").unwrap_or("127.0.0.1:3030".into());
         let addr = Self::resolve_host(hostname.as_str()).expect("AppState failure");
         AppState {
             addr,
             ws_addr: Arc::from(env::var("WS_ADDRESS").unwrap_or("ws://127.0.0.1:3030".into())),
-            pool: Arc::new(pool),
+            pool: pool.clone(),
             view: dioxus_liveview::LiveViewPool::new(),
-            room_pool: RoomPool::spawn(),
+            room_pool: RoomPool::spawn(pool.clone(), Cluster::from_env(pool)),
+            api_secret: Self::api_secret(),
+            admin_token: Self::admin_token(),
+            shutdown: Shutdown::new(),
+        }
+    }
+
+    fn api_secret() -> Arc<str> {
+        match env::var("API_SECRET") {
+            Ok(secret) if !secret.trim().is_empty() => Arc::from(secret.trim()),
+            _ => {
+                tracing::warn!("API_SECRET is not set, API tokens will not survive a restart");
+                Arc::from(nanoid::nanoid!(32))
+            }
+        }
+    }
+
+    fn admin_token() -> Option<Arc<str>> {
+        match env::var("ADMIN_TOKEN") {
+            Ok(token) if !token.trim().is_empty() => Some(Arc::from(token.trim())),
+            _ => {
+                tracing::info!("ADMIN_TOKEN is not set, the admin dashboard is disabled");
+                None
+            }
         }
     }
 
@@ -0,0 +1,188 @@
+use crate::{
+    estimate::{CardDeck, Estimate},
+    room::{Participant, ParticipantStatus},
+};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub enum Consensus {
+    /// Every card played is the same.
+    Unanimous,
+    /// Cards played are at most one step apart in the deck.
+    Close,
+    Split,
+    NoVotes,
+}
+
+/// Summary of a revealed round. Numeric values are only available when the deck has numeric cards.
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct RoundStatistics {
+    pub average: Option<f64>,
+    pub median: Option<f64>,
+    pub mode: Vec<Estimate>,
+    pub min: Option<Estimate>,
+    pub max: Option<Estimate>,
+    pub votes: usize,
+    pub voters: usize,
+    pub question_marks: usize,
+    pub coffees: usize,
+    pub consensus: Consensus,
+}
+
+impl RoundStatistics {
+    /// Only online participants count, votes and voters alike.
+    pub fn new(participants: &HashMap<Uuid, Participant>, deck: &CardDeck) -> RoundStatistics {
+        let voting = || {
+            participants
+                .values()
+                .filter(|p| !p.observer && p.status == ParticipantStatus::Online)
+        };
+        let voters = voting().count();
+        let estimates: Vec<&Estimate> = voting()
+            .filter(|p| p.estimate != Estimate::None)
+            .map(|p| &p.estimate)
+            .collect();
+        let cards: Vec<(usize, &Estimate)> = estimates
+            .iter()
+            .filter(|e| matches!(e, Estimate::Card(_)))
+            .filter_map(|e| deck.position(e).map(|position| (position, *e)))
+            .sorted_by_key(|(position, _)| *position)
+            .collect();
+
+        let mut values: Vec<f64> = cards.iter().filter_map(|(_, e)| e.value()).collect();
+        values.sort_by(f64::total_cmp);
+        let average =
+            (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64);
+
+        let counts = cards.iter().counts_by(|(position, _)| *position);
+        let max_count = counts.values().max().copied().unwrap_or(0);
+        let mode = cards
+            .iter()
+            .dedup_by(|a, b| a.0 == b.0)
+            .filter(|(position, _)| counts[position] == max_count)
+            .map(|(_, e)| (*e).clone())
+            .collect();
+
+        let consensus = match (cards.first(), cards.last()) {
+            (Some(min), Some(max)) if min.0 == max.0 => Consensus::Unanimous,
+            (Some(min), Some(max)) if max.0 - min.0 == 1 => Consensus::Close,
+            (Some(_), Some(_)) => Consensus::Split,
+            _ => Consensus::NoVotes,
+        };
+
+        RoundStatistics {
+            average,
+            median: median(&values),
+            mode,
+            min: cards.first().map(|(_, e)| (*e).clone()),
+            max: cards.last().map(|(_, e)| (*e).clone()),
+            votes: estimates.len(),
+            voters,
+            question_marks: estimates
+                .iter()
+                .filter(|e| ***e == Estimate::QuestionMark)
+                .count(),
+            coffees: estimates.iter().filter(|e| ***e == Estimate::Coffe).count(),
+            consensus,
+        }
+    }
+}
+
+/// Median of values already sorted in ascending order.
+fn median(values: &[f64]) -> Option<f64> {
+    let middle = values.len() / 2;
+    match values.len() {
+        0 => None,
+        len if len % 2 == 0 => Some((values[middle - 1] + values[middle]) / 2.0),
+        _ => Some(values[middle]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    /// Deck, cards played, then the expected average, median, mode and consensus.
+    type Case<'a> = (
+        &'a CardDeck,
+        &'a [&'a str],
+        Option<f64>,
+        Option<f64>,
+        &'a [&'a str],
+        Consensus,
+    );
+
+    fn participants(cards: &[&str]) -> HashMap<Uuid, Participant> {
+        cards
+            .iter()
+            .map(|card| {
+                let mut participant = Participant::new(Uuid::new_v4(), Arc::from("voter"));
+                participant.estimate = Estimate::from(*card);
+                (participant.session_id, participant)
+            })
+            .collect()
+    }
+
+    fn statistics(deck: &CardDeck, cards: &[&str]) -> RoundStatistics {
+        RoundStatistics::new(&participants(cards), deck)
+    }
+
+    #[test]
+    fn summarizes_revealed_cards() {
+        let fibonacci = CardDeck::ModifiedFibonacci;
+        let t_shirt = CardDeck::TShirt;
+        #[rustfmt::skip]
+        let cases: [Case; 9] = [
+            (&fibonacci, &[], None, None, &[], Consensus::NoVotes),
+            (&fibonacci, &["☕", "☕️"], None, None, &[], Consensus::NoVotes),
+            (&fibonacci, &["?", "?", "☕"], None, None, &[], Consensus::NoVotes),
+            (&fibonacci, &["5"], Some(5.0), Some(5.0), &["5"], Consensus::Unanimous),
+            (&fibonacci, &["5", "?", "5"], Some(5.0), Some(5.0), &["5"], Consensus::Unanimous),
+            (&fibonacci, &["3", "5", "5", "3"], Some(4.0), Some(4.0), &["3", "5"], Consensus::Close),
+            (&fibonacci, &["1", "13", "2"], Some(16.0 / 3.0), Some(2.0), &["1", "2", "13"], Consensus::Split),
+            (&t_shirt, &["M", "S", "M"], None, None, &["M"], Consensus::Close),
+            (&t_shirt, &["XS", "XL"], None, None, &["XS", "XL"], Consensus::Split),
+        ];
+        for (deck, cards, average, median, mode, consensus) in cases {
+            let statistics = statistics(deck, cards);
+            let mode: Vec<Estimate> = mode.iter().map(|card| Estimate::from(*card)).collect();
+            assert_eq!(statistics.average, average, "average of {cards:?}");
+            assert_eq!(statistics.median, median, "median of {cards:?}");
+            assert_eq!(statistics.mode, mode, "mode of {cards:?}");
+            assert_eq!(statistics.consensus, consensus, "consensus of {cards:?}");
+        }
+    }
+
+    #[test]
+    fn counts_special_cards_and_missing_votes() {
+        let statistics = statistics(&CardDeck::ModifiedFibonacci, &["?", "☕", "", "8", "2"]);
+        assert_eq!(statistics.votes, 4);
+        assert_eq!(statistics.voters, 5);
+        assert_eq!(statistics.question_marks, 1);
+        assert_eq!(statistics.coffees, 1);
+        assert_eq!(statistics.min, Some(Estimate::from("2")));
+        assert_eq!(statistics.max, Some(Estimate::from("8")));
+
+        let mut participants = participants(&["5", ""]);
+        let mut left = Participant::new(Uuid::new_v4(), Arc::from("left"));
+        left.estimate = Estimate::from("13");
+        left.status = ParticipantStatus::Left;
+        participants.insert(left.session_id, left);
+        let statistics = RoundStatistics::new(&participants, &CardDeck::ModifiedFibonacci);
+        assert_eq!(statistics.votes, 1);
+        assert_eq!(statistics.voters, 2);
+        assert_eq!(statistics.max, Some(Estimate::from("5")));
+    }
+
+    #[test]
+    fn median_of_sorted_values() {
+        assert_eq!(median(&[]), None);
+        assert_eq!(median(&[3.0]), Some(3.0));
+        assert_eq!(median(&[1.0, 2.0, 8.0]), Some(2.0));
+        assert_eq!(median(&[1.0, 2.0, 3.0, 8.0]), Some(2.5));
+    }
+}
@@ -0,0 +1,426 @@
+use crate::{
+    app::use_app_props,
+    channel::RoomRequest,
+    estimate::{CardDeck, Estimate},
+    import::{self, ImportError},
+    integrations::SyncStatusBadge,
+    round::{self, RoundRecord},
+    story::{Story, StoryId, StoryQueue},
+};
+use dioxus::prelude::*;
+use serde::Deserialize;
+
+const INPUT_STYLE: &str = "bg-white border border-slate-300 rounded-full px-4 py-2 focus:outline-none focus:ring-2 focus:ring-slate-600";
+const ICON_BTN_STYLE: &str =
+    "px-2 py-1 rounded-full text-slate-400 hover:bg-slate-100 hover:text-slate-600";
+
+#[component]
+pub fn CurrentStory(stories: Signal<StoryQueue>) -> Element {
+    let stories = stories.read();
+    let Some(story) = stories.current() else {
+        return rsx! {};
+    };
+
+    rsx! {
+        div { class: "flex flex-col gap-y-1 px-6 py-4 bg-white rounded-lg shadow-md text-slate-600",
+            span { class: "text-xs uppercase text-gray-500",
+                "Now estimating"
+                if let Some(key) = &story.key {
+                    " · {key}"
+                }
+            }
+            if let Some(link) = &story.link {
+                a {
+                    class: "text-xl font-semibold hover:underline",
+                    href: "{link}",
+                    target: "_blank",
+                    rel: "noopener noreferrer",
+                    "{story.title}"
+                }
+            } else {
+                span { class: "text-xl font-semibold", "{story.title}" }
+            }
+            if let Some(description) = &story.description {
+                p { class: "text-sm whitespace-pre-line", "{description}" }
+            }
+        }
+    }
+}
+
+#[component]
+pub fn StoryQueuePanel(stories: Signal<StoryQueue>, is_facilitator: Memo<bool>) -> Element {
+    let app_props = use_app_props();
+    let queue = stories.read();
+    let count = queue.stories.len();
+
+    let send = move |request: RoomRequest| async move {
+        _ = app_props().channel.send(request).await;
+    };
+
+    rsx! {
+        div { class: "flex flex-col gap-y-4 text-slate-600",
+            div { class: "flex items-center justify-between",
+                h1 { class: "text-lg font-semibold", "Stories" }
+                if is_facilitator() {
+                    button {
+                        class: "rounded-full px-6 py-2 font-bold text-white bg-slate-600 hover:bg-slate-500",
+                        onclick: move |_| send(RoomRequest::NextStory(app_props().session_id)),
+                        "Next story"
+                    }
+                }
+            }
+            if count > 0 {
+                ol { class: "flex flex-col divide-y divide-gray-200 bg-white rounded-lg shadow-md",
+                    for (index , story) in queue.stories.iter().enumerate() {
+                        StoryItem {
+                            key: "{story.id}",
+                            story: story.clone(),
+                            index,
+                            count,
+                            current: queue.current.as_ref() == Some(&story.id),
+                            is_facilitator,
+                        }
+                    }
+                }
+            } else {
+                p { class: "text-sm text-gray-500", "No stories in the queue." }
+            }
+            if is_facilitator() {
+                AddStoryForm {}
+                ImportStoriesForm {}
+            }
+        }
+    }
+}
+
+#[component]
+fn StoryItem(
+    story: Story,
+    index: usize,
+    count: usize,
+    current: bool,
+    is_facilitator: Memo<bool>,
+) -> Element {
+    let app_props = use_app_props();
+    let story_id: StoryId = story.id.clone();
+
+    let send = move |request: RoomRequest| async move {
+        _ = app_props().channel.send(request).await;
+    };
+
+    rsx! {
+        li {
+            class: "flex items-center gap-x-2 px-4 py-2",
+            class: if current { "bg-slate-100 font-semibold" },
+            span { class: "w-6 text-gray-400", "{index + 1}." }
+            if let Some(key) = &story.key {
+                span { class: "text-gray-400 whitespace-nowrap", "{key}" }
+            }
+            span { class: "flex-1", "{story.title}" }
+            if is_facilitator() {
+                if !current {
+                    button {
+                        class: "{ICON_BTN_STYLE}",
+                        title: "Estimate now",
+                        onclick: {
+                            let story_id = story_id.clone();
+                            move |_| send(RoomRequest::SelectStory(app_props().session_id, story_id.clone()))
+                        },
+                        "▶"
+                    }
+                }
+                if index > 0 {
+                    button {
+                        class: "{ICON_BTN_STYLE}",
+                        title: "Move up",
+                        onclick: {
+                            let story_id = story_id.clone();
+                            move |_| send(RoomRequest::MoveStory(app_props().session_id, story_id.clone(), index - 1))
+                        },
+                        "↑"
+                    }
+                }
+                if index + 1 < count {
+                    button {
+                        class: "{ICON_BTN_STYLE}",
+                        title: "Move down",
+                        onclick: {
+                            let story_id = story_id.clone();
+                            move |_| send(RoomRequest::MoveStory(app_props().session_id, story_id.clone(), index + 1))
+                        },
+                        "↓"
+                    }
+                }
+                button {
+                    class: "{ICON_BTN_STYLE}",
+                    title: "Remove",
+                    onclick: {
+                        let story_id = story_id.clone();
+                        move |_| send(RoomRequest::RemoveStory(app_props().session_id, story_id.clone()))
+                    },
+                    "✕"
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn AddStoryForm() -> Element {
+    let app_props = use_app_props();
+    let mut title = use_signal(String::new);
+    let mut link = use_signal(String::new);
+    let mut description = use_signal(String::new);
+
+    rsx! {
+        div { class: "flex flex-wrap items-center gap-2",
+            input {
+                id: "storyTitleInput",
+                r#type: "text",
+                class: "flex-1 {INPUT_STYLE}",
+                placeholder: "Story title",
+                autocomplete: "off",
+                oninput: move |evt| title.set(evt.value()),
+            }
+            input {
+                id: "storyLinkInput",
+                r#type: "url",
+                class: "flex-1 {INPUT_STYLE}",
+                placeholder: "https://",
+                autocomplete: "off",
+                oninput: move |evt| link.set(evt.value()),
+            }
+            textarea {
+                id: "storyDescriptionInput",
+                class: "w-full bg-white border border-slate-300 rounded-lg px-4 py-2 focus:outline-none focus:ring-2 focus:ring-slate-600",
+                placeholder: "Description",
+                rows: "2",
+                oninput: move |evt| description.set(evt.value()),
+            }
+            button {
+                class: "rounded-full px-6 py-2 font-bold text-slate-600 bg-white border border-slate-300 hover:bg-slate-100",
+                onclick: move |_| {
+                    let story = Story::new(&title(), &link(), &description());
+                    async move {
+                        let Some(story) = story else {
+                            return;
+                        };
+                        _ = app_props()
+                            .channel
+                            .send(RoomRequest::AddStory(app_props().session_id, story))
+                            .await;
+                        title.set(String::new());
+                        link.set(String::new());
+                        description.set(String::new());
+                        _ = document::eval(
+                            r#"
+                            for (const id of ["storyTitleInput", "storyLinkInput", "storyDescriptionInput"]) {
+                                document.getElementById(id).value = "";
+                            }
+                            "#,
+                        );
+                    }
+                },
+                "Add story"
+            }
+        }
+    }
+}
+
+const MAX_IMPORT_FILE_SIZE: usize = 1024 * 1024;
+const MAX_SHOWN_IMPORT_ERRORS: usize = 10;
+
+#[derive(Deserialize)]
+struct UploadedFile {
+    name: String,
+    /// Missing when the file is larger than `MAX_IMPORT_FILE_SIZE`.
+    content: Option<String>,
+}
+
+/// Adds the stories of a CSV, Jira XML/CSV or GitHub issues JSON export to the queue.
+#[component]
+fn ImportStoriesForm() -> Element {
+    let app_props = use_app_props();
+    let mut imported = use_signal(|| None::<usize>);
+    let mut errors = use_signal(Vec::<ImportError>::new);
+
+    let read_file = format!(
+        r#"
+        const input = document.getElementById("storyImportInput");
+        const file = input.files[0];
+        input.value = "";
+        if (file) {{
+            const content = file.size <= {MAX_IMPORT_FILE_SIZE} ? await file.text() : null;
+            dioxus.send({{ name: file.name, content }});
+        }}
+        "#
+    );
+
+    rsx! {
+        div { class: "flex flex-col gap-y-2",
+            label { class: "flex items-center gap-x-2 text-sm",
+                span { class: "font-semibold", "Import stories" }
+                input {
+                    id: "storyImportInput",
+                    r#type: "file",
+                    class: "text-sm file:rounded-full file:px-4 file:py-2 file:border file:border-slate-300 file:bg-white file:text-slate-600 hover:file:bg-slate-100",
+                    accept: ".csv,.xml,.json",
+                    onchange: move |_| {
+                        let mut file_eval = document::eval(&read_file);
+                        async move {
+                            let Ok(file) = file_eval.recv::<UploadedFile>().await else {
+                                return;
+                            };
+                            let Some(content) = file.content else {
+                                imported.set(None);
+                                errors
+                                    .set(
+                                        vec![
+                                            ImportError {
+                                                row: None,
+                                                message: format!("{} is larger than 1 MB", file.name),
+                                            },
+                                        ],
+                                    );
+                                return;
+                            };
+                            let import = import::parse(&file.name, &content);
+                            imported.set(Some(import.stories.len()));
+                            errors.set(import.errors);
+                            if !import.stories.is_empty() {
+                                _ = app_props()
+                                    .channel
+                                    .send(RoomRequest::ImportStories(app_props().session_id, import.stories))
+                                    .await;
+                            }
+                        }
+                    },
+                }
+            }
+            if let Some(count) = imported() {
+                p { class: "text-sm text-gray-500", "Imported {count} stories." }
+            }
+            if !errors.read().is_empty() {
+                ul { class: "text-sm text-red-600",
+                    for error in errors.read().iter().take(MAX_SHOWN_IMPORT_ERRORS) {
+                        li { "{error}" }
+                    }
+                    if errors.read().len() > MAX_SHOWN_IMPORT_ERRORS {
+                        li { "and {errors.read().len() - MAX_SHOWN_IMPORT_ERRORS} more rows could not be imported." }
+                    }
+                }
+            }
+        }
+    }
+}
+
+const MAX_FINAL_ESTIMATE_LENGTH: usize = 10;
+
+#[component]
+pub fn FinalEstimatePicker(deck: Signal<CardDeck>) -> Element {
+    let app_props = use_app_props();
+    let mut typed_estimate = use_signal(String::new);
+
+    let finalize = move |estimate: Estimate| async move {
+        _ = app_props()
+            .channel
+            .send(RoomRequest::FinalizeEstimate(
+                app_props().session_id,
+                estimate,
+            ))
+            .await;
+    };
+
+    rsx! {
+        div { class: "flex flex-wrap items-center gap-2 text-slate-600",
+            span { class: "font-semibold", "Agreed estimate" }
+            for estimate in deck().cards().into_iter().filter(|e| matches!(e, Estimate::Card(_))) {
+                button {
+                    key: "{estimate}",
+                    class: "w-12 py-2 rounded-md bg-white shadow-md hover:bg-slate-100",
+                    onclick: {
+                        let estimate = estimate.clone();
+                        move |_| finalize(estimate.clone())
+                    },
+                    "{estimate}"
+                }
+            }
+            input {
+                r#type: "text",
+                class: "w-24 bg-white border border-slate-300 rounded-full px-4 py-2 focus:outline-none focus:ring-2 focus:ring-slate-600",
+                placeholder: "Other",
+                maxlength: "{MAX_FINAL_ESTIMATE_LENGTH}",
+                autocomplete: "off",
+                oninput: move |evt| typed_estimate.set(evt.value()),
+            }
+            button {
+                class: "rounded-full px-4 py-2 font-bold text-white bg-slate-600 hover:bg-slate-500",
+                onclick: move |_| {
+                    let typed: String = typed_estimate().chars().take(MAX_FINAL_ESTIMATE_LENGTH).collect();
+                    let estimate = Estimate::from(typed.as_str());
+                    async move {
+                        if estimate != Estimate::None {
+                            finalize(estimate).await;
+                        }
+                    }
+                },
+                "Save"
+            }
+        }
+    }
+}
+
+#[component]
+pub fn FinalizedStories(rounds: Signal<Vec<RoundRecord>>, is_facilitator: Memo<bool>) -> Element {
+    let rounds = rounds.read();
+    let finalized: Vec<(usize, &RoundRecord)> = rounds
+        .iter()
+        .enumerate()
+        .filter(|(_, round)| round.final_estimate.is_some())
+        .collect();
+    if finalized.is_empty() {
+        return rsx! {};
+    }
+    let total = round::total_points(&rounds);
+
+    rsx! {
+        div { class: "flex flex-col gap-y-4 text-slate-600",
+            h1 { class: "text-lg font-semibold", "Estimated" }
+            table { class: "w-full text-sm text-left bg-white rounded-lg shadow-md",
+                tbody {
+                    for (index , round) in finalized {
+                        tr { class: "border-b",
+                            td { class: "py-2 px-6",
+                                match &round.story {
+                                    Some(story) => rsx! { "{story.title}" },
+                                    None => rsx! { "Round {index + 1}" },
+                                }
+                            }
+                            td { class: "py-2 px-6 text-gray-500",
+                                if let Some(issue_key) = &round.issue_key {
+                                    "{issue_key}"
+                                }
+                            }
+                            td { class: "py-2 px-6",
+                                SyncStatusBadge { round: round.clone(), is_facilitator }
+                            }
+                            td { class: "py-2 px-6 text-center font-semibold",
+                                if let Some(estimate) = &round.final_estimate {
+                                    "{estimate}"
+                                }
+                            }
+                        }
+                    }
+                }
+                tfoot {
+                    tr { class: "font-bold",
+                        td { class: "py-2 px-6", "Total" }
+                        td {}
+                        td {}
+                        td { class: "py-2 px-6 text-center", "{total}" }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use crate::validate::{self, ALPHABET_AND_NUMBERS};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+pub type StoryId = Arc<str>;
+
+const MAX_TITLE_LENGTH: usize = 200;
+const MAX_DESCRIPTION_LENGTH: usize = 2000;
+
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct Story {
+    pub id: StoryId,
+    /// Issue key in the tracker the story was imported from, e.g. `PROJ-123` or `#42`.
+    #[serde(default)]
+    pub key: Option<Arc<str>>,
+    pub title: Arc<str>,
+    pub link: Option<Arc<str>>,
+    pub description: Option<Arc<str>>,
+}
+
+impl Story {
+    /// Creates a story from user input. Returns `None` when the title is blank.
+    pub fn new(title: &str, link: &str, description: &str) -> Option<Story> {
+        let title: String = title.trim().chars().take(MAX_TITLE_LENGTH).collect();
+        if title.is_empty() {
+            return None;
+        }
+        let link = link.trim();
+        let link =
+            (link.starts_with("https://") || link.starts_with("http://")).then(|| Arc::from(link));
+        let description: String = description
+            .trim()
+            .chars()
+            .take(MAX_DESCRIPTION_LENGTH)
+            .collect();
+        Some(Story {
+            id: Arc::from(nanoid::nanoid!(10, &ALPHABET_AND_NUMBERS)),
+            key: None,
+            title: Arc::from(title),
+            link,
+            description: (!description.is_empty()).then(|| Arc::from(description)),
+        })
+    }
+
+    /// Keys that are no valid [`validate::issue_key`] are dropped, they end up in tracker URLs.
+    pub fn with_key(mut self, key: &str) -> Story {
+        self.key = validate::issue_key(key);
+        self
+    }
+}
+
+/// Ordered agenda of the stories a session estimates.
+#[derive(PartialEq, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StoryQueue {
+    pub stories: Vec<Story>,
+    pub current: Option<StoryId>,
+}
+
+impl StoryQueue {
+    pub fn current(&self) -> Option<&Story> {
+        let current = self.current.as_ref()?;
+        self.stories.iter().find(|story| story.id == *current)
+    }
+
+    pub fn add(&mut self, story: Story) {
+        if self.current.is_none() {
+            self.current = Some(story.id.clone());
+        }
+        self.stories.push(story);
+    }
+
+    pub fn remove(&mut self, story_id: &StoryId) {
+        self.stories.retain(|story| story.id != *story_id);
+        if self.current.as_ref() == Some(story_id) {
+            self.current = None;
+        }
+    }
+
+    /// Moves the story to `index`, clamped to the end of the queue.
+    pub fn move_to(&mut self, story_id: &StoryId, index: usize) {
+        if let Some(position) = self.stories.iter().position(|s| s.id == *story_id) {
+            let story = self.stories.remove(position);
+            let index = index.min(self.stories.len());
+            self.stories.insert(index, story);
+        }
+    }
+
+    pub fn select(&mut self, story_id: &StoryId) {
+        if self.stories.iter().any(|story| story.id == *story_id) {
+            self.current = Some(story_id.clone());
+        }
+    }
+
+    /// Takes the current story off the queue and makes the following one current.
+    pub fn advance(&mut self) -> Option<Story> {
+        let position = self
+            .current
+            .as_ref()
+            .and_then(|current| self.stories.iter().position(|s| s.id == *current));
+        let finished = position.map(|position| self.stories.remove(position));
+        self.current = self
+            .stories
+            .get(position.unwrap_or(0))
+            .or(self.stories.first())
+            .map(|story| story.id.clone());
+        finished
+    }
+}
@@ -1,4 +1,10 @@
-use crate::{channel::EstimateVisibility, estimate::Estimate, room::Participant};
+use crate::{
+    app::use_app_props,
+    channel::{EstimateVisibility, RoomRequest},
+    estimate::{CardDeck, Estimate},
+    room::Participant,
+    statistics::{Consensus, RoundStatistics},
+};
 use dioxus::prelude::*;
 use itertools::Itertools;
 use std::collections::HashMap;
@@ -10,14 +16,20 @@ const LOGO_TRANS_PNG_PATH: &str = "/assets/logo_trans.png";
 pub fn Table(
     participants: Signal<HashMap<Uuid, Participant>>,
     estimate_visibility: Signal<EstimateVisibility>,
+    deck: Signal<CardDeck>,
+    is_facilitator: Memo<bool>,
 ) -> Element {
     let p = participants.read();
+    let deck = deck.read();
+    let (observers, voters): (Vec<_>, Vec<_>) = p.iter().partition(|x| x.1.observer);
     let participants: Vec<(&Uuid, &Participant)> = if estimate_visibility().is_visible() {
-        let sorted_vec: Vec<_> = p.iter().sorted_by_key(|x| x.1.estimate.clone()).collect();
+        let sorted_vec: Vec<_> = voters
+            .into_iter()
+            .sorted_by_key(|x| deck.position(&x.1.estimate))
+            .collect();
         sorted_vec
     } else {
-        let unsorted_vec: Vec<_> = p.iter().collect();
-        unsorted_vec
+        voters
     };
 
     rsx! {
@@ -31,7 +43,9 @@ pub fn Table(
             tbody { class: "text-lg",
                 for (_ , participant) in participants {
                     tr { class: "bg-gray-50  border-b",
-                        td { class: "py-3 px-6", "{participant.name}" }
+                        td { class: "py-3 px-6",
+                            ParticipantName { participant: participant.clone(), is_facilitator }
+                        }
                         td { class: "py-3 px-6 text-center",
                             EstimateResultCard {
                                 estimate: participant.estimate.clone(),
@@ -41,6 +55,110 @@ pub fn Table(
                     }
                 }
             }
+            if !observers.is_empty() {
+                thead { class: "text-base text-gray-700 uppercase bg-gray-100",
+                    tr {
+                        th { scope: "col", colspan: "2", class: "py-3 px-6", "Observers" }
+                    }
+                }
+                tbody { class: "text-lg",
+                    for (_ , participant) in observers {
+                        tr { class: "bg-gray-50  border-b",
+                            td { colspan: "2", class: "py-3 px-6",
+                                ParticipantName { participant: participant.clone(), is_facilitator }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+pub fn StatisticsPanel(statistics: RoundStatistics) -> Element {
+    let format_value = |value: Option<f64>| match value {
+        Some(value) => format!("{}", (value * 10.0).round() / 10.0),
+        None => "-".to_string(),
+    };
+    let format_card = |card: &Option<Estimate>| match card {
+        Some(card) => card.to_string(),
+        None => "-".to_string(),
+    };
+    let mode = if statistics.mode.is_empty() {
+        "-".to_string()
+    } else {
+        statistics.mode.iter().join(", ")
+    };
+    let spread = format!(
+        "{} – {}",
+        format_card(&statistics.min),
+        format_card(&statistics.max)
+    );
+    let (consensus, consensus_style) = match statistics.consensus {
+        Consensus::Unanimous => ("Consensus", "bg-green-100 text-green-700"),
+        Consensus::Close => ("Close", "bg-yellow-100 text-yellow-700"),
+        Consensus::Split => ("Split", "bg-red-100 text-red-700"),
+        Consensus::NoVotes => ("No votes", "bg-gray-100 text-gray-500"),
+    };
+    let items = [
+        ("Average", format_value(statistics.average)),
+        ("Median", format_value(statistics.median)),
+        ("Mode", mode),
+        ("Spread", spread),
+        (
+            "Votes",
+            format!("{} / {}", statistics.votes, statistics.voters),
+        ),
+        ("?", statistics.question_marks.to_string()),
+        ("☕️", statistics.coffees.to_string()),
+    ];
+
+    rsx! {
+        div { class: "flex flex-wrap items-center gap-3 text-slate-600",
+            span { class: "px-4 py-2 rounded-full text-sm font-bold uppercase {consensus_style}",
+                "{consensus}"
+            }
+            for (label , value) in items {
+                div { class: "flex flex-col items-center px-4 py-2 bg-white rounded-lg shadow-md",
+                    span { class: "text-xs uppercase text-gray-500", "{label}" }
+                    span { class: "text-lg font-semibold", "{value}" }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn ParticipantName(participant: Participant, is_facilitator: Memo<bool>) -> Element {
+    rsx! {
+        "{participant.name}"
+        if participant.is_facilitator() {
+            span { class: "ml-2 px-2 py-0.5 text-xs font-semibold uppercase rounded-full bg-slate-200 text-slate-600",
+                "Facilitator"
+            }
+        } else if is_facilitator() {
+            MakeFacilitatorButton { session_id: participant.session_id }
+        }
+    }
+}
+
+#[component]
+fn MakeFacilitatorButton(session_id: Uuid) -> Element {
+    let app_props = use_app_props();
+    rsx! {
+        button {
+            class: "ml-2 px-2 py-0.5 text-xs font-semibold uppercase rounded-full text-slate-400 hover:bg-slate-100 hover:text-slate-600",
+            title: "Make facilitator",
+            onclick: move |_| {
+                async move {
+                    _ = app_props()
+                        .channel
+                        .send(RoomRequest::TransferFacilitator(app_props().session_id, session_id))
+                        .await;
+                }
+            },
+            "Make facilitator"
         }
     }
 }
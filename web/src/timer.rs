@@ -0,0 +1,101 @@
+use crate::{app::use_app_props, channel::RoomRequest, room::RoomSettings};
+use dioxus::prelude::*;
+
+const TIMER_PRESETS: [u64; 2] = [30, 60];
+
+#[component]
+pub fn TimerControls(timer: Signal<Option<u64>>, settings: Signal<RoomSettings>) -> Element {
+    let app_props = use_app_props();
+    let mut custom_seconds = use_signal(String::new);
+
+    let start_timer = move |seconds: u64| async move {
+        _ = app_props()
+            .channel
+            .send(RoomRequest::StartTimer(app_props().session_id, seconds))
+            .await;
+    };
+
+    rsx! {
+        div { class: "flex flex-wrap items-center gap-x-4 gap-y-2 text-slate-600",
+            for seconds in TIMER_PRESETS {
+                button {
+                    class: "rounded-full px-4 py-2 font-bold text-slate-600 bg-white border border-slate-300 hover:bg-slate-100",
+                    onclick: move |_| start_timer(seconds),
+                    "{seconds}s"
+                }
+            }
+            input {
+                r#type: "number",
+                min: "1",
+                class: "w-24 bg-white border border-slate-300 rounded-full px-4 py-2 focus:outline-none focus:ring-2 focus:ring-slate-600",
+                placeholder: "Secs",
+                oninput: move |evt| custom_seconds.set(evt.value()),
+            }
+            button {
+                class: "rounded-full px-4 py-2 font-bold text-white bg-slate-600 hover:bg-slate-500",
+                onclick: move |_| {
+                    let seconds = custom_seconds().trim().parse::<u64>().ok();
+                    async move {
+                        if let Some(seconds) = seconds {
+                            start_timer(seconds).await;
+                        }
+                    }
+                },
+                "Start"
+            }
+            if timer().is_some_and(|remaining| remaining > 0) {
+                button {
+                    class: "rounded-full px-4 py-2 font-bold text-slate-600 bg-slate-50 hover:bg-slate-100",
+                    onclick: move |_| async move {
+                        _ = app_props()
+                            .channel
+                            .send(RoomRequest::StopTimer(app_props().session_id))
+                            .await;
+                    },
+                    "Stop"
+                }
+            }
+            label { class: "inline-flex items-center gap-x-2 cursor-pointer select-none",
+                input {
+                    r#type: "checkbox",
+                    class: "w-4 h-4 accent-slate-600",
+                    checked: settings().reveal_on_timer_expiry,
+                    onchange: move |evt| {
+                        let new_settings = RoomSettings {
+                            reveal_on_timer_expiry: evt.checked(),
+                            ..settings()
+                        };
+                        async move {
+                            _ = app_props()
+                                .channel
+                                .send(RoomRequest::ChangeSettings(app_props().session_id, new_settings))
+                                .await;
+                        }
+                    },
+                }
+                "Reveal when time is up"
+            }
+        }
+    }
+}
+
+#[component]
+pub fn RoundTimerDisplay(timer: Signal<Option<u64>>) -> Element {
+    match timer() {
+        Some(0) => rsx! {
+            span { class: "px-6 py-2 rounded-full text-2xl font-bold text-white bg-red-500",
+                "Time's up"
+            }
+        },
+        Some(remaining) => {
+            let minutes = remaining / 60;
+            let seconds = remaining % 60;
+            rsx! {
+                span { class: "px-6 py-2 rounded-full text-2xl font-bold tabular-nums text-slate-600 bg-white shadow-md",
+                    "{minutes}:{seconds:02}"
+                }
+            }
+        }
+        None => rsx! {},
+    }
+}
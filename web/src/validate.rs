@@ -1,6 +1,10 @@
 use std::sync::Arc;
 
-use crate::{room::RoomId, username};
+use crate::{estimate::Estimate, room::RoomId, username};
+
+const MAX_CUSTOM_CARDS: usize = 20;
+const MAX_CARD_LENGTH: usize = 5;
+const MAX_ISSUE_KEY_LENGTH: usize = 50;
 
 pub const ALPHABET_AND_NUMBERS: [char; 62] = [
     '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i',
@@ -36,3 +40,68 @@ pub fn room_id(room_id: RoomId) -> RoomId {
 
     return Arc::from(filtered_chars);
 }
+
+/// Parses a comma separated list of cards, dropping blanks and duplicates. Cards are compared
+/// as estimates, so spellings of the same special card end up once.
+pub fn custom_deck(cards: &str) -> Vec<Arc<str>> {
+    let mut deck: Vec<Estimate> = Vec::new();
+    for card in cards.split(',') {
+        let card: String = card.trim().chars().take(MAX_CARD_LENGTH).collect();
+        let estimate = Estimate::from(card.as_str());
+        if estimate == Estimate::None || deck.contains(&estimate) {
+            continue;
+        }
+        deck.push(estimate);
+    }
+    deck.truncate(MAX_CUSTOM_CARDS);
+    deck.into_iter().map(Arc::from).collect()
+}
+
+/// Keeps issue keys like `PROJ-123` usable as a path segment of the tracker's REST API.
+pub fn issue_key(issue_key: &str) -> Option<Arc<str>> {
+    let issue_key = issue_key.trim();
+    let valid = !issue_key.is_empty()
+        && issue_key.len() <= MAX_ISSUE_KEY_LENGTH
+        && issue_key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '#');
+    valid.then(|| Arc::from(issue_key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cards(deck: &[Arc<str>]) -> Vec<&str> {
+        deck.iter().map(|card| card.as_ref()).collect()
+    }
+
+    #[test]
+    fn custom_deck_drops_duplicate_estimates() {
+        let deck = custom_deck("1, 2, 1, ☕, ☕️, ?, ?");
+        assert_eq!(cards(&deck), ["1", "2", "☕️", "?"]);
+    }
+
+    #[test]
+    fn custom_deck_of_blanks_is_empty() {
+        assert!(custom_deck("").is_empty());
+        assert!(custom_deck(" , ,, ").is_empty());
+    }
+
+    #[test]
+    fn custom_deck_is_limited() {
+        let input = (1..=30)
+            .map(|n| n.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        let deck = custom_deck(&input);
+        assert_eq!(deck.len(), MAX_CUSTOM_CARDS);
+        assert_eq!(deck.last().map(|card| card.as_ref()), Some("20"));
+    }
+
+    #[test]
+    fn custom_deck_trims_cards() {
+        let deck = custom_deck("  XS ,M  , enormous");
+        assert_eq!(cards(&deck), ["XS", "M", "enorm"]);
+    }
+}
@@ -0,0 +1,413 @@
+use crate::{
+    api,
+    channel::RoomBroadcastMessage,
+    connector::{self, Retry},
+    error::ScError,
+    estimate::Estimate,
+    metrics,
+    room::{Participant, RoomId},
+    round::Vote,
+};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::{collections::HashMap, env, fmt, sync::Arc, time::Duration};
+use tokio::sync::{broadcast, mpsc, watch};
+use uuid::Uuid;
+
+const SIGNATURE_HEADER: &str = "X-Scrum-Poker-Signature";
+const EVENT_HEADER: &str = "X-Scrum-Poker-Event";
+
+/// Deliveries waiting for a slow receiver, later ones are dropped.
+const MAX_PENDING_DELIVERIES: usize = 256;
+
+/// Receivers get a few minutes to come back before a delivery is dropped.
+const DELIVERY_RETRY: Retry = Retry {
+    attempts: 6,
+    backoff: Duration::from_secs(5),
+};
+
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    ParticipantJoined,
+    ParticipantLeft,
+    Revealed,
+    Hidden,
+    EstimatesDeleted,
+}
+
+impl WebhookEvent {
+    pub const ALL: [WebhookEvent; 5] = [
+        WebhookEvent::ParticipantJoined,
+        WebhookEvent::ParticipantLeft,
+        WebhookEvent::Revealed,
+        WebhookEvent::Hidden,
+        WebhookEvent::EstimatesDeleted,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            WebhookEvent::ParticipantJoined => "participant_joined",
+            WebhookEvent::ParticipantLeft => "participant_left",
+            WebhookEvent::Revealed => "revealed",
+            WebhookEvent::Hidden => "hidden",
+            WebhookEvent::EstimatesDeleted => "estimates_deleted",
+        }
+    }
+}
+
+/// Endpoint that receives the selected events, signed with the shared secret.
+#[derive(PartialEq, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    pub url: Arc<str>,
+    pub secret: Arc<str>,
+    pub events: Vec<WebhookEvent>,
+}
+
+impl WebhookConfig {
+    pub fn new(url: &str, secret: &str, events: Vec<WebhookEvent>) -> Option<WebhookConfig> {
+        let url = url.trim();
+        let secret = secret.trim();
+        if connector::public_url(url).is_none() || secret.is_empty() || events.is_empty() {
+            return None;
+        }
+        Some(WebhookConfig {
+            url: Arc::from(url),
+            secret: Arc::from(secret),
+            events,
+        })
+    }
+
+    /// Webhook of every room from `WEBHOOK_URL`, `WEBHOOK_SECRET` and the comma separated
+    /// `WEBHOOK_EVENTS`, which defaults to all events.
+    pub fn from_env() -> Option<WebhookConfig> {
+        let var = |name: &str| env::var(name).unwrap_or_default();
+        let events = match var("WEBHOOK_EVENTS").trim() {
+            "" => WebhookEvent::ALL.to_vec(),
+            events => events
+                .split(',')
+                .filter_map(|name| {
+                    WebhookEvent::ALL
+                        .into_iter()
+                        .find(|event| event.name() == name.trim())
+                })
+                .collect(),
+        };
+        WebhookConfig::new(&var("WEBHOOK_URL"), &var("WEBHOOK_SECRET"), events)
+    }
+}
+
+impl fmt::Debug for WebhookConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebhookConfig")
+            .field("url", &self.url)
+            .field("secret", &"***")
+            .field("events", &self.events)
+            .finish()
+    }
+}
+
+/// Participant of a payload, by the public id of the REST API. Session ids are never sent.
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookParticipant {
+    pub id: Uuid,
+    pub name: Option<Arc<str>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookPayload {
+    pub event: WebhookEvent,
+    pub room_id: RoomId,
+    pub timestamp: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub participant: Option<WebhookParticipant>,
+    /// Votes of the round, sent with `revealed`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub votes: Option<Vec<Vote>>,
+}
+
+/// Derives webhook payloads from the broadcast messages of a room. It follows the participants
+/// through the messages so payloads can carry names and votes the messages themselves lack.
+#[derive(Default)]
+struct PayloadBuilder {
+    participants: HashMap<Uuid, Participant>,
+}
+
+impl PayloadBuilder {
+    /// Starts from the participants already in the room, e.g. restored from a snapshot.
+    fn new(participants: impl IntoIterator<Item = Participant>) -> Self {
+        PayloadBuilder {
+            participants: participants
+                .into_iter()
+                .map(|p| (p.session_id, p))
+                .collect(),
+        }
+    }
+
+    fn payload(&mut self, room_id: &RoomId, msg: &RoomBroadcastMessage) -> Option<WebhookPayload> {
+        let participant = |p: &Participant| WebhookParticipant {
+            id: api::participant_id(p.session_id),
+            name: Some(p.name.clone()),
+        };
+        let (event, participant, votes) = match msg {
+            RoomBroadcastMessage::Joined(p) => {
+                self.participants.insert(p.session_id, p.clone());
+                (WebhookEvent::ParticipantJoined, Some(participant(p)), None)
+            }
+            RoomBroadcastMessage::ParticipantUpdate(p) => {
+                self.participants.insert(p.session_id, p.clone());
+                return None;
+            }
+            RoomBroadcastMessage::Left(session_id) => {
+                let name = self.participants.remove(session_id).map(|p| p.name.clone());
+                let participant = WebhookParticipant {
+                    id: api::participant_id(*session_id),
+                    name,
+                };
+                (WebhookEvent::ParticipantLeft, Some(participant), None)
+            }
+            RoomBroadcastMessage::ChangedVisibility(visibility) if visibility.is_visible() => {
+                let mut votes: Vec<Vote> = self
+                    .participants
+                    .values()
+                    .filter(|p| !p.observer && p.estimate != Estimate::None)
+                    .map(|p| Vote {
+                        name: p.name.clone(),
+                        estimate: p.estimate.clone(),
+                    })
+                    .collect();
+                votes.sort_by(|a, b| a.name.cmp(&b.name));
+                (WebhookEvent::Revealed, None, Some(votes))
+            }
+            RoomBroadcastMessage::ChangedVisibility(_) => (WebhookEvent::Hidden, None, None),
+            RoomBroadcastMessage::EstimatesDeleted | RoomBroadcastMessage::DeckChanged(_) => {
+                for p in self.participants.values_mut() {
+                    p.estimate = Estimate::None;
+                }
+                if matches!(msg, RoomBroadcastMessage::DeckChanged(_)) {
+                    return None;
+                }
+                (WebhookEvent::EstimatesDeleted, None, None)
+            }
+            _ => return None,
+        };
+        Some(WebhookPayload {
+            event,
+            room_id: room_id.clone(),
+            timestamp: Utc::now(),
+            participant,
+            votes,
+        })
+    }
+}
+
+/// `sha256=` followed by the hex encoded HMAC-SHA256 of the body keyed with the secret.
+pub fn signature(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+struct Delivery {
+    config: WebhookConfig,
+    event: WebhookEvent,
+    body: Arc<[u8]>,
+}
+
+async fn deliver(http: &reqwest::Client, delivery: &Delivery) -> Result<(), ScError> {
+    let signature = signature(&delivery.config.secret, &delivery.body);
+    DELIVERY_RETRY
+        .send(|| {
+            http.post(delivery.config.url.as_ref())
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(SIGNATURE_HEADER, &signature)
+                .header(EVENT_HEADER, delivery.event.name())
+                .body(delivery.body.to_vec())
+        })
+        .await?;
+    Ok(())
+}
+
+/// Follows the broadcasts of a room and posts the selected events to the global webhook and to
+/// the one configured for the room, starting from the participants already in it. Deliveries
+/// run in order on a separate task, so a slow receiver delays later deliveries but never the
+/// room, and deliveries beyond [`MAX_PENDING_DELIVERIES`] are dropped.
+pub fn spawn_worker(
+    room_id: RoomId,
+    mut broadcast: broadcast::Receiver<RoomBroadcastMessage>,
+    room_webhook: watch::Receiver<Option<WebhookConfig>>,
+    participants: Vec<Participant>,
+) {
+    let global_webhook = WebhookConfig::from_env();
+    let (delivery_tx, mut delivery_rx) = mpsc::channel::<Delivery>(MAX_PENDING_DELIVERIES);
+
+    tokio::spawn(async move {
+        let http = connector::http_client();
+        while let Some(delivery) = delivery_rx.recv().await {
+            if let Err(err) = deliver(&http, &delivery).await {
+                tracing::warn!(
+                    "Dropped {} webhook to {}, error: {}",
+                    delivery.event.name(),
+                    delivery.config.url,
+                    err
+                );
+            }
+        }
+    });
+
+    tokio::spawn(async move {
+        let mut builder = PayloadBuilder::new(participants);
+        loop {
+            let msg = match broadcast.recv().await {
+                Ok(msg) => msg,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    metrics::BROADCAST_LAGS
+                        .with_label_values(&["webhook"])
+                        .inc();
+                    tracing::warn!(
+                        "Webhook worker of room {} skipped {} messages",
+                        room_id,
+                        skipped
+                    );
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            let Some(payload) = builder.payload(&room_id, &msg) else {
+                continue;
+            };
+            let webhooks: Vec<WebhookConfig> = global_webhook
+                .iter()
+                .chain(room_webhook.borrow().iter())
+                .filter(|config| config.events.contains(&payload.event))
+                .cloned()
+                .collect();
+            if webhooks.is_empty() {
+                continue;
+            }
+            let body: Arc<[u8]> = match serde_json::to_vec(&payload) {
+                Ok(body) => Arc::from(body),
+                Err(err) => {
+                    tracing::error!("Failed to serialize webhook payload, error: {}", err);
+                    continue;
+                }
+            };
+            for config in webhooks {
+                let delivery = Delivery {
+                    config,
+                    event: payload.event,
+                    body: body.clone(),
+                };
+                if let Err(mpsc::error::TrySendError::Full(delivery)) =
+                    delivery_tx.try_send(delivery)
+                {
+                    tracing::warn!(
+                        "Dropped {} webhook of room {}, {} deliveries are pending",
+                        delivery.event.name(),
+                        room_id,
+                        MAX_PENDING_DELIVERIES
+                    );
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channel::EstimateVisibility;
+
+    #[test]
+    fn signs_body_with_hmac_sha256() {
+        assert_eq!(
+            signature("key", b"The quick brown fox jumps over the lazy dog"),
+            "sha256=f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+
+    #[test]
+    fn payloads_follow_participants() {
+        let room_id: RoomId = Arc::from("room1");
+        let mut builder = PayloadBuilder::default();
+        let mut alice = Participant::new(Uuid::from_u128(1), Arc::from("Alice"));
+
+        let joined = builder
+            .payload(&room_id, &RoomBroadcastMessage::Joined(alice.clone()))
+            .unwrap();
+        assert_eq!(joined.event, WebhookEvent::ParticipantJoined);
+
+        alice.estimate = Estimate::from("5");
+        let update = RoomBroadcastMessage::ParticipantUpdate(alice.clone());
+        assert!(builder.payload(&room_id, &update).is_none());
+
+        let revealed = RoomBroadcastMessage::ChangedVisibility(EstimateVisibility::Visible);
+        let revealed = builder.payload(&room_id, &revealed).unwrap();
+        assert_eq!(revealed.event, WebhookEvent::Revealed);
+        assert_eq!(
+            revealed.votes.unwrap(),
+            vec![Vote {
+                name: Arc::from("Alice"),
+                estimate: Estimate::from("5")
+            }]
+        );
+
+        let left = builder
+            .payload(&room_id, &RoomBroadcastMessage::Left(alice.session_id))
+            .unwrap();
+        assert_eq!(left.event, WebhookEvent::ParticipantLeft);
+        assert_eq!(left.participant.unwrap().name.as_deref(), Some("Alice"));
+    }
+
+    #[test]
+    fn payloads_never_carry_session_ids() {
+        let room_id: RoomId = Arc::from("room1");
+        let mut builder = PayloadBuilder::default();
+        let alice = Participant::new(Uuid::new_v4(), Arc::from("Alice"));
+
+        let joined = builder
+            .payload(&room_id, &RoomBroadcastMessage::Joined(alice.clone()))
+            .unwrap();
+        let left = builder
+            .payload(&room_id, &RoomBroadcastMessage::Left(alice.session_id))
+            .unwrap();
+        for payload in [joined, left] {
+            let body = serde_json::to_string(&payload).unwrap();
+            assert!(!body.contains(&alice.session_id.to_string()), "{body}");
+            assert!(
+                !body.contains(&alice.session_id.simple().to_string()),
+                "{body}"
+            );
+            assert_eq!(
+                payload.participant.unwrap().id,
+                api::participant_id(alice.session_id)
+            );
+        }
+    }
+
+    #[test]
+    fn restored_participants_vote() {
+        let room_id: RoomId = Arc::from("room1");
+        let mut alice = Participant::new(Uuid::new_v4(), Arc::from("Alice"));
+        alice.estimate = Estimate::from("3");
+        let mut builder = PayloadBuilder::new([alice.clone()]);
+
+        let revealed = RoomBroadcastMessage::ChangedVisibility(EstimateVisibility::Visible);
+        let revealed = builder.payload(&room_id, &revealed).unwrap();
+        assert_eq!(
+            revealed.votes.unwrap(),
+            vec![Vote {
+                name: Arc::from("Alice"),
+                estimate: Estimate::from("3")
+            }]
+        );
+
+        let left = builder
+            .payload(&room_id, &RoomBroadcastMessage::Left(alice.session_id))
+            .unwrap();
+        assert_eq!(left.participant.unwrap().name, Some(Arc::from("Alice")));
+    }
+}